@@ -0,0 +1,377 @@
+//! Async front end for `GraviexClient`, enabled with the `async` feature.
+//!
+//! This is a thread pool adapter, not an async transport: every call runs
+//! the blocking client on tokio's blocking thread pool, so signing, tonces
+//! and error handling are exactly the ones `graviex_handler` uses. Each
+//! request in flight holds one of the pool's threads until graviex answers,
+//! so fanning out 50 calls ties up 50 threads, and calls past the pool's
+//! limit (`tokio::runtime::Builder::max_blocking_threads`, 512 unless set)
+//! wait for one to free up. Keep fan-outs to what the pool can spare.
+//!
+//! A call whose thread panics passes the panic on. One still waiting for a
+//! thread when the runtime shuts down fails with `GraviexError::Transport`
+//! holding an `Interrupted` io error.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+
+use crate::*;
+
+/// An async graviex api v3 client, cheap to clone and share between tasks.
+#[derive(Debug, Clone)]
+pub struct AsyncGraviexClient {
+    inner: Arc<GraviexClient>,
+}
+
+impl AsyncGraviexClient {
+    #[must_use]
+    pub fn new(client: GraviexClient) -> AsyncGraviexClient {
+        AsyncGraviexClient { inner: Arc::new(client) }
+    }
+
+    /// The blocking client behind this one.
+    #[must_use]
+    pub fn blocking(&self) -> &GraviexClient {
+        &self.inner
+    }
+
+    // start f on the blocking pool without waiting for it
+    fn spawn<T, F>(&self, f: F) -> JoinHandle<Result<T, GraviexError>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&GraviexClient) -> Result<T, GraviexError> + Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        tokio::task::spawn_blocking(move || f(&inner))
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, GraviexError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&GraviexClient) -> Result<T, GraviexError> + Send + 'static,
+    {
+        join(self.spawn(f)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn resync_tonce(&self) -> Result<(), GraviexError> {
+        self.run(GraviexClient::resync_tonce).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn markets(&self) -> Result<Vec<MarketList>, GraviexError> {
+        self.run(GraviexClient::markets).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn market(&self, m: &str) -> Result<Market, GraviexError> {
+        let m = m.to_string();
+        self.run(move |client| client.market(&m)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn tickers(&self) -> Result<HashMap<String, Ticker>, GraviexError> {
+        self.run(GraviexClient::tickers).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn ticker(&self, t: &str) -> Result<Ticker, GraviexError> {
+        let t = t.to_string();
+        self.run(move |client| client.ticker(&t)).await
+    }
+
+    /// Tickers for every market in `markets`, fetched concurrently.
+    /// Results come back in the same order as `markets`. Each market holds
+    /// a blocking pool thread while its request is in flight.
+    pub async fn ticker_each(&self, markets: &[&str]) -> Vec<Result<Ticker, GraviexError>> {
+        let handles: Vec<_> = markets
+            .iter()
+            .map(|m| {
+                let m = m.to_string();
+                self.spawn(move |client| client.ticker(&m))
+            })
+            .collect();
+        join_each(handles).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn me(&self) -> Result<Member, GraviexError> {
+        self.run(GraviexClient::me).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn register_device(&self, device_id: &str) -> Result<serde_json::Value, GraviexError> {
+        let device_id = device_id.to_string();
+        self.run(move |client| client.register_device(&device_id)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.history(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn deposits(&self, query: &DepositsQuery) -> Result<Vec<Deposit>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.deposits(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn deposit(&self, txid: &str) -> Result<Deposit, GraviexError> {
+        let txid = txid.to_string();
+        self.run(move |client| client.deposit(&txid)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn deposit_address(&self, currency: &str) -> Result<DepositAddress, GraviexError> {
+        let currency = currency.to_string();
+        self.run(move |client| client.deposit_address(&currency)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn gen_deposit_address(&self, currency: &str) -> Result<DepositAddress, GraviexError> {
+        let currency = currency.to_string();
+        self.run(move |client| client.gen_deposit_address(&currency)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn orders_get(&self, query: &OrdersQuery) -> Result<Vec<Order>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.orders_get(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn orders_post(&self, order: &NewOrder) -> Result<Order, GraviexError> {
+        let order = order.clone();
+        self.run(move |client| client.orders_post(&order)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn orders_history(&self, query: &OrdersHistoryQuery) -> Result<Vec<Order>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.orders_history(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn orders_multi(&self, orders: &MultiOrder) -> Result<Vec<Order>, GraviexError> {
+        let orders = orders.clone();
+        self.run(move |client| client.orders_multi(&orders)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn orders_clear(&self, side: Side) -> Result<Vec<Order>, GraviexError> {
+        self.run(move |client| client.orders_clear(side)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn order(&self, order_id: usize) -> Result<Order, GraviexError> {
+        self.run(move |client| client.order(order_id)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn order_delete(&self, order_id: usize) -> Result<Order, GraviexError> {
+        self.run(move |client| client.order_delete(order_id)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn order_book(&self, query: &OrderBookQuery) -> Result<OrderBook, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.order_book(&query)).await
+    }
+
+    /// Order books for every query in `queries`, fetched concurrently.
+    /// Results come back in the same order as `queries`. Each query holds
+    /// a blocking pool thread while its request is in flight.
+    pub async fn order_book_each(&self, queries: &[OrderBookQuery]) -> Vec<Result<OrderBook, GraviexError>> {
+        let handles: Vec<_> = queries
+            .iter()
+            .cloned()
+            .map(|query| self.spawn(move |client| client.order_book(&query)))
+            .collect();
+        join_each(handles).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn depth(&self, query: &DepthQuery) -> Result<Depth, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.depth(&query)).await
+    }
+
+    /// Depth for every query in `queries`, fetched concurrently.
+    /// Results come back in the same order as `queries`. Each query holds
+    /// a blocking pool thread while its request is in flight.
+    pub async fn depth_each(&self, queries: &[DepthQuery]) -> Vec<Result<Depth, GraviexError>> {
+        let handles: Vec<_> = queries
+            .iter()
+            .cloned()
+            .map(|query| self.spawn(move |client| client.depth(&query)))
+            .collect();
+        join_each(handles).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn trades(&self, query: &TradesQuery) -> Result<Vec<Trade>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.trades(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn trades_my(&self, query: &TradesQuery) -> Result<Vec<Trade>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.trades_my(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn trades_history(&self, query: &TradesHistoryQuery) -> Result<Vec<Trade>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.trades_history(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn trades_simple(&self, market: &str) -> Result<Vec<SimpleTrade>, GraviexError> {
+        let market = market.to_string();
+        self.run(move |client| client.trades_simple(&market)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn kline(&self, query: &KlineQuery) -> Result<Vec<Candle>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.kline(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn kline_pending(&self, query: &KlinePendingQuery) -> Result<KlineWithPending, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.kline_pending(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn timestamp(&self) -> Result<usize, GraviexError> {
+        self.run(GraviexClient::timestamp).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn settings_get(&self) -> Result<Settings, GraviexError> {
+        self.run(GraviexClient::settings_get).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn settings_store(&self, settings: &Settings) -> Result<Settings, GraviexError> {
+        let settings = settings.clone();
+        self.run(move |client| client.settings_store(&settings)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn currency_info(&self, coin: &str) -> Result<CurrencyInfo, GraviexError> {
+        let coin = coin.to_string();
+        self.run(move |client| client.currency_info(&coin)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn withdraws(&self, query: &WithdrawsQuery) -> Result<Vec<Withdraw>, GraviexError> {
+        let query = query.clone();
+        self.run(move |client| client.withdraws(&query)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn create_withdraw(&self, withdraw: &NewWithdraw) -> Result<Withdraw, GraviexError> {
+        let withdraw = withdraw.clone();
+        self.run(move |client| client.create_withdraw(&withdraw)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn fund_sources(&self, currency: &str) -> Result<Vec<FundSource>, GraviexError> {
+        let currency = currency.to_string();
+        self.run(move |client| client.fund_sources(&currency)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn fund_source_create(&self, source: &NewFundSource) -> Result<FundSource, GraviexError> {
+        let source = source.clone();
+        self.run(move |client| client.fund_source_create(&source)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn fund_source_remove(&self, source_id: usize) -> Result<FundSource, GraviexError> {
+        self.run(move |client| client.fund_source_remove(source_id)).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn strategies_list(&self) -> Result<Vec<Strategy>, GraviexError> {
+        self.run(GraviexClient::strategies_list).await
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub async fn strategies_list_my(&self) -> Result<Vec<Strategy>, GraviexError> {
+        self.run(GraviexClient::strategies_list_my).await
+    }
+}
+
+impl From<GraviexClient> for AsyncGraviexClient {
+    fn from(client: GraviexClient) -> AsyncGraviexClient {
+        AsyncGraviexClient::new(client)
+    }
+}
+
+// wait for a blocking request, passing its panic on to the caller; it is
+// only cancelled if the runtime shuts down first
+async fn join<T>(handle: JoinHandle<Result<T, GraviexError>>) -> Result<T, GraviexError> {
+    match handle.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(GraviexError::Transport(minreq::Error::IoError(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "request task was cancelled",
+        )))),
+    }
+}
+
+async fn join_each<T>(handles: Vec<JoinHandle<Result<T, GraviexError>>>) -> Vec<Result<T, GraviexError>> {
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(join(handle).await);
+    }
+    results
+}
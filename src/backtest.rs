@@ -0,0 +1,925 @@
+//! Replaying recorded market history through a `Strategy`, to see how it
+//! would have done without risking any funds.
+//!
+//! Strategies get the same hooks and `Context` as live. Their orders fill
+//! against the replayed trades, or the candles of markets recorded without
+//! trades, and against depth snapshots. Fees, precision and minimums come
+//! from each market's recorded `Ticker`, like graviex applies them.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use rust_decimal::MathematicalOps;
+use serde::{Deserialize, Serialize};
+
+use crate::resample::{Tick, Timeframe};
+use crate::strategy::{Context, Strategy};
+use crate::time::format_timestamp;
+use crate::*;
+
+/// A run of one market's k lines.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Series {
+    pub market: String, // like "ethbtc"
+    pub period: Period,
+    pub candles: Vec<Candle>,
+}
+
+/// One market's order book as it stood at `depth.timestamp`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    pub market: String, // like "ethbtc"
+    pub depth: Depth,
+}
+
+/// Recorded market history to replay, kept as json between runs.
+///
+/// Only markets with a ticker can be traded, the ticker says what the
+/// market charges and accepts. The rest only feed the strategy's hooks.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Dataset {
+    pub tickers: BTreeMap<String, Ticker>, // by market
+    pub candles: Vec<Series>,
+    pub trades: Vec<Trade>, // of any market, in any order
+    pub depth: Vec<Snapshot>,
+}
+
+impl Dataset {
+    #[must_use]
+    pub fn new() -> Dataset {
+        Dataset::default()
+    }
+
+    /// Trade `market` by the fees and rules of `ticker`, as from `ticker(market)`.
+    #[must_use]
+    pub fn ticker(mut self, market: &str, ticker: Ticker) -> Dataset {
+        self.tickers.insert(market.to_string(), ticker);
+        self
+    }
+
+    #[must_use]
+    pub fn candles(mut self, market: &str, period: Period, candles: Vec<Candle>) -> Dataset {
+        self.candles.push(Series { market: market.to_string(), period, candles });
+        self
+    }
+
+    /// Public trades, as from `trades` or `trades_history`. Trades without
+    /// a readable `created_at` are left out of the replay.
+    #[must_use]
+    pub fn trades(mut self, trades: Vec<Trade>) -> Dataset {
+        self.trades.extend(trades);
+        self
+    }
+
+    /// A `depth` snapshot of `market`.
+    #[must_use]
+    pub fn depth(mut self, market: &str, depth: Depth) -> Dataset {
+        self.depth.push(Snapshot { market: market.to_string(), depth });
+        self
+    }
+
+    /// Read a dataset saved with `save`.
+    ///
+    /// # Errors
+    /// returns `DatasetError` if the file can't be read or isn't a dataset
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Dataset, DatasetError> {
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(DatasetError::Corrupt)
+    }
+
+    /// # Errors
+    /// returns `DatasetError::Io` if the file can't be written
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), DatasetError> {
+        let json = serde_json::to_string(self).map_err(DatasetError::Corrupt)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    // everything with a time, oldest first. candles come once they close,
+    // so nothing is seen before it could have been
+    fn events(&self) -> Vec<(usize, Event<'_>)> {
+        let mut events: Vec<(usize, Event<'_>)> = Vec::new();
+        events.extend(self.depth.iter().map(|snapshot| (snapshot.depth.timestamp, Event::Depth(snapshot))));
+        events.extend(self.trades.iter().filter_map(|trade| Some((trade.at()?, Event::Trade(trade)))));
+        for series in &self.candles {
+            let period = series.period.seconds();
+            events.extend(series.candles.iter().map(|candle| (candle.timestamp + period, Event::Candle(series, candle))));
+        }
+        events.sort_by_key(|(at, event)| (*at, event.order()));
+        events
+    }
+}
+
+#[derive(Debug)]
+pub enum DatasetError {
+    /// The file could not be read or written.
+    Io(io::Error),
+    /// The file is not a dataset.
+    Corrupt(serde_json::Error),
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io(e) => write!(f, "dataset io error: {}", e),
+            DatasetError::Corrupt(e) => write!(f, "dataset file is corrupt: {}", e),
+        }
+    }
+}
+
+impl Error for DatasetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatasetError::Io(e) => Some(e),
+            DatasetError::Corrupt(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for DatasetError {
+    fn from(e: io::Error) -> DatasetError {
+        DatasetError::Io(e)
+    }
+}
+
+// one thing replayed
+enum Event<'a> {
+    Depth(&'a Snapshot),
+    Trade(&'a Trade),
+    Candle(&'a Series, &'a Candle),
+}
+
+impl Event<'_> {
+    // of things at the same second: books first, then trades by id, then bars
+    fn order(&self) -> (u8, usize) {
+        match self {
+            Event::Depth(_) => (0, 0),
+            Event::Trade(trade) => (1, trade.id),
+            Event::Candle(..) => (2, 0),
+        }
+    }
+}
+
+/// One fill of one of the strategy's orders, a line of the trade log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub order_id: usize,
+    pub market: String, // like "ethbtc"
+    pub side: Side,
+    pub price: Decimal,
+    pub volume: Decimal, // in base units
+    pub fee: Amount, // kept out of what the fill paid, base for buys and quote for sells
+    pub maker: bool, // whether the order was resting, rather than taking on arrival
+    pub at: usize, // unix timestamp
+}
+
+/// Base bought and sold again, the unit `Report::win_rate` counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTrip {
+    pub market: String, // like "ethbtc"
+    pub opened: usize, // when the first of it was bought
+    pub closed: usize, // when it was sold
+    pub volume: Decimal, // in base units
+    pub cost: Decimal, // quote spent buying it
+    pub proceeds: Decimal, // quote received selling it, after the fee
+}
+
+impl RoundTrip {
+    #[must_use]
+    pub fn pnl(&self) -> Decimal {
+        self.proceeds - self.cost
+    }
+}
+
+/// How a strategy did over a `Backtest`.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub currency: String, // every value is in, like "btc"
+    pub start: Decimal, // the starting balances at the first prices replayed
+    pub end: Decimal, // the final balances at the last prices replayed
+    pub pnl: Decimal, // `end` less `start`
+    pub max_drawdown: Decimal, // deepest fall from a high, as a fraction of the high
+    pub sharpe: Option<Decimal>, // annualized, from the returns of each sample period
+    pub win_rate: Option<Decimal>, // fraction of round trips that made money
+    pub equity: Vec<(usize, Decimal)>, // value at the end of each sample period, by its start
+    pub fills: Vec<Fill>, // oldest first
+    pub round_trips: Vec<RoundTrip>, // buys matched to later sells first in, first out
+    pub accounts: Vec<Account>, // as the replay ended
+}
+
+impl Report {
+    /// Fees paid, by currency.
+    #[must_use]
+    pub fn fees(&self) -> BTreeMap<String, Decimal> {
+        let mut fees = BTreeMap::new();
+        for fill in &self.fills {
+            *fees.entry(fill.fee.currency.clone()).or_insert(Decimal::ZERO) += fill.fee.value;
+        }
+        fees
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |fraction: Decimal| (fraction * Decimal::ONE_HUNDRED).round_dp(2);
+        writeln!(f, "pnl {} {} ({} to {})", self.pnl.round_dp(8), self.currency, self.start.round_dp(8), self.end.round_dp(8))?;
+        writeln!(f, "max drawdown {}%", percent(self.max_drawdown))?;
+        match self.sharpe {
+            Some(sharpe) => writeln!(f, "sharpe {}", sharpe.round_dp(2))?,
+            None => writeln!(f, "sharpe n/a")?,
+        }
+        match self.win_rate {
+            Some(rate) => writeln!(f, "win rate {}% of {} round trips", percent(rate), self.round_trips.len())?,
+            None => writeln!(f, "no round trips")?,
+        }
+        write!(f, "{} fills", self.fills.len())
+    }
+}
+
+/// Replays a `Dataset` through a strategy, starting from the balances
+/// given, and reports how it did.
+///
+/// Orders fill at their own price once a replayed trade reaches it, or a
+/// candle trades through it, up to the trade's or candle's volume. Candles
+/// only fill orders placed before they opened, and only on markets
+/// recorded without trades, from their shortest period. Orders that cross
+/// the book fill against its levels, or in full at the last price when the
+/// market has no depth. Market orders take what they can and cancel the rest.
+///
+/// `on_ticker` follows every trade and candle of markets with a ticker, and
+/// `on_account` every change to the balances.
+#[derive(Debug)]
+pub struct Backtest {
+    data: Dataset,
+    balances: BTreeMap<String, Decimal>,
+    timer: Option<usize>, // seconds between `on_timer`
+    sample: Timeframe,
+    currency: Option<String>,
+}
+
+impl Backtest {
+    /// A backtest over `data`, starting with nothing, sampling returns daily.
+    #[must_use]
+    pub fn new(data: Dataset) -> Backtest {
+        Backtest { data, balances: BTreeMap::new(), timer: None, sample: Timeframe::days(1), currency: None }
+    }
+
+    /// Start with `amount` of `currency`, like "btc".
+    #[must_use]
+    pub fn balance(mut self, currency: &str, amount: Decimal) -> Backtest {
+        self.balances.insert(currency.to_string(), amount);
+        self
+    }
+
+    /// Call `on_timer` every `interval` of replayed time.
+    #[must_use]
+    pub fn timer(mut self, interval: Duration) -> Backtest {
+        self.timer = Some(usize::try_from(interval.as_secs()).unwrap_or(usize::MAX).max(1));
+        self
+    }
+
+    /// Sample the value every `sample` for the equity curve and the sharpe ratio.
+    #[must_use]
+    pub fn sample(mut self, sample: Timeframe) -> Backtest {
+        self.sample = sample;
+        self
+    }
+
+    /// Value everything in `currency`, by default the quote currency of
+    /// the first market with a ticker.
+    #[must_use]
+    pub fn value_in(mut self, currency: &str) -> Backtest {
+        self.currency = Some(currency.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn data(&self) -> &Dataset {
+        &self.data
+    }
+
+    /// Run `strategy` over the whole dataset, or until it stops.
+    pub fn run<S: Strategy + ?Sized>(&self, strategy: &mut S) -> Report {
+        let currency = self.currency.clone().unwrap_or_else(|| {
+            self.data.tickers.values().next().map_or_else(|| "btc".to_string(), |ticker| ticker.quote_unit.clone())
+        });
+        let events = self.data.events();
+        let mut sim = Sim::new(&self.data, &self.balances, &events);
+        let start = sim.value(&currency);
+
+        let mut equity = Equity::new(self.sample, start);
+        let mut timer = events.first().map(|(at, _)| *at);
+        sim.now = timer.unwrap_or_default();
+        sim.hook(strategy, |strategy, ctx| strategy.on_start(ctx));
+        sim.report_accounts(strategy);
+
+        for (at, event) in &events {
+            if sim.stopped {
+                break;
+            }
+            if let Some(interval) = self.timer {
+                while let Some(due) = timer.filter(|due| due <= at && !sim.stopped) {
+                    sim.now = due;
+                    sim.hook(strategy, |strategy, ctx| strategy.on_timer(ctx));
+                    timer = Some(due + interval);
+                }
+            }
+            sim.now = *at;
+            sim.replay(strategy, event);
+            sim.report_accounts(strategy);
+            equity.record(*at, sim.value(&currency));
+        }
+        strategy.on_stop(&mut sim);
+        // what on_stop's cancels led to, for a strategy that stopped too
+        while let Some(order) = sim.updates.pop_front() {
+            strategy.on_order_update(&mut sim, &order);
+        }
+
+        let end = sim.value(&currency);
+        equity.record(sim.now, end);
+        let wins = sim.round_trips.iter().filter(|trip| trip.pnl() > Decimal::ZERO).count();
+        Report {
+            currency,
+            start,
+            end,
+            pnl: end - start,
+            max_drawdown: equity.max_drawdown,
+            sharpe: equity.sharpe(),
+            win_rate: (!sim.round_trips.is_empty())
+                .then(|| Decimal::from(wins) / Decimal::from(sim.round_trips.len())),
+            equity: equity.finish(),
+            fills: sim.fills,
+            round_trips: sim.round_trips,
+            accounts: sim.accounts.into_values().collect(),
+        }
+    }
+}
+
+// the value over time, sampled once per period
+struct Equity {
+    sample: Timeframe,
+    start: Decimal,
+    period: Option<(usize, Decimal)>, // start of the current period, value so far
+    samples: Vec<(usize, Decimal)>,
+    peak: Decimal,
+    max_drawdown: Decimal,
+}
+
+impl Equity {
+    fn new(sample: Timeframe, start: Decimal) -> Equity {
+        Equity { sample, start, period: None, samples: Vec::new(), peak: start, max_drawdown: Decimal::ZERO }
+    }
+
+    fn record(&mut self, at: usize, value: Decimal) {
+        let current = self.sample.start_of(at);
+        if let Some((mut period, last)) = self.period {
+            // periods without anything replayed kept their value
+            while period < current {
+                self.samples.push((period, last));
+                period += self.sample.seconds;
+            }
+        }
+        self.period = Some((current, value));
+        self.peak = self.peak.max(value);
+        if self.peak > Decimal::ZERO {
+            self.max_drawdown = self.max_drawdown.max((self.peak - value) / self.peak);
+        }
+    }
+
+    fn finish(mut self) -> Vec<(usize, Decimal)> {
+        self.samples.extend(self.period.take());
+        self.samples
+    }
+
+    // mean over standard deviation of the returns per period, scaled to a year
+    fn sharpe(&self) -> Option<Decimal> {
+        let values: Vec<Decimal> =
+            std::iter::once(self.start).chain(self.samples.iter().chain(&self.period).map(|(_, value)| *value)).collect();
+        let returns: Vec<Decimal> = values
+            .windows(2)
+            .filter(|pair| !pair[0].is_zero())
+            .map(|pair| pair[1] / pair[0] - Decimal::ONE)
+            .collect();
+        if returns.len() < 2 {
+            return None;
+        }
+        let count = Decimal::from(returns.len());
+        let mean = returns.iter().sum::<Decimal>() / count;
+        let variance = returns.iter().map(|r| (r - mean) * (r - mean)).sum::<Decimal>() / (count - Decimal::ONE);
+        let deviation = variance.sqrt()?;
+        if deviation.is_zero() {
+            return None;
+        }
+        let per_year = Decimal::from(365 * 86400) / Decimal::from(self.sample.seconds);
+        Some(mean / deviation * per_year.sqrt()?)
+    }
+}
+
+// an open order and what filling it needs
+#[derive(Debug, Clone)]
+struct Resting {
+    order: Order,
+    side: Side,
+    limit: Option<Decimal>, // none for market orders
+    placed: usize,
+    funds: Decimal, // price * volume of the fills so far
+}
+
+// base bought and not sold yet
+#[derive(Debug)]
+struct Lot {
+    at: usize,
+    volume: Decimal,
+    cost: Decimal, // in quote
+}
+
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
+fn api_error(code: i64, message: &str) -> GraviexError {
+    GraviexError::Api { code, message: message.to_string() }
+}
+
+// the exchange as the strategy sees it during a backtest
+struct Sim<'a> {
+    now: usize,
+    templates: &'a BTreeMap<String, Ticker>, // as recorded
+    rules: BTreeMap<String, MarketRules>,
+    fees: BTreeMap<String, FeeSchedule>,
+    first: HashMap<String, Decimal>, // first price replayed, to value by until a market trades
+    fill_from_trades: HashSet<String>, // markets filled by their trades
+    fill_from_candles: HashMap<String, Period>, // the rest, by their shortest period
+    tickers: HashMap<String, Ticker>, // as replayed so far
+    books: HashMap<String, OrderBookState>,
+    taken: HashMap<(String, Side, Decimal), Decimal>, // off book levels since their snapshot
+    accounts: BTreeMap<String, Account>,
+    accounts_changed: bool,
+    orders: BTreeMap<usize, Resting>, // open orders by id
+    next_id: usize,
+    updates: VecDeque<Order>, // for `on_order_update` once the current hook returns
+    fills: Vec<Fill>,
+    lots: HashMap<String, VecDeque<Lot>>, // by market
+    round_trips: Vec<RoundTrip>,
+    stopped: bool,
+}
+
+impl<'a> Sim<'a> {
+    fn new(data: &'a Dataset, balances: &BTreeMap<String, Decimal>, events: &[(usize, Event<'_>)]) -> Sim<'a> {
+        let mut first = HashMap::new();
+        for (_, event) in events {
+            let (market, price) = match event {
+                Event::Trade(trade) => (&trade.market, trade.price),
+                Event::Candle(series, candle) => (&series.market, candle.open),
+                Event::Depth(_) => continue,
+            };
+            first.entry(market.clone()).or_insert(price);
+        }
+        let fill_from_trades: HashSet<String> = data.trades.iter().map(|trade| trade.market.clone()).collect();
+        let mut fill_from_candles: HashMap<String, Period> = HashMap::new();
+        for series in data.candles.iter().filter(|series| !fill_from_trades.contains(&series.market)) {
+            let period = fill_from_candles.entry(series.market.clone()).or_insert(series.period);
+            *period = (*period).min(series.period);
+        }
+        Sim {
+            now: 0,
+            templates: &data.tickers,
+            rules: data.tickers.iter().map(|(market, ticker)| (market.clone(), MarketRules::from_ticker(market, ticker))).collect(),
+            fees: data.tickers.iter().map(|(market, ticker)| (market.clone(), FeeSchedule::from_ticker(market, ticker))).collect(),
+            first,
+            fill_from_trades,
+            fill_from_candles,
+            tickers: HashMap::new(),
+            books: HashMap::new(),
+            taken: HashMap::new(),
+            accounts: balances
+                .iter()
+                .map(|(currency, balance)| {
+                    (currency.clone(), Account { currency: currency.clone(), balance: *balance, locked: Decimal::ZERO })
+                })
+                .collect(),
+            accounts_changed: true, // for the first on_account
+            orders: BTreeMap::new(),
+            next_id: 1,
+            updates: VecDeque::new(),
+            fills: Vec::new(),
+            lots: HashMap::new(),
+            round_trips: Vec::new(),
+            stopped: false,
+        }
+    }
+
+    // call a hook, then hand out the order updates it led to
+    fn hook<S: Strategy + ?Sized>(&mut self, strategy: &mut S, hook: impl FnOnce(&mut S, &mut dyn Context)) {
+        if self.stopped {
+            return;
+        }
+        hook(strategy, self);
+        self.deliver(strategy);
+    }
+
+    // order updates, including any the strategy's answers to them lead to
+    fn deliver<S: Strategy + ?Sized>(&mut self, strategy: &mut S) {
+        while !self.stopped {
+            let Some(order) = self.updates.pop_front() else { break };
+            strategy.on_order_update(self, &order);
+        }
+    }
+
+    // `on_account`, if the balances moved since the last one
+    fn report_accounts<S: Strategy + ?Sized>(&mut self, strategy: &mut S) {
+        if !self.accounts_changed {
+            return;
+        }
+        self.accounts_changed = false;
+        let member = Member {
+            sn: "backtest".to_string(),
+            name: None,
+            email: String::new(),
+            activated: true,
+            accounts_filtered: self.accounts.values().cloned().collect(),
+        };
+        self.hook(strategy, |strategy, ctx| strategy.on_account(ctx, &member));
+    }
+
+    fn replay<S: Strategy + ?Sized>(&mut self, strategy: &mut S, event: &Event<'_>) {
+        match *event {
+            Event::Depth(snapshot) => {
+                let changes = self.update_book(snapshot);
+                self.deliver(strategy);
+                let book = self.books[&snapshot.market].clone();
+                self.hook(strategy, |strategy, ctx| strategy.on_depth(ctx, &book, &changes));
+            }
+            Event::Trade(trade) => {
+                if self.fill_from_trades.contains(&trade.market) {
+                    let price = trade.price;
+                    self.cross(&trade.market, self.now, trade.volume, |side, limit| match side {
+                        Side::Buy => price <= limit,
+                        Side::Sell => price >= limit,
+                    });
+                }
+                self.deliver(strategy);
+                let ticker = self.traded(&trade.market, trade.price);
+                self.hook(strategy, |strategy, ctx| strategy.on_trade(ctx, &trade.market, trade));
+                if let Some(ticker) = ticker {
+                    self.hook(strategy, |strategy, ctx| strategy.on_ticker(ctx, &trade.market, &ticker));
+                }
+            }
+            Event::Candle(series, candle) => {
+                if self.fill_from_candles.get(&series.market) == Some(&series.period) {
+                    self.cross(&series.market, candle.timestamp, candle.volume, |side, limit| match side {
+                        Side::Buy => candle.low < limit,
+                        Side::Sell => candle.high > limit,
+                    });
+                }
+                self.deliver(strategy);
+                let ticker = self.traded(&series.market, candle.close);
+                self.hook(strategy, |strategy, ctx| strategy.on_candle(ctx, &series.market, series.period, candle));
+                if let Some(ticker) = ticker {
+                    self.hook(strategy, |strategy, ctx| strategy.on_ticker(ctx, &series.market, &ticker));
+                }
+            }
+        }
+    }
+
+    // `market` last traded at `price`, returning its ticker if it has one
+    fn traded(&mut self, market: &str, price: Decimal) -> Option<Ticker> {
+        let now = self.now;
+        let ticker = self.ticker_mut(market)?;
+        ticker.last = price;
+        ticker.at = now;
+        Some(ticker.clone())
+    }
+
+    // the replayed ticker of a market with a recorded one
+    fn ticker_mut(&mut self, market: &str) -> Option<&mut Ticker> {
+        if !self.tickers.contains_key(market) {
+            let template = self.templates.get(market)?.clone();
+            self.tickers.insert(market.to_string(), template);
+        }
+        self.tickers.get_mut(market)
+    }
+
+    fn last_price(&self, market: &str) -> Option<Decimal> {
+        self.tickers.get(market).map(|ticker| ticker.last).filter(|last| *last > Decimal::ZERO)
+    }
+
+    // a new snapshot replaces the book and fills the orders it crosses
+    fn update_book(&mut self, snapshot: &Snapshot) -> Vec<LevelChange> {
+        let market = &snapshot.market;
+        self.taken.retain(|(taken, _, _), _| taken != market);
+        let book = self.books.entry(market.clone()).or_insert_with(|| OrderBookState::new(market));
+        let changes = book.update_from_depth(&snapshot.depth);
+        let (bid, ask) = (book.best_bid(), book.best_ask());
+        let now = self.now;
+        if let Some(ticker) = self.ticker_mut(market) {
+            ticker.buy = bid.map(|level| level.price).unwrap_or_default();
+            ticker.sell = ask.map(|level| level.price).unwrap_or_default();
+            ticker.at = now;
+        }
+        if self.books[market].is_empty() {
+            return changes;
+        }
+        for (id, side, limit) in self.resting(market, |_, _| true) {
+            let remaining = self.orders.get(&id).map_or(Decimal::ZERO, |resting| resting.order.remaining_volume);
+            let volume = self.take(market, side, Some(limit), remaining).into_iter().map(|(_, volume)| volume).sum();
+            if volume > Decimal::ZERO {
+                self.fill(id, limit, volume, true);
+            }
+        }
+        changes
+    }
+
+    // open limit orders on `market` that `reaches`, best priced first on each side
+    fn resting(&self, market: &str, reaches: impl Fn(&Resting, Decimal) -> bool) -> Vec<(usize, Side, Decimal)> {
+        let mut resting: Vec<(usize, Side, Decimal)> = self
+            .orders
+            .values()
+            .filter(|resting| resting.order.market == market)
+            .filter_map(|resting| {
+                let limit = resting.limit?;
+                reaches(resting, limit).then_some((resting.order.id, resting.side, limit))
+            })
+            .collect();
+        resting.sort_by_key(|(id, side, limit)| match side {
+            Side::Buy => (0, -*limit, *id),
+            Side::Sell => (1, *limit, *id),
+        });
+        resting
+    }
+
+    // fill orders on `market` placed by `since` that the market reached, at
+    // their own price, until `volume` runs out
+    fn cross(&mut self, market: &str, since: usize, mut volume: Decimal, reaches: impl Fn(Side, Decimal) -> bool) {
+        for (id, _, limit) in self.resting(market, |resting, limit| resting.placed <= since && reaches(resting.side, limit)) {
+            let remaining = self.orders.get(&id).map_or(Decimal::ZERO, |resting| resting.order.remaining_volume);
+            let filled = remaining.min(volume);
+            if filled <= Decimal::ZERO {
+                break;
+            }
+            volume -= filled;
+            self.fill(id, limit, filled, true);
+        }
+    }
+
+    // what an order to `side` up to `volume` at `limit` or better takes on
+    // arrival, as (price, volume) off the book, or the last price without one
+    fn take(&mut self, market: &str, side: Side, limit: Option<Decimal>, volume: Decimal) -> Vec<(Decimal, Decimal)> {
+        let crosses = |price: Decimal| match side {
+            Side::Buy => limit.is_none_or(|limit| price <= limit),
+            Side::Sell => limit.is_none_or(|limit| price >= limit),
+        };
+        let mut taken = Vec::new();
+        match self.books.get(market).filter(|book| !book.is_empty()) {
+            Some(book) => {
+                let opposite = opposite(side);
+                let mut left = volume;
+                for level in book.levels(opposite).take_while(|level| crosses(level.price)) {
+                    let key = (market.to_string(), opposite, level.price);
+                    let available = level.volume - self.taken.get(&key).copied().unwrap_or_default();
+                    let volume = left.min(available);
+                    if volume > Decimal::ZERO {
+                        *self.taken.entry(key).or_insert(Decimal::ZERO) += volume;
+                        taken.push((level.price, volume));
+                        left -= volume;
+                    }
+                    if left.is_zero() {
+                        break;
+                    }
+                }
+            }
+            None => taken.extend(self.last_price(market).filter(|last| crosses(*last)).map(|last| (last, volume))),
+        }
+        taken
+    }
+
+    fn balance(&mut self, currency: &str) -> &mut Account {
+        self.accounts_changed = true;
+        self.accounts.entry(currency.to_string()).or_insert_with(|| Account {
+            currency: currency.to_string(),
+            balance: Decimal::ZERO,
+            locked: Decimal::ZERO,
+        })
+    }
+
+    // fill `volume` of order `id` at `price`, moving funds like graviex
+    fn fill(&mut self, id: usize, price: Decimal, volume: Decimal, maker: bool) -> Option<Order> {
+        let resting = self.orders.get_mut(&id)?;
+        resting.order.executed_volume += volume;
+        resting.order.remaining_volume -= volume;
+        resting.funds += price * volume;
+        resting.order.avg_price = resting.funds / resting.order.executed_volume;
+        let (side, limit) = (resting.side, resting.limit);
+        let order = if resting.order.remaining_volume.is_zero() {
+            let mut done = self.orders.remove(&id)?.order;
+            done.state = OrderState::Done.as_str().to_string();
+            done
+        } else {
+            resting.order.clone()
+        };
+
+        let fees = self.fees.get(&order.market)?.clone();
+        let cost = fees.cost(side, price, volume);
+        match side {
+            Side::Buy => {
+                let quote = self.balance(&fees.quote_unit);
+                match limit {
+                    Some(limit) => {
+                        quote.locked -= limit * volume;
+                        quote.balance += (limit - price) * volume;
+                    }
+                    None => quote.balance -= price * volume,
+                }
+                self.balance(&fees.base_unit).balance += cost.net.value;
+                let lot = Lot { at: self.now, volume: cost.net.value, cost: price * volume };
+                self.lots.entry(order.market.clone()).or_default().push_back(lot);
+            }
+            Side::Sell => {
+                let base = self.balance(&fees.base_unit);
+                match limit {
+                    Some(_) => base.locked -= volume,
+                    None => base.balance -= volume,
+                }
+                self.balance(&fees.quote_unit).balance += cost.net.value;
+                self.close_lots(&order.market, volume, cost.net.value);
+            }
+        }
+        self.fills.push(Fill {
+            order_id: id,
+            market: order.market.clone(),
+            side,
+            price,
+            volume,
+            fee: cost.fee,
+            maker,
+            at: self.now,
+        });
+        self.updates.push_back(order.clone());
+        Some(order)
+    }
+
+    // match base sold against what was bought first
+    fn close_lots(&mut self, market: &str, volume: Decimal, proceeds: Decimal) {
+        let lots = self.lots.entry(market.to_string()).or_default();
+        let (mut matched, mut cost, mut opened) = (Decimal::ZERO, Decimal::ZERO, None);
+        while matched < volume {
+            let Some(lot) = lots.front_mut() else { break };
+            let taken = (volume - matched).min(lot.volume);
+            let share = lot.cost * taken / lot.volume;
+            opened.get_or_insert(lot.at);
+            matched += taken;
+            cost += share;
+            lot.volume -= taken;
+            lot.cost -= share;
+            if lot.volume.is_zero() {
+                lots.pop_front();
+            }
+        }
+        if let Some(opened) = opened {
+            self.round_trips.push(RoundTrip {
+                market: market.to_string(),
+                opened,
+                closed: self.now,
+                volume: matched,
+                cost,
+                proceeds: proceeds * matched / volume,
+            });
+        }
+    }
+
+    // everything held, at the latest prices
+    fn value(&self, currency: &str) -> Decimal {
+        self.accounts.values().map(|account| (account.balance + account.locked) * self.rate(&account.currency, currency)).sum()
+    }
+
+    // what one `from` is worth in `to`, through a market trading one for the
+    // other, or nothing without one
+    fn rate(&self, from: &str, to: &str) -> Decimal {
+        if from == to {
+            return Decimal::ONE;
+        }
+        for (market, fees) in &self.fees {
+            let Some(price) = self.last_price(market).or_else(|| self.first.get(market).copied()) else { continue };
+            if fees.base_unit == from && fees.quote_unit == to {
+                return price;
+            }
+            if fees.quote_unit == from && fees.base_unit == to && price > Decimal::ZERO {
+                return Decimal::ONE / price;
+            }
+        }
+        Decimal::ZERO
+    }
+}
+
+impl Context for Sim<'_> {
+    fn now(&self) -> u64 {
+        u64::try_from(self.now).unwrap_or_default()
+    }
+
+    fn place(&mut self, new: &NewOrder) -> Result<Order, GraviexError> {
+        let rules = self
+            .rules
+            .get(&new.market)
+            .ok_or_else(|| ValidationError::UnknownMarket { market: new.market.clone() })?;
+        rules.check(new)?;
+        let (base, quote) = (rules.base_unit.clone(), rules.quote_unit.clone());
+        let limit = new.kind.price();
+
+        let taken = self.take(&new.market, new.side, limit, new.volume);
+        if limit.is_none() && taken.is_empty() {
+            return Err(api_error(2002, "Failed to create order. Nothing to fill a market order against."));
+        }
+        let (currency, needed) = match (new.side, limit) {
+            (Side::Buy, Some(price)) => (quote, price * new.volume),
+            (Side::Buy, None) => (quote, taken.iter().map(|(price, volume)| price * volume).sum()),
+            (Side::Sell, _) => (base, new.volume),
+        };
+        if self.accounts.get(&currency).is_none_or(|account| account.balance < needed) {
+            // give back what the order would have taken off the book
+            for (price, volume) in &taken {
+                if let Some(taken) = self.taken.get_mut(&(new.market.clone(), opposite(new.side), *price)) {
+                    *taken -= *volume;
+                }
+            }
+            return Err(api_error(2002, "Failed to create order. Insufficient balance."));
+        }
+        if limit.is_some() {
+            let account = self.balance(&currency);
+            account.balance -= needed;
+            account.locked += needed;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut order = Order {
+            id,
+            side: new.side.as_str().to_string(),
+            price: limit.unwrap_or_default(),
+            avg_price: Decimal::ZERO,
+            state: OrderState::Wait.as_str().to_string(),
+            market: new.market.clone(),
+            created_at: format_timestamp(self.now()),
+            volume: new.volume,
+            remaining_volume: new.volume,
+            executed_volume: Decimal::ZERO,
+            trades: None,
+        };
+        self.orders.insert(id, Resting { order: order.clone(), side: new.side, limit, placed: self.now, funds: Decimal::ZERO });
+        for (price, volume) in taken {
+            order = self.fill(id, price, volume, false).unwrap_or(order);
+        }
+        if limit.is_none() {
+            // market orders never rest
+            if let Some(mut resting) = self.orders.remove(&id) {
+                resting.order.state = OrderState::Cancel.as_str().to_string();
+                order = resting.order;
+                self.updates.push_back(order.clone());
+            }
+        }
+        Ok(order)
+    }
+
+    fn cancel(&mut self, id: usize) -> Result<Order, GraviexError> {
+        let mut resting = self.orders.remove(&id).ok_or_else(|| api_error(2004, "Order not found."))?;
+        if let (Some(limit), Some(fees)) = (resting.limit, self.fees.get(&resting.order.market).cloned()) {
+            let remaining = resting.order.remaining_volume;
+            let (currency, amount) = match resting.side {
+                Side::Buy => (&fees.quote_unit, limit * remaining),
+                Side::Sell => (&fees.base_unit, remaining),
+            };
+            let account = self.balance(currency);
+            account.locked -= amount;
+            account.balance += amount;
+        }
+        resting.order.state = OrderState::Cancel.as_str().to_string();
+        self.updates.push_back(resting.order.clone());
+        Ok(resting.order)
+    }
+
+    fn open_orders(&self) -> Vec<&Order> {
+        self.orders.values().map(|resting| &resting.order).collect()
+    }
+
+    fn account(&self, currency: &str) -> Option<&Account> {
+        self.accounts.get(currency)
+    }
+
+    fn ticker(&self, market: &str) -> Option<&Ticker> {
+        self.tickers.get(market)
+    }
+
+    fn book(&self, market: &str) -> Option<&OrderBookState> {
+        self.books.get(market)
+    }
+
+    fn stop(&mut self) {
+        self.stopped = true;
+    }
+}
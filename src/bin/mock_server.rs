@@ -0,0 +1,28 @@
+use std::env;
+
+use graviex_api_client::mock::{MockExchange, MockServer};
+use graviex_api_client::Decimal;
+
+// serve a mock graviex on the address given as the first argument,
+// with one funded member signing as GRAVIEX_KEY and GRAVIEX_SECRET
+fn main() {
+    let addr = env::args().nth(1).unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let key = env::var("GRAVIEX_KEY").unwrap_or_else(|_| "mock".to_string());
+    let secret = env::var("GRAVIEX_SECRET").unwrap_or_else(|_| "mock".to_string());
+
+    let mut exchange = MockExchange::new();
+    exchange.add_member(&key, &secret);
+    exchange.credit(&key, "btc", Decimal::new(10, 0));
+    exchange.credit(&key, "eth", Decimal::new(100, 0));
+    exchange.credit(&key, "gio", Decimal::new(100_000, 0));
+
+    let server = match MockServer::bind(&addr, exchange) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("could not bind {}: {}", addr, e);
+            return;
+        }
+    };
+    println!("mock graviex listening on {} for access key {}", server.url(), key);
+    server.wait();
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+
+/// Everything resting at one price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub price: Decimal,
+    pub volume: Decimal, // in base units
+}
+
+/// How one price level moved between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelChange {
+    pub side: Side, // Buy for bids, Sell for asks
+    pub price: Decimal,
+    pub before: Decimal, // zero when the level is new
+    pub after: Decimal, // zero when the level is gone
+}
+
+impl LevelChange {
+    /// Volume added to the level, negative when it shrank.
+    #[must_use]
+    pub fn delta(&self) -> Decimal {
+        self.after - self.before
+    }
+}
+
+/// A market's order book aggregated into price levels.
+///
+/// Feed it snapshots from `depth` or `order_book` as often as you poll;
+/// every update replaces the book and says which levels changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderBookState {
+    pub market: String, // like "ethbtc"
+    pub timestamp: Option<usize>, // of the last depth snapshot, order_book has none
+    bids: BTreeMap<Decimal, Decimal>, // volume by price
+    asks: BTreeMap<Decimal, Decimal>, // volume by price
+}
+
+impl OrderBookState {
+    /// An empty book for `market`.
+    #[must_use]
+    pub fn new(market: &str) -> OrderBookState {
+        OrderBookState { market: market.to_string(), ..OrderBookState::default() }
+    }
+
+    /// A book built from a `depth` snapshot.
+    #[must_use]
+    pub fn from_depth(market: &str, depth: &Depth) -> OrderBookState {
+        let mut book = OrderBookState::new(market);
+        book.update_from_depth(depth);
+        book
+    }
+
+    /// A book built from an `order_book` snapshot.
+    #[must_use]
+    pub fn from_order_book(market: &str, order_book: &OrderBook) -> OrderBookState {
+        let mut book = OrderBookState::new(market);
+        book.update_from_order_book(order_book);
+        book
+    }
+
+    /// Replace the book with a `depth` snapshot, returning what changed.
+    pub fn update_from_depth(&mut self, depth: &Depth) -> Vec<LevelChange> {
+        let levels = |orders: &[TinyOrder]| orders.iter().map(|order| (order.price, order.vol)).collect();
+        self.timestamp = Some(depth.timestamp);
+        self.replace(levels(&depth.bids), levels(&depth.asks))
+    }
+
+    /// Replace the book with an `order_book` snapshot, adding up the
+    /// remaining volume of orders at the same price, returning what changed.
+    pub fn update_from_order_book(&mut self, order_book: &OrderBook) -> Vec<LevelChange> {
+        let levels = |orders: &[Order]| orders.iter().map(|order| (order.price, order.remaining_volume)).collect();
+        self.timestamp = None;
+        self.replace(levels(&order_book.bids), levels(&order_book.asks))
+    }
+
+    fn replace(&mut self, bids: Vec<(Decimal, Decimal)>, asks: Vec<(Decimal, Decimal)>) -> Vec<LevelChange> {
+        let bids = aggregate(bids);
+        let asks = aggregate(asks);
+        let mut changes = diff(Side::Buy, &self.bids, &bids);
+        changes.extend(diff(Side::Sell, &self.asks, &asks));
+        self.bids = bids;
+        self.asks = asks;
+        changes
+    }
+
+    /// Bid levels, best (highest) first.
+    pub fn bids(&self) -> impl Iterator<Item = Level> + '_ {
+        self.bids.iter().rev().map(|(price, volume)| Level { price: *price, volume: *volume })
+    }
+
+    /// Ask levels, best (lowest) first.
+    pub fn asks(&self) -> impl Iterator<Item = Level> + '_ {
+        self.asks.iter().map(|(price, volume)| Level { price: *price, volume: *volume })
+    }
+
+    /// Levels on `side`, Buy for bids and Sell for asks, best first.
+    pub fn levels(&self, side: Side) -> Box<dyn Iterator<Item = Level> + '_> {
+        match side {
+            Side::Buy => Box::new(self.bids()),
+            Side::Sell => Box::new(self.asks()),
+        }
+    }
+
+    #[must_use]
+    pub fn best_bid(&self) -> Option<Level> {
+        self.bids().next()
+    }
+
+    #[must_use]
+    pub fn best_ask(&self) -> Option<Level> {
+        self.asks().next()
+    }
+
+    /// Best ask minus best bid.
+    #[must_use]
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// Halfway between the best bid and the best ask.
+    #[must_use]
+    pub fn mid(&self) -> Option<Decimal> {
+        Some((self.best_ask()?.price + self.best_bid()?.price) / Decimal::TWO)
+    }
+
+    /// Levels on `side`, best first, with `volume` summed over every level
+    /// up to and including this one.
+    #[must_use]
+    pub fn cumulative(&self, side: Side) -> Vec<Level> {
+        let mut total = Decimal::ZERO;
+        self.levels(side)
+            .map(|level| {
+                total += level.volume;
+                Level { price: level.price, volume: total }
+            })
+            .collect()
+    }
+
+    /// Volume on `side` at `price` or better.
+    #[must_use]
+    pub fn volume_to(&self, side: Side, price: Decimal) -> Decimal {
+        self.levels(side)
+            .take_while(|level| match side {
+                Side::Buy => level.price >= price,
+                Side::Sell => level.price <= price,
+            })
+            .map(|level| level.volume)
+            .sum()
+    }
+
+    /// Average price a market order to `side` `volume` would fill at,
+    /// walking the opposite side of the book. None if the book is too thin.
+    #[must_use]
+    pub fn vwap(&self, side: Side, volume: Decimal) -> Option<Decimal> {
+        if volume <= Decimal::ZERO {
+            return None;
+        }
+        let opposite = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let mut left = volume;
+        let mut funds = Decimal::ZERO;
+        for level in self.levels(opposite) {
+            let taken = left.min(level.volume);
+            funds += taken * level.price;
+            left -= taken;
+            if left.is_zero() {
+                return Some(funds / volume);
+            }
+        }
+        None
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+}
+
+// sum volume by price, dropping empty levels
+fn aggregate(levels: Vec<(Decimal, Decimal)>) -> BTreeMap<Decimal, Decimal> {
+    let mut book = BTreeMap::new();
+    for (price, volume) in levels {
+        *book.entry(price).or_insert(Decimal::ZERO) += volume;
+    }
+    book.retain(|_, volume| !volume.is_zero());
+    book
+}
+
+// levels that differ between two books, best first
+fn diff(side: Side, before: &BTreeMap<Decimal, Decimal>, after: &BTreeMap<Decimal, Decimal>) -> Vec<LevelChange> {
+    let prices: BTreeSet<&Decimal> = before.keys().chain(after.keys()).collect();
+    let changes = prices.into_iter().filter_map(|price| {
+        let before = before.get(price).copied().unwrap_or_default();
+        let after = after.get(price).copied().unwrap_or_default();
+        (before != after).then_some(LevelChange { side, price: *price, before, after })
+    });
+    match side {
+        Side::Buy => changes.rev().collect(),
+        Side::Sell => changes.collect(),
+    }
+}
+
+impl GraviexClient {
+    /// Poll `depth` into `book`, returning the levels that changed since
+    /// the last snapshot.
+    ///
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong, leaving `book` as it was
+    pub fn refresh_depth(&self, book: &mut OrderBookState, query: &DepthQuery) -> Result<Vec<LevelChange>, GraviexError> {
+        let depth = self.depth(query)?;
+        Ok(book.update_from_depth(&depth))
+    }
+
+    /// Poll `order_book` into `book`, returning the levels that changed
+    /// since the last snapshot.
+    ///
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong, leaving `book` as it was
+    pub fn refresh_order_book(
+        &self,
+        book: &mut OrderBookState,
+        query: &OrderBookQuery)
+    -> Result<Vec<LevelChange>, GraviexError> {
+        let order_book = self.order_book(query)?;
+        Ok(book.update_from_order_book(&order_book))
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+
+use crate::time::parse_timestamp;
+use crate::*;
+
+/// Which way `kline_range` walks through history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward, // oldest bar first
+    Backward, // newest bar first
+}
+
+/// A stretch of k lines longer than one `kline` call returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlineRange {
+    pub market: String,
+    pub period: Period,
+    pub from: usize, // first bar starting at or after this unix time
+    pub to: Option<usize>, // last bar starting at or before this unix time, default now
+    pub page_size: usize, // bars per request, default 500
+    pub direction: Direction, // default forward
+    pub pending: Option<usize>, // trade id for `kline_pending`, to fill in the open bar
+}
+
+impl KlineRange {
+    /// Every `period` bar of `market` from `from` up to now.
+    #[must_use]
+    pub fn new(market: &str, period: Period, from: usize) -> KlineRange {
+        KlineRange {
+            market: market.to_string(),
+            period,
+            from,
+            to: None,
+            page_size: 500,
+            direction: Direction::Forward,
+            pending: None,
+        }
+    }
+
+    #[must_use]
+    pub fn to(mut self, to: usize) -> KlineRange {
+        self.to = Some(to);
+        self
+    }
+
+    /// Fetch `page_size` bars per request, at least one.
+    #[must_use]
+    pub fn page_size(mut self, page_size: usize) -> KlineRange {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Walk from the newest bar back to the oldest.
+    #[must_use]
+    pub fn backward(mut self) -> KlineRange {
+        self.direction = Direction::Backward;
+        self
+    }
+
+    /// Fetch the newest page from `kline_pending` with `trade_id`, folding
+    /// trades graviex hasn't put in a bar yet into the open one. Only used
+    /// when the range runs up to now.
+    #[must_use]
+    pub fn pending(mut self, trade_id: usize) -> KlineRange {
+        self.pending = Some(trade_id);
+        self
+    }
+}
+
+/// Bars of a `KlineRange`, fetched a page at a time as they are needed.
+///
+/// Pages are stitched together so every bar comes exactly once, and bars
+/// missing from graviex's answer are filled in flat at the previous close
+/// with no volume. Stops for good after the first error.
+#[derive(Debug)]
+pub struct KlinePages<'a> {
+    client: &'a GraviexClient,
+    range: KlineRange,
+    to: Option<usize>, // the range's end, or graviex's time once asked
+    cursor: Option<usize>, // forward, where the next page starts; backward, where the last one did
+    buffer: VecDeque<Candle>, // fetched, in the order they are handed out
+    edge: Option<Candle>, // the last bar handed out
+    done: bool,
+}
+
+impl<'a> KlinePages<'a> {
+    fn new(client: &'a GraviexClient, range: &KlineRange) -> KlinePages<'a> {
+        KlinePages {
+            client,
+            range: range.clone(),
+            to: range.to,
+            cursor: None,
+            buffer: VecDeque::new(),
+            edge: None,
+            done: false,
+        }
+    }
+
+    fn seconds(&self) -> usize {
+        self.range.period.seconds()
+    }
+
+    // how much time one page covers
+    fn span(&self) -> usize {
+        self.seconds() * self.range.page_size
+    }
+
+    fn query(&self, timestamp: Option<usize>) -> KlineQuery {
+        let query = KlineQuery::new(&self.range.market, self.range.period).limit(self.range.page_size);
+        match timestamp {
+            Some(timestamp) => query.timestamp(timestamp),
+            None => query,
+        }
+    }
+
+    // the bars of `query` in order, with pending trades folded in when
+    // the page reaches the open bar of a range running up to now
+    fn fetch(&self, query: KlineQuery, newest: bool) -> Result<Vec<Candle>, GraviexError> {
+        let mut candles = match self.range.pending {
+            Some(trade_id) if newest && self.range.to.is_none() => {
+                self.client.kline_with_pending(&KlinePendingQuery::new(query, trade_id))?
+            }
+            _ => self.client.kline(&query)?,
+        };
+        candles.sort_by_key(|candle| candle.timestamp);
+        candles.dedup_by_key(|candle| candle.timestamp);
+        candles.retain(|candle| self.keep(candle));
+        Ok(candles)
+    }
+
+    // only bars inside the range, and not handed out already
+    fn keep(&self, candle: &Candle) -> bool {
+        let after = match (&self.edge, self.range.direction) {
+            (Some(edge), Direction::Forward) => candle.timestamp > edge.timestamp,
+            (Some(edge), Direction::Backward) => candle.timestamp < edge.timestamp,
+            (None, _) => true,
+        };
+        after && candle.timestamp >= self.range.from && self.to.is_none_or(|to| candle.timestamp <= to)
+    }
+
+    fn next_forward(&mut self) -> Result<(), GraviexError> {
+        // a page of nothing proves nothing while graviex may skip quiet
+        // bars, so walking up to now needs to know when now is
+        let to = match self.to {
+            Some(to) => to,
+            None => *self.to.insert(self.client.timestamp()?),
+        };
+        let seconds = self.seconds();
+        let cursor = self.cursor.unwrap_or(self.range.from);
+        let page = self.fetch(self.query(Some(cursor)), cursor + self.span() > to)?;
+        self.cursor = Some(page.last().map_or(cursor + self.span(), |bar| bar.timestamp + seconds));
+
+        let mut bars: Vec<Candle> = self.edge.iter().cloned().chain(page).collect();
+        fill_gaps(&mut bars, seconds);
+        if self.edge.is_some() {
+            bars.remove(0);
+        }
+        self.done = self.cursor.is_some_and(|cursor| cursor > to);
+        self.buffer.extend(bars);
+        Ok(())
+    }
+
+    fn next_backward(&mut self) -> Result<(), GraviexError> {
+        let seconds = self.seconds();
+        let end = self.cursor.or(self.to.map(|to| to + seconds));
+        // without an end, graviex's own latest page is the place to start
+        let timestamp = end.map(|end| end.saturating_sub(self.span()).max(self.range.from));
+        let page = self.fetch(self.query(timestamp), self.edge.is_none())?;
+        let Some(start) = timestamp.or(page.first().map(|bar| bar.timestamp)) else {
+            self.done = true;
+            return Ok(());
+        };
+        self.cursor = Some(start);
+
+        let mut bars: Vec<Candle> = page.into_iter().chain(self.edge.iter().cloned()).collect();
+        fill_gaps(&mut bars, seconds);
+        if self.edge.is_some() {
+            bars.pop();
+        }
+        self.done = start <= self.range.from || start < seconds;
+        self.buffer.extend(bars.into_iter().rev());
+        Ok(())
+    }
+}
+
+impl Iterator for KlinePages<'_> {
+    type Item = Result<Candle, GraviexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buffer.is_empty() && !self.done {
+            let fetched = match self.range.direction {
+                Direction::Forward => self.next_forward(),
+                Direction::Backward => self.next_backward(),
+            };
+            if let Err(e) = fetched {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        let candle = self.buffer.pop_front()?;
+        self.edge = Some(candle.clone());
+        Some(Ok(candle))
+    }
+}
+
+// put flat bars at the previous close wherever a step is missing
+fn fill_gaps(candles: &mut Vec<Candle>, seconds: usize) {
+    let mut filled: Vec<Candle> = Vec::with_capacity(candles.len());
+    for candle in candles.drain(..) {
+        if let Some(previous) = filled.last().cloned() {
+            let mut timestamp = previous.timestamp + seconds;
+            while timestamp < candle.timestamp {
+                filled.push(flat(timestamp, previous.close));
+                timestamp += seconds;
+            }
+        }
+        filled.push(candle);
+    }
+    *candles = filled;
+}
+
+fn flat(timestamp: usize, close: Decimal) -> Candle {
+    Candle { timestamp, open: close, high: close, low: close, close, volume: Decimal::ZERO }
+}
+
+/// Fold the trades of a `kline_pending` answer into its bars.
+///
+/// Graviex sends the trades made since the last bar opened, some of
+/// which that bar may already count. Trades in the last bar only widen
+/// its range and move its close, keeping whichever volume is larger;
+/// later trades open new bars. Trades before the last bar, or with a
+/// `created_at` that doesn't parse, are ignored.
+pub fn merge_pending(candles: &mut Vec<Candle>, trades: &[Trade], period: Period) {
+    let seconds = period.seconds();
+    let mut trades: Vec<(usize, &Trade)> = trades
+        .iter()
+        .filter_map(|trade| Some((usize::try_from(parse_timestamp(&trade.created_at)?).ok()?, trade)))
+        .collect();
+    trades.sort_by_key(|(at, trade)| (*at, trade.id));
+
+    let start = candles.last().map(|candle| candle.timestamp);
+    let mut last_volume = Decimal::ZERO; // of the trades falling in the last bar
+    for (at, trade) in trades {
+        // bars line up with graviex's own where there are some
+        let bucket = match start {
+            Some(start) if at < start => continue,
+            Some(start) => start + (at - start) / seconds * seconds,
+            None => at - at % seconds,
+        };
+        match candles.last().map(|candle| (candle.timestamp, candle.close)) {
+            Some((timestamp, _)) if timestamp == bucket => {
+                let Some(candle) = candles.last_mut() else { continue };
+                candle.high = candle.high.max(trade.price);
+                candle.low = candle.low.min(trade.price);
+                candle.close = trade.price;
+                if Some(bucket) == start {
+                    last_volume += trade.volume;
+                    candle.volume = candle.volume.max(last_volume);
+                } else {
+                    candle.volume += trade.volume;
+                }
+            }
+            previous => {
+                if let Some((mut timestamp, close)) = previous {
+                    timestamp += seconds;
+                    while timestamp < bucket {
+                        candles.push(flat(timestamp, close));
+                        timestamp += seconds;
+                    }
+                }
+                candles.push(Candle { volume: trade.volume, ..flat(bucket, trade.price) });
+            }
+        }
+    }
+}
+
+impl GraviexClient {
+    /// Every bar of `range`, fetched a page at a time as the iterator
+    /// is advanced.
+    ///
+    /// ```no_run
+    /// # use graviex_api_client::*;
+    /// # let client = GraviexClient::new("key", "secret");
+    /// let week: Result<Vec<Candle>, _> = client
+    ///     .kline_range(&KlineRange::new("ethbtc", Period::Hour1, 1_626_048_000).to(1_626_652_800))
+    ///     .collect();
+    /// ```
+    #[must_use]
+    pub fn kline_range(&self, range: &KlineRange) -> KlinePages<'_> {
+        KlinePages::new(self, range)
+    }
+
+    /// `kline_pending` with its trades folded into the bars, see
+    /// `merge_pending`.
+    ///
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn kline_with_pending(&self, query: &KlinePendingQuery) -> Result<Vec<Candle>, GraviexError> {
+        let KlineWithPending { mut k, trades } = self.kline_pending(query)?;
+        merge_pending(&mut k, &trades, query.kline.period);
+        Ok(k)
+    }
+}
@@ -1,2 +0,0 @@
-const GRAVIEX_KEY: &str = "xxxxxx";
-const GRAVIEX_SECRET: &str = "xxxxxx";
@@ -0,0 +1,72 @@
+pub use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::{NewOrder, OrderKind, Side, Ticker};
+
+/// `value` cut down to `places` decimals, never rounding away from zero.
+#[must_use]
+pub fn round_down(value: Decimal, places: usize) -> Decimal {
+    value.round_dp_with_strategy(dp(places), RoundingStrategy::ToZero)
+}
+
+/// `value` pushed up to `places` decimals, never rounding towards zero.
+#[must_use]
+pub fn round_up(value: Decimal, places: usize) -> Decimal {
+    value.round_dp_with_strategy(dp(places), RoundingStrategy::AwayFromZero)
+}
+
+/// Number of significant decimals in `value`, ignoring trailing zeros.
+#[must_use]
+pub fn decimal_places(value: Decimal) -> usize {
+    value.normalize().scale() as usize
+}
+
+fn dp(places: usize) -> u32 {
+    u32::try_from(places).unwrap_or(u32::MAX)
+}
+
+/// Rounding rules for orders on the ticker's market.
+impl Ticker {
+    /// `volume` rounded down to `base_fixed` decimals, so we never try to
+    /// move more than we have.
+    #[must_use]
+    pub fn round_volume(&self, volume: Decimal) -> Decimal {
+        round_down(volume, self.base_fixed)
+    }
+
+    /// `price` rounded to `quote_fixed` decimals in our favour: bids round
+    /// down and asks round up.
+    #[must_use]
+    pub fn round_price(&self, price: Decimal, side: Side) -> Decimal {
+        match side {
+            Side::Buy => round_down(price, self.quote_fixed),
+            Side::Sell => round_up(price, self.quote_fixed),
+        }
+    }
+
+    /// Whether `volume` at `price` clears both `base_min` and `quote_min`.
+    #[must_use]
+    pub fn meets_minimum(&self, price: Decimal, volume: Decimal) -> bool {
+        volume >= self.base_min && price * volume >= self.quote_min
+    }
+
+    /// Smallest volume at `price` that clears both minimums, rounded up to
+    /// `base_fixed` decimals.
+    #[must_use]
+    pub fn min_volume(&self, price: Decimal) -> Decimal {
+        let mut volume = self.base_min;
+        if price > Decimal::ZERO {
+            volume = volume.max(self.quote_min / price);
+        }
+        round_up(volume, self.base_fixed)
+    }
+
+    /// `order` with its price and volume rounded to this market's precision.
+    #[must_use]
+    pub fn quantize(&self, mut order: NewOrder) -> NewOrder {
+        order.volume = self.round_volume(order.volume);
+        if let OrderKind::Limit { price } = &mut order.kind {
+            *price = self.round_price(*price, order.side);
+        }
+        order
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::strategy::{Context, Strategy};
+use crate::*;
+
+// something done every `interval`
+#[derive(Debug, Clone, Copy)]
+struct Schedule {
+    interval: Duration,
+    due: Instant,
+}
+
+impl Schedule {
+    fn new(interval: Duration) -> Schedule {
+        Schedule { interval, due: Instant::now() }
+    }
+
+    // whether it is due, moving it on to the next time if so
+    fn take(&mut self, now: Instant) -> bool {
+        if self.due > now {
+            return false;
+        }
+        self.due = now + self.interval;
+        true
+    }
+}
+
+// what the engine knows, and strategies see through their context
+#[derive(Debug, Default)]
+struct State {
+    tickers: HashMap<String, Ticker>,
+    books: HashMap<String, OrderBookState>,
+    orders: BTreeMap<usize, Order>, // open orders by id
+    canceling: BTreeMap<usize, Order>, // canceled by the strategy, maybe still on the book
+    accounts: HashMap<String, Account>, // by currency
+    stopped: bool,
+}
+
+fn is_open(order: &Order) -> bool {
+    order.state == OrderState::Wait.as_str()
+}
+
+// whether anything a strategy cares about moved between two looks at an order
+fn changed(before: &Order, after: &Order) -> bool {
+    before.state != after.state
+        || before.executed_volume != after.executed_volume
+        || before.remaining_volume != after.remaining_volume
+}
+
+// the context strategies get live
+struct Live<'a> {
+    client: &'a GraviexClient,
+    state: &'a mut State,
+}
+
+impl Context for Live<'_> {
+    fn now(&self) -> u64 {
+        time::now()
+    }
+
+    fn place(&mut self, order: &NewOrder) -> Result<Order, GraviexError> {
+        let order = self.client.orders_post(order)?;
+        if is_open(&order) {
+            self.state.orders.insert(order.id, order.clone());
+        }
+        Ok(order)
+    }
+
+    fn cancel(&mut self, id: usize) -> Result<Order, GraviexError> {
+        // graviex answers before the cancel goes through, the next order
+        // refresh tells the strategy once it has
+        let order = self.client.order_delete(id)?;
+        let known = self.state.orders.remove(&id).unwrap_or_else(|| order.clone());
+        self.state.canceling.insert(id, known);
+        Ok(order)
+    }
+
+    fn open_orders(&self) -> Vec<&Order> {
+        self.state.orders.values().collect()
+    }
+
+    fn account(&self, currency: &str) -> Option<&Account> {
+        self.state.accounts.get(currency)
+    }
+
+    fn ticker(&self, market: &str) -> Option<&Ticker> {
+        self.state.tickers.get(market)
+    }
+
+    fn book(&self, market: &str) -> Option<&OrderBookState> {
+        self.state.books.get(market)
+    }
+
+    fn stop(&mut self) {
+        self.state.stopped = true;
+    }
+}
+
+/// Runs a `Strategy` live: polls the market data it subscribes to, keeps
+/// its orders and accounts fresh, and calls its hooks as things change.
+///
+/// Orders are refreshed on every market subscribed to or holding an open
+/// order, calling `on_order_update` as they fill, get canceled, or show
+/// up from elsewhere. Accounts are read with `me`, calling `on_account`.
+/// Market data comes from a `Poller`, which logs and backs off its own
+/// failures; failures of the engine's requests go to `on_error`.
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use std::time::Duration;
+/// # use graviex_api_client::*;
+/// use graviex_api_client::strategy::Strategy;
+///
+/// struct Printer;
+///
+/// impl Strategy for Printer {
+///     fn on_ticker(&mut self, _ctx: &mut dyn Context, market: &str, ticker: &Ticker) {
+///         println!("{} last traded at {}", market, ticker.last);
+///     }
+/// }
+///
+/// let client = Arc::new(GraviexClient::from_env().unwrap());
+/// let engine = Engine::new(client, Printer).subscribe(Topic::Ticker("ethbtc".to_string()), Duration::from_secs(5));
+/// engine.run();
+/// ```
+pub struct Engine<S> {
+    client: Arc<GraviexClient>,
+    strategy: S,
+    poller: Poller,
+    events: Receiver<MarketEvent>,
+    sender: Sender<MarketEvent>, // for each subscription's callback
+    markets: BTreeSet<String>, // subscribed to, for the order refresh
+    account: Option<Schedule>,
+    orders: Option<Schedule>,
+    timer: Option<Schedule>,
+    state: State,
+    started: bool,
+}
+
+impl<S: Strategy> Engine<S> {
+    /// Run `strategy` with `client`, reading accounts every 30 seconds and
+    /// orders every 5.
+    #[must_use]
+    pub fn new(client: Arc<GraviexClient>, strategy: S) -> Engine<S> {
+        let (sender, events) = mpsc::channel();
+        Engine {
+            poller: Poller::new(Arc::clone(&client)),
+            client,
+            strategy,
+            events,
+            sender,
+            markets: BTreeSet::new(),
+            account: Some(Schedule::new(Duration::from_secs(30))),
+            orders: Some(Schedule::new(Duration::from_secs(5))),
+            timer: None,
+            state: State::default(),
+            started: false,
+        }
+    }
+
+    /// Poll `topic` every `interval` for the strategy's hooks.
+    #[must_use]
+    pub fn subscribe(mut self, topic: Topic, interval: Duration) -> Engine<S> {
+        self.markets.insert(topic.market().to_string());
+        let sender = self.sender.clone();
+        self.poller.subscribe(topic, interval, move |event| {
+            let _ = sender.send(event.clone());
+        });
+        self
+    }
+
+    /// Back off failing market data requests by `backoff`'s delays, see
+    /// `Poller::backoff`.
+    #[must_use]
+    pub fn backoff(mut self, backoff: RetryPolicy) -> Engine<S> {
+        self.poller = self.poller.backoff(backoff);
+        self
+    }
+
+    /// Read accounts every `interval`, or never with `None`.
+    #[must_use]
+    pub fn refresh_account(mut self, interval: Option<Duration>) -> Engine<S> {
+        self.account = interval.map(Schedule::new);
+        self
+    }
+
+    /// Read orders every `interval`, or never with `None`.
+    #[must_use]
+    pub fn refresh_orders(mut self, interval: Option<Duration>) -> Engine<S> {
+        self.orders = interval.map(Schedule::new);
+        self
+    }
+
+    /// Call `on_timer` every `interval`.
+    #[must_use]
+    pub fn timer(mut self, interval: Duration) -> Engine<S> {
+        self.timer = Some(Schedule::new(interval));
+        self
+    }
+
+    #[must_use]
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Whether the strategy asked to stop.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.state.stopped
+    }
+
+    // call a hook, unless the strategy stopped
+    fn hook(&mut self, hook: impl FnOnce(&mut S, &mut dyn Context)) {
+        if self.state.stopped {
+            return;
+        }
+        let mut ctx = Live { client: &self.client, state: &mut self.state };
+        hook(&mut self.strategy, &mut ctx);
+    }
+
+    /// Do everything that is due once, returning when the next thing will be.
+    pub fn step(&mut self) -> Instant {
+        if !self.started {
+            self.started = true;
+            self.hook(|strategy, ctx| strategy.on_start(ctx));
+        }
+        let next_poll = self.poller.poll_once();
+        while let Ok(event) = self.events.try_recv() {
+            self.dispatch(event);
+        }
+
+        let now = Instant::now();
+        if self.account.as_mut().is_some_and(|schedule| schedule.take(now)) {
+            self.refresh_accounts();
+        }
+        if self.orders.as_mut().is_some_and(|schedule| schedule.take(now)) {
+            self.refresh_open_orders();
+        }
+        if self.timer.as_mut().is_some_and(|schedule| schedule.take(now)) {
+            self.hook(|strategy, ctx| strategy.on_timer(ctx));
+        }
+
+        [self.account, self.orders, self.timer]
+            .iter()
+            .flatten()
+            .map(|schedule| schedule.due)
+            .chain(next_poll)
+            .min()
+            .unwrap_or(now + Duration::from_secs(1))
+    }
+
+    fn dispatch(&mut self, event: MarketEvent) {
+        match event {
+            MarketEvent::Ticker { market, ticker } => {
+                self.state.tickers.insert(market.clone(), ticker.clone());
+                self.hook(|strategy, ctx| strategy.on_ticker(ctx, &market, &ticker));
+            }
+            MarketEvent::Depth { market, book, changes } => {
+                self.state.books.insert(market, book.clone());
+                self.hook(|strategy, ctx| strategy.on_depth(ctx, &book, &changes));
+            }
+            MarketEvent::Trades { market, trades } => {
+                for trade in &trades {
+                    self.hook(|strategy, ctx| strategy.on_trade(ctx, &market, trade));
+                }
+            }
+            MarketEvent::Kline { market, period, candles } => {
+                for candle in &candles {
+                    self.hook(|strategy, ctx| strategy.on_candle(ctx, &market, period, candle));
+                }
+            }
+        }
+    }
+
+    fn refresh_accounts(&mut self) {
+        match self.client.me() {
+            Ok(member) => {
+                self.state.accounts = member
+                    .accounts_filtered
+                    .iter()
+                    .map(|account| (account.currency.clone(), account.clone()))
+                    .collect();
+                self.hook(|strategy, ctx| strategy.on_account(ctx, &member));
+            }
+            Err(e) => self.hook(|strategy, ctx| strategy.on_error(ctx, &e)),
+        }
+    }
+
+    fn refresh_open_orders(&mut self) {
+        let mut markets = self.markets.clone();
+        let known = self.state.orders.values().chain(self.state.canceling.values());
+        markets.extend(known.map(|order| order.market.clone()));
+        for market in markets {
+            let open = match self.client.orders_get(&OrdersQuery::new().market(&market)) {
+                Ok(open) => open,
+                Err(e) => {
+                    self.hook(|strategy, ctx| strategy.on_error(ctx, &e));
+                    continue;
+                }
+            };
+            let mut updates = Vec::new();
+            let ids: HashSet<usize> = open.iter().map(|order| order.id).collect();
+            // canceled orders stay listed until the cancel goes through
+            for order in open.into_iter().filter(|order| !self.state.canceling.contains_key(&order.id)) {
+                if self.state.orders.get(&order.id).is_none_or(|known| changed(known, &order)) {
+                    self.state.orders.insert(order.id, order.clone());
+                    updates.push(order);
+                }
+            }
+            // off the book since last time, so filled or canceled
+            let gone: Vec<usize> = self
+                .state
+                .orders
+                .values()
+                .chain(self.state.canceling.values())
+                .filter(|order| order.market == market && !ids.contains(&order.id))
+                .map(|order| order.id)
+                .collect();
+            for id in gone {
+                match self.client.order(id) {
+                    Ok(order) => {
+                        self.state.orders.remove(&id);
+                        self.state.canceling.remove(&id);
+                        updates.push(order);
+                    }
+                    Err(e) => self.hook(|strategy, ctx| strategy.on_error(ctx, &e)),
+                }
+            }
+            for order in updates {
+                self.hook(|strategy, ctx| strategy.on_order_update(ctx, &order));
+            }
+        }
+    }
+
+    // the last hook, called whether or not the strategy stopped itself
+    fn finish(&mut self) {
+        let mut ctx = Live { client: &self.client, state: &mut self.state };
+        self.strategy.on_stop(&mut ctx);
+    }
+
+    /// Run until the strategy stops, returning it.
+    pub fn run(mut self) -> S {
+        while !self.state.stopped {
+            let next = self.step();
+            if !self.state.stopped {
+                thread::sleep(next.saturating_duration_since(Instant::now()));
+            }
+        }
+        self.finish();
+        self.strategy
+    }
+}
+
+impl<S: Strategy + Send + 'static> Engine<S> {
+    /// Run on a thread of its own until the strategy stops or the handle
+    /// is stopped or dropped.
+    #[must_use]
+    pub fn start(mut self) -> EngineHandle<S> {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            while !self.state.stopped {
+                let wait = self.step().saturating_duration_since(Instant::now());
+                match stopped.recv_timeout(wait) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    _ => break,
+                }
+            }
+            self.finish();
+            self.strategy
+        });
+        EngineHandle { stop, thread: Some(thread) }
+    }
+}
+
+/// An `Engine` running on its own thread, stopped when dropped.
+#[derive(Debug)]
+pub struct EngineHandle<S> {
+    stop: Sender<()>,
+    thread: Option<JoinHandle<S>>,
+}
+
+impl<S> EngineHandle<S> {
+    /// Stop the engine and wait for it, handing back the strategy unless
+    /// one of its hooks panicked.
+    pub fn stop(mut self) -> Option<S> {
+        let _ = self.stop.send(());
+        self.thread.take()?.join().ok()
+    }
+
+    /// Whether the engine stopped, because the strategy asked it to.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+}
+
+impl<S> Drop for EngineHandle<S> {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
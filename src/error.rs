@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use crate::registry::RegistryError;
+use crate::tonce::{TONCE_INVALID, TONCE_USED};
+use crate::validation::ValidationError;
+
+/// Everything that can go wrong talking to graviex.
+#[derive(Debug)]
+pub enum GraviexError {
+    /// The request never completed: dns, tls, timeouts, dropped connections.
+    Transport(minreq::Error),
+    /// Graviex answered with a non-2xx status and no error body we understand.
+    Status { status: u16, body: String },
+    /// Graviex rejected the request with an error body like
+    /// `{"error":{"code":2002,"message":"Failed to create order."}}`.
+    Api { code: i64, message: String },
+    /// Graviex rejected our credentials, signature or tonce.
+    Auth { code: i64, message: String },
+    /// The response body was not the json we expected.
+    Decode { source: serde_json::Error, body: String },
+    /// The client's own rate limiter held the request back. Nothing was sent.
+    RateLimited { retry_after: Duration },
+    /// The local order registry refused the call or couldn't be saved.
+    Registry(RegistryError),
+    /// The order breaks its market's rules and was never sent.
+    Validation(ValidationError),
+}
+
+impl GraviexError {
+    /// Turn a response into the error it describes, if it describes one.
+    #[must_use]
+    pub fn from_response(status: u16, body: &str) -> Option<GraviexError> {
+        if let Some((code, message)) = api_error(body) {
+            return Some(if is_auth_code(code) {
+                GraviexError::Auth { code, message }
+            } else {
+                GraviexError::Api { code, message }
+            });
+        }
+        if (200..300).contains(&status) {
+            return None;
+        }
+        Some(GraviexError::Status { status, body: body.to_string() })
+    }
+
+    /// The graviex error code, for `Api` and `Auth` errors.
+    #[must_use]
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            GraviexError::Api { code, .. } | GraviexError::Auth { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Whether graviex refused the request because of its tonce.
+    #[must_use]
+    pub fn is_tonce_error(&self) -> bool {
+        matches!(self.code(), Some(TONCE_USED | TONCE_INVALID))
+    }
+}
+
+impl fmt::Display for GraviexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraviexError::Transport(e) => write!(f, "transport error: {}", e),
+            GraviexError::Status { status, body } => write!(f, "http status {}: {}", status, body),
+            GraviexError::Api { code, message } => write!(f, "graviex error {}: {}", code, message),
+            GraviexError::Auth { code, message } => write!(f, "graviex auth error {}: {}", code, message),
+            GraviexError::Decode { source, body } => write!(f, "could not decode {:?}: {}", body, source),
+            GraviexError::RateLimited { retry_after } => write!(f, "rate limited, retry in {:?}", retry_after),
+            GraviexError::Registry(e) => write!(f, "{}", e),
+            GraviexError::Validation(e) => write!(f, "invalid order: {}", e),
+        }
+    }
+}
+
+impl Error for GraviexError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GraviexError::Transport(e) => Some(e),
+            GraviexError::Decode { source, .. } => Some(source),
+            GraviexError::Registry(e) => Some(e),
+            GraviexError::Validation(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<minreq::Error> for GraviexError {
+    fn from(e: minreq::Error) -> GraviexError {
+        GraviexError::Transport(e)
+    }
+}
+
+impl From<RegistryError> for GraviexError {
+    fn from(e: RegistryError) -> GraviexError {
+        GraviexError::Registry(e)
+    }
+}
+
+impl From<ValidationError> for GraviexError {
+    fn from(e: ValidationError) -> GraviexError {
+        GraviexError::Validation(e)
+    }
+}
+
+/// Deserialize `body` into `T`, keeping the body around if that fails.
+///
+/// # Errors
+/// returns `GraviexError::Decode` if `body` doesn't match `T`
+pub fn decode<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, GraviexError> {
+    serde_json::from_str(body).map_err(|source| GraviexError::Decode {
+        source,
+        body: body.to_string(),
+    })
+}
+
+// pull code and message out of {"error":{"code":2005,"message":"..."}}
+fn api_error(body: &str) -> Option<(i64, String)> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let error = value.get("error")?;
+    let code = error.get("code")?.as_i64()?;
+    let message = error.get("message").and_then(|m| m.as_str()).unwrap_or_default();
+    Some((code, message.to_string()))
+}
+
+// 2001 authorization failed, 2005 bad signature, 2006 and 2007 bad tonce,
+// 2008 through 2011 unknown, disabled, expired or out of scope access key
+fn is_auth_code(code: i64) -> bool {
+    code == 2001 || (2005..=2011).contains(&code)
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+use crate::decimal::{round_down, round_up};
+use crate::*;
+
+/// A quantity of one currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Amount {
+    pub value: Decimal,
+    pub currency: String, // like "btc"
+}
+
+impl Amount {
+    fn new(value: Decimal, currency: &str) -> Amount {
+        Amount { value, currency: currency.to_string() }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.currency)
+    }
+}
+
+/// What an order costs and brings in once it fully fills.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderCost {
+    pub spent: Amount, // quote for buys, base for sells
+    pub gross: Amount, // what the fill pays out before the fee
+    pub fee: Amount, // taken out of `gross`, so in the same currency
+    pub net: Amount, // what actually lands in the account
+    pub break_even: Decimal, // price the opposite order needs to get `spent` back
+}
+
+/// The fees on one market. Graviex takes its fee out of what each side
+/// receives: buyers pay `buy_fee` in the base currency, sellers pay
+/// `sell_fee` in the quote currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeSchedule {
+    pub market: String, // like "ethbtc"
+    pub base_unit: String, // like "eth"
+    pub quote_unit: String, // like "btc"
+    pub buy_fee: Decimal, // fraction, like 0.002
+    pub sell_fee: Decimal, // fraction, like 0.002
+    pub price_fixed: usize, // decimals break even prices are rounded to
+}
+
+impl FeeSchedule {
+    /// The fees from a ticker, `base_fee` for buys and `quote_fee` for sells.
+    #[must_use]
+    pub fn from_ticker(market: &str, ticker: &Ticker) -> FeeSchedule {
+        FeeSchedule {
+            market: market.to_string(),
+            base_unit: ticker.base_unit.clone(),
+            quote_unit: ticker.quote_unit.clone(),
+            buy_fee: ticker.base_fee,
+            sell_fee: ticker.quote_fee,
+            price_fixed: ticker.quote_fixed,
+        }
+    }
+
+    /// The fees from a market's attributes, the bid fee for buys and the
+    /// ask fee for sells.
+    #[must_use]
+    pub fn from_market(market: &Market) -> FeeSchedule {
+        let attributes = &market.attributes;
+        FeeSchedule {
+            market: attributes.id.clone(),
+            base_unit: attributes.base_unit.clone(),
+            quote_unit: attributes.quote_unit.clone(),
+            buy_fee: attributes.bid.fee,
+            sell_fee: attributes.ask.fee,
+            price_fixed: attributes.bid.fixed,
+        }
+    }
+
+    /// The fee fraction `side` pays.
+    #[must_use]
+    pub fn fee(&self, side: Side) -> Decimal {
+        match side {
+            Side::Buy => self.buy_fee,
+            Side::Sell => self.sell_fee,
+        }
+    }
+
+    /// What an order to `side` `volume` at `price` costs and brings in.
+    #[must_use]
+    pub fn cost(&self, side: Side, price: Decimal, volume: Decimal) -> OrderCost {
+        let total = price * volume;
+        let (spent, gross, currency) = match side {
+            Side::Buy => (Amount::new(total, &self.quote_unit), volume, &self.base_unit),
+            Side::Sell => (Amount::new(volume, &self.base_unit), total, &self.quote_unit),
+        };
+        let fee = gross * self.fee(side);
+        OrderCost {
+            spent,
+            gross: Amount::new(gross, currency),
+            fee: Amount::new(fee, currency),
+            net: Amount::new(gross - fee, currency),
+            break_even: self.break_even(side, price),
+        }
+    }
+
+    /// The price an order on the other side needs, after filling a `side`
+    /// order at `price`, to end up where we started once both fees are paid.
+    /// Buying at `p` needs a sale at `p / ((1 - buy_fee) * (1 - sell_fee))`
+    /// or more, selling at `p` a buy back at `p * (1 - sell_fee) * (1 - buy_fee)`
+    /// or less. Rounded to `price_fixed` decimals on the safe side.
+    #[must_use]
+    pub fn break_even(&self, side: Side, price: Decimal) -> Decimal {
+        let kept = (Decimal::ONE - self.buy_fee) * (Decimal::ONE - self.sell_fee);
+        match side {
+            Side::Buy if kept > Decimal::ZERO => round_up(price / kept, self.price_fixed),
+            Side::Buy => Decimal::MAX,
+            Side::Sell => round_down(price * kept, self.price_fixed),
+        }
+    }
+}
+
+impl GraviexClient {
+    /// The fees on `market`, from its ticker.
+    ///
+    /// # Errors
+    /// returns `GraviexError` if the ticker can't be fetched
+    pub fn fee_schedule(&self, market: &str) -> Result<FeeSchedule, GraviexError> {
+        Ok(FeeSchedule::from_ticker(market, &self.ticker(market)?))
+    }
+}
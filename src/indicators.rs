@@ -0,0 +1,383 @@
+//! Technical indicators over k line bars.
+//!
+//! Every indicator takes bars one at a time through `Indicator::next`, so
+//! it can follow a live feed, and `Indicator::batch` runs it over history.
+//! Values are `None` until there are enough bars. Indicators of a single
+//! price read the close, or any other series through `update`.
+
+use std::collections::VecDeque;
+
+use rust_decimal::MathematicalOps;
+
+use crate::resample::Timeframe;
+use crate::*;
+
+/// Something computed bar by bar.
+pub trait Indicator {
+    type Output;
+
+    /// Take the next bar, returning the value once there is enough history.
+    fn next(&mut self, candle: &Candle) -> Option<Self::Output>;
+
+    /// Forget every bar seen so far.
+    fn reset(&mut self);
+
+    /// The value after each of `candles`, carrying on from any bars
+    /// already seen.
+    fn batch(&mut self, candles: &[Candle]) -> Vec<Option<Self::Output>>
+    where
+        Self: Sized,
+    {
+        candles.iter().map(|candle| self.next(candle)).collect()
+    }
+}
+
+// periods are counts, at least one
+fn count(period: usize) -> Decimal {
+    Decimal::from(period.max(1))
+}
+
+/// Simple moving average of the last `period` closes.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<Decimal>,
+    sum: Decimal,
+}
+
+impl Sma {
+    #[must_use]
+    pub fn new(period: usize) -> Sma {
+        Sma { period: period.max(1), window: VecDeque::new(), sum: Decimal::ZERO }
+    }
+
+    pub fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        (self.window.len() == self.period).then(|| self.sum / count(self.period))
+    }
+}
+
+impl Indicator for Sma {
+    type Output = Decimal;
+
+    fn next(&mut self, candle: &Candle) -> Option<Decimal> {
+        self.update(candle.close)
+    }
+
+    fn reset(&mut self) {
+        *self = Sma::new(self.period);
+    }
+}
+
+/// Exponential moving average weighting the newest close by
+/// 2 / (`period` + 1), seeded with the simple average of the first
+/// `period` closes.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    seed: Sma,
+    value: Option<Decimal>,
+}
+
+impl Ema {
+    #[must_use]
+    pub fn new(period: usize) -> Ema {
+        Ema { period: period.max(1), seed: Sma::new(period), value: None }
+    }
+
+    pub fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        self.value = match self.value {
+            Some(previous) => Some(previous + (value - previous) * Decimal::TWO / (count(self.period) + Decimal::ONE)),
+            None => self.seed.update(value),
+        };
+        self.value
+    }
+}
+
+impl Indicator for Ema {
+    type Output = Decimal;
+
+    fn next(&mut self, candle: &Candle) -> Option<Decimal> {
+        self.update(candle.close)
+    }
+
+    fn reset(&mut self) {
+        *self = Ema::new(self.period);
+    }
+}
+
+// Wilder's smoothing: a simple average of the first `period` values,
+// then each new one weighted 1 / `period`
+#[derive(Debug, Clone)]
+struct Wilder {
+    period: usize,
+    seed: Sma,
+    value: Option<Decimal>,
+}
+
+impl Wilder {
+    fn new(period: usize) -> Wilder {
+        Wilder { period: period.max(1), seed: Sma::new(period), value: None }
+    }
+
+    fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        let period = count(self.period);
+        self.value = match self.value {
+            Some(previous) => Some((previous * (period - Decimal::ONE) + value) / period),
+            None => self.seed.update(value),
+        };
+        self.value
+    }
+}
+
+/// Wilder's relative strength index of the closes, from 0 to 100.
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    previous: Option<Decimal>,
+    gains: Wilder,
+    losses: Wilder,
+}
+
+impl Rsi {
+    #[must_use]
+    pub fn new(period: usize) -> Rsi {
+        Rsi { period, previous: None, gains: Wilder::new(period), losses: Wilder::new(period) }
+    }
+
+    pub fn update(&mut self, value: Decimal) -> Option<Decimal> {
+        let change = value - self.previous.replace(value)?;
+        let gain = self.gains.update(change.max(Decimal::ZERO));
+        let loss = self.losses.update((-change).max(Decimal::ZERO));
+        let (gain, loss) = (gain?, loss?);
+        Some(if loss.is_zero() {
+            // nothing but gains, or nothing at all
+            if gain.is_zero() { Decimal::from(50) } else { Decimal::ONE_HUNDRED }
+        } else {
+            Decimal::ONE_HUNDRED - Decimal::ONE_HUNDRED / (Decimal::ONE + gain / loss)
+        })
+    }
+}
+
+impl Indicator for Rsi {
+    type Output = Decimal;
+
+    fn next(&mut self, candle: &Candle) -> Option<Decimal> {
+        self.update(candle.close)
+    }
+
+    fn reset(&mut self) {
+        *self = Rsi::new(self.period);
+    }
+}
+
+/// One reading of `Macd`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacdValue {
+    pub macd: Decimal, // fast ema less slow ema
+    pub signal: Decimal, // ema of `macd`
+    pub histogram: Decimal, // `macd` less `signal`
+}
+
+/// Moving average convergence divergence of the closes, like
+/// `Macd::new(12, 26, 9)`.
+#[derive(Debug, Clone)]
+pub struct Macd {
+    periods: (usize, usize, usize),
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    #[must_use]
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Macd {
+        Macd { periods: (fast, slow, signal), fast: Ema::new(fast), slow: Ema::new(slow), signal: Ema::new(signal) }
+    }
+
+    pub fn update(&mut self, value: Decimal) -> Option<MacdValue> {
+        let (fast, slow) = (self.fast.update(value), self.slow.update(value));
+        let macd = fast? - slow?;
+        let signal = self.signal.update(macd)?;
+        Some(MacdValue { macd, signal, histogram: macd - signal })
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdValue;
+
+    fn next(&mut self, candle: &Candle) -> Option<MacdValue> {
+        self.update(candle.close)
+    }
+
+    fn reset(&mut self) {
+        let (fast, slow, signal) = self.periods;
+        *self = Macd::new(fast, slow, signal);
+    }
+}
+
+/// One reading of `Bollinger`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bands {
+    pub upper: Decimal,
+    pub middle: Decimal, // simple moving average
+    pub lower: Decimal,
+}
+
+/// Bollinger bands `width` standard deviations either side of the simple
+/// moving average of the closes, like `Bollinger::new(20, Decimal::TWO)`.
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    width: Decimal,
+    window: VecDeque<Decimal>,
+    sma: Sma,
+}
+
+impl Bollinger {
+    #[must_use]
+    pub fn new(period: usize, width: Decimal) -> Bollinger {
+        Bollinger { width, window: VecDeque::new(), sma: Sma::new(period) }
+    }
+
+    pub fn update(&mut self, value: Decimal) -> Option<Bands> {
+        self.window.push_back(value);
+        if self.window.len() > self.sma.period {
+            self.window.pop_front();
+        }
+        let middle = self.sma.update(value)?;
+        // of the whole window, not a sample of it
+        let variance = self.window.iter().map(|value| (value - middle) * (value - middle)).sum::<Decimal>()
+            / count(self.window.len());
+        let spread = variance.sqrt().unwrap_or_default() * self.width;
+        Some(Bands { upper: middle + spread, middle, lower: middle - spread })
+    }
+}
+
+impl Indicator for Bollinger {
+    type Output = Bands;
+
+    fn next(&mut self, candle: &Candle) -> Option<Bands> {
+        self.update(candle.close)
+    }
+
+    fn reset(&mut self) {
+        *self = Bollinger::new(self.sma.period, self.width);
+    }
+}
+
+/// Wilder's average true range, the smoothed size of a bar including any
+/// jump from the previous close.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    close: Option<Decimal>,
+    average: Wilder,
+}
+
+impl Atr {
+    #[must_use]
+    pub fn new(period: usize) -> Atr {
+        Atr { period, close: None, average: Wilder::new(period) }
+    }
+}
+
+impl Indicator for Atr {
+    type Output = Decimal;
+
+    fn next(&mut self, candle: &Candle) -> Option<Decimal> {
+        let range = candle.high - candle.low;
+        let true_range = match self.close.replace(candle.close) {
+            Some(close) => range.max((candle.high - close).abs()).max((candle.low - close).abs()),
+            None => range,
+        };
+        self.average.update(true_range)
+    }
+
+    fn reset(&mut self) {
+        *self = Atr::new(self.period);
+    }
+}
+
+/// Volume weighted average of the typical price, the mean of high, low
+/// and close, since the first bar or the start of each session.
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    session: Option<Timeframe>,
+    started: Option<usize>, // start of the current session
+    value: Decimal, // typical price times volume, summed
+    volume: Decimal,
+}
+
+impl Vwap {
+    #[must_use]
+    pub fn new() -> Vwap {
+        Vwap::default()
+    }
+
+    /// Start over with every bar of `session`, like `Timeframe::days(1)`
+    /// for a daily vwap.
+    #[must_use]
+    pub fn session(session: Timeframe) -> Vwap {
+        Vwap { session: Some(session), ..Vwap::default() }
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = Decimal;
+
+    fn next(&mut self, candle: &Candle) -> Option<Decimal> {
+        if let Some(session) = self.session {
+            let start = session.start_of(candle.timestamp);
+            if self.started.replace(start) != Some(start) {
+                self.value = Decimal::ZERO;
+                self.volume = Decimal::ZERO;
+            }
+        }
+        let typical = (candle.high + candle.low + candle.close) / Decimal::from(3);
+        self.value += typical * candle.volume;
+        self.volume += candle.volume;
+        (!self.volume.is_zero()).then(|| self.value / self.volume)
+    }
+
+    fn reset(&mut self) {
+        *self = Vwap { session: self.session, ..Vwap::default() };
+    }
+}
+
+/// On balance volume: the running total of volume, added on bars closing
+/// higher and taken away on bars closing lower.
+#[derive(Debug, Clone, Default)]
+pub struct Obv {
+    close: Option<Decimal>,
+    total: Decimal,
+}
+
+impl Obv {
+    #[must_use]
+    pub fn new() -> Obv {
+        Obv::default()
+    }
+}
+
+impl Indicator for Obv {
+    type Output = Decimal;
+
+    fn next(&mut self, candle: &Candle) -> Option<Decimal> {
+        if let Some(close) = self.close.replace(candle.close) {
+            if candle.close > close {
+                self.total += candle.volume;
+            } else if candle.close < close {
+                self.total -= candle.volume;
+            }
+        }
+        Some(self.total)
+    }
+
+    fn reset(&mut self) {
+        *self = Obv::default();
+    }
+}
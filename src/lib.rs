@@ -1,21 +1,69 @@
-use std::time::{SystemTime, UNIX_EPOCH};
 use std::collections::HashMap;
-use std::error::Error;
+use std::env;
+use std::fmt;
 use std::str;
-use std::hash::BuildHasher;
-use itertools::Itertools;
-use sha2::Sha256;
-// use hmac::{Hmac, Mac, NewMac};
-use hmac::{Hmac, Mac};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 extern crate serde;
 use serde::{Serialize, Deserialize};
 
-include!("creds.rs");
-include!("test.rs");
-
-
-static mut SEED: usize = 0;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod backtest;
+pub mod book;
+pub mod candles;
+pub mod decimal;
+pub mod engine;
+pub mod error;
+pub mod fees;
+pub mod indicators;
+pub mod logging;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod params;
+pub mod placement;
+pub mod poller;
+pub mod ratelimit;
+pub mod registry;
+pub mod resample;
+pub mod retry;
+pub mod signature;
+pub mod strategy;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod time;
+pub mod tonce;
+pub mod transport;
+pub mod validation;
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncGraviexClient;
+pub use backtest::{Backtest, Dataset, DatasetError, Fill, Report, RoundTrip, Series, Snapshot};
+pub use book::{Level, LevelChange, OrderBookState};
+pub use candles::{merge_pending, Direction, KlinePages, KlineRange};
+pub use decimal::Decimal;
+pub use engine::{Engine, EngineHandle};
+pub use error::GraviexError;
+pub use fees::{Amount, FeeSchedule, OrderCost};
+pub use indicators::{Atr, Bands, Bollinger, Ema, Indicator, Macd, MacdValue, Obv, Rsi, Sma, Vwap};
+pub use params::*;
+pub use placement::{Placement, PlacementPolicy};
+pub use poller::{MarketEvent, Poller, PollerHandle, Topic};
+pub use ratelimit::{Access, Budget, LimitPolicy, RateLimiter};
+pub use registry::{OrderRegistry, RegistryError, TrackedOrder, TrackedState};
+pub use resample::{bars_from_trades, resample, Bar, ResampleError, Resampler, Tick, Timeframe};
+pub use retry::RetryPolicy;
+// `Strategy` at the root is graviex's own, the trait stays in its module
+pub use strategy::Context;
+#[cfg(feature = "stream")]
+pub use stream::{Stream, StreamClient, StreamError, StreamEvent, StreamHandle};
+use error::decode;
+use tonce::TonceGenerator;
+pub use transport::{HttpRequest, HttpResponse, Method, MinreqTransport, Transport};
+pub use validation::{MarketRules, ValidationError, Validator};
+
+/// Default location of the graviex api.
+pub const GRAVIEX_URL: &str = "https://graviex.net";
 
 /// These structs are response formats from graviex's api.
 /// We use them so we can more easily deserialize responses with serde.
@@ -34,8 +82,8 @@ pub struct Depth {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TinyOrder {
-    pub price: String,
-    pub vol: String,
+    pub price: Decimal,
+    pub vol: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -59,18 +107,18 @@ pub struct Attributes {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Bid {
-    pub fee: f64,
+    pub fee: Decimal,
     pub currency: String,
     pub fixed: usize,
-    pub lot: f64,
+    pub lot: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ask {
-    pub fee: f64,
+    pub fee: Decimal,
     pub currency: String,
     pub fixed: usize,
-    pub lot: f64,
+    pub lot: Decimal,
 }
 
 
@@ -80,30 +128,30 @@ pub struct Ticker {
     pub name: String, // like "GIO/BTC"
     pub base_unit: String, // like "gio"
     pub base_fixed: usize, // number of decimal places considered
-    pub base_fee: f64, // fee, typically 0.002, percentual
+    pub base_fee: Decimal, // fee, typically 0.002, percentual
     pub quote_unit: String, // like "btc"
     pub quote_fixed: usize, // number of decimal places considered
-    pub quote_fee: f64, // fee, typically 0.002, percentual
+    pub quote_fee: Decimal, // fee, typically 0.002, percentual
     pub api: bool, // whether accessible via api or not
-    pub base_lot: Option<f64>, // no idea what this is for, int or null typically
-    pub quote_lot: Option<f64>, // no idea what this is for, int or null typically
-    pub base_min: String, // minimum fee we must exceed to have a valid trade
-    pub quote_min: String, // minimum fee we must exceed to have a valid trade
+    pub base_lot: Option<Decimal>, // no idea what this is for, int or null typically
+    pub quote_lot: Option<Decimal>, // no idea what this is for, int or null typically
+    pub base_min: Decimal, // minimum fee we must exceed to have a valid trade
+    pub quote_min: Decimal, // minimum fee we must exceed to have a valid trade
     pub blocks: usize, // perhaps having to due with number of orders waiting, or chain stats
     pub block_time: String, // time like "2021-07-12 12:49:13", might be empty ""
     pub wstatus: String, // on or off
-    pub low: String, // lowest price in last 24h
-    pub high: String, // highest price in last 24h
-    pub last: String, // last price
-    pub open: String, // currently available price
-    pub volume: String, // trade volume in last 24h
-    pub volume2: String, // second coin volume in 24h
-    pub sell: String, // available buy price
-    pub buy: String, // avaiable sell price
+    pub low: Decimal, // lowest price in last 24h
+    pub high: Decimal, // highest price in last 24h
+    pub last: Decimal, // last price
+    pub open: Decimal, // currently available price
+    pub volume: Decimal, // trade volume in last 24h
+    pub volume2: Decimal, // second coin volume in 24h
+    pub sell: Decimal, // available buy price
+    pub buy: Decimal, // avaiable sell price
     pub at: usize, // unix timestamp of data like 1626125887
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Member {
     pub sn: String, // unique identifier of user
     pub name: Option<String>, // username
@@ -112,35 +160,38 @@ pub struct Member {
     pub accounts_filtered: Vec<Account>, // user's accounts info
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Account {
     pub currency: String, // account type like btc or usd
-    pub balance: String, // excludes locked funds
-    pub locked: String, // locked funds
+    pub balance: Decimal, // excludes locked funds
+    pub locked: Decimal, // locked funds
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Order {
     pub id: usize, // unique order ID
     pub side: String, // buy or sell
-    pub price: String, // order price,
-    pub avg_price: String, // average execution price
+    pub price: Decimal, // order price,
+    pub avg_price: Decimal, // average execution price
     pub state: String, // wait, done, or cancel
     pub market: String, // which market the order belongs to
     pub created_at: String, // 2014-04-18T02:02:33Z formatted creation date
-    pub volume: String, // volume to buy/sell, == remaining_volume + executed_volume
-    pub remaining_volume: String, // remaining volume, always <= to volume
-    pub executed_volume: String, // fulfilled volume, always <= volume
+    pub volume: Decimal, // volume to buy/sell, == remaining_volume + executed_volume
+    pub remaining_volume: Decimal, // remaining volume, always <= to volume
+    pub executed_volume: Decimal, // fulfilled volume, always <= volume
     pub trades: Option<Vec<Trade>>, // the order's trade history. only some results have
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Trade {
     pub id: usize, // unique ID
-    pub price: String, // trade pricec
-    pub volume: String, // trade volume
+    pub price: Decimal, // trade pricec
+    pub volume: Decimal, // trade volume
+    pub funds: Option<Decimal>, // price * volume
     pub market: String, // like btcusd
     pub created_at: String, // time formatted like 2014-04-18T02:02:33Z
+    pub side: Option<String>, // buy or sell, only on your own trades
+    pub order_id: Option<usize>, // your order behind the trade, only on your own trades
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -149,802 +200,865 @@ pub struct OrderBook {
     pub bids: Vec<Order>,
 }
 
-fn graviex_handler<S: BuildHasher>(
-req_method: &str,
-api_target: &str,
-data: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SimpleTrade {
+    pub tid: usize, // unique trade ID
+    pub price: Decimal, // trade price
+    pub amount: Decimal, // trade volume
+    pub date: usize, // unix timestamp of the trade
+    #[serde(rename = "type")]
+    pub side: String, // buy or sell
+}
+
+/// One k line bar, sent by graviex as `[timestamp, open, high, low, close, volume]`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Candle {
+    pub timestamp: usize, // unix timestamp of the start of the bar
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal, // volume traded during the bar, in base units
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KlineWithPending {
+    pub k: Vec<Candle>, // finished bars
+    pub trades: Vec<Trade>, // trades not yet folded into a bar
+}
 
-    type HmacSha256 = Hmac<Sha256>;
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Deposit {
+    pub id: usize, // unique deposit ID
+    pub currency: String, // like btc
+    pub amount: Decimal, // deposited amount
+    pub fee: Option<Decimal>, // fee taken by graviex
+    pub txid: Option<String>, // blockchain transaction id
+    pub confirmations: Option<usize>, // blockchain confirmations so far
+    pub state: String, // like submitted, accepted, rejected
+    pub created_at: String, // 2014-04-18T02:02:33Z formatted creation date
+    pub done_at: Option<String>, // when the deposit was credited, if it was
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Withdraw {
+    pub id: usize, // unique withdraw ID
+    pub currency: String, // like btc
+    pub amount: Decimal, // withdrawn amount, excludes fee
+    pub fee: Option<Decimal>, // fee taken by graviex
+    pub fund_uid: Option<String>, // destination address
+    pub fund_extra: Option<String>, // label of the destination address
+    pub txid: Option<String>, // blockchain transaction id, once sent
+    pub state: String, // like submitted, accepted, processing, done, canceled
+    pub created_at: String, // 2014-04-18T02:02:33Z formatted creation date
+    pub done_at: Option<String>, // when the withdraw was sent, if it was
+}
 
-    // populate q with any values that were passed via data arg
-    // this step is necessary because sometimes data will be empty
-    let mut q: HashMap<&str, &str> = HashMap::new();
-    for (key, value) in data {
-        q.insert(key, value);
+/// A deposit or withdrawal from `history`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HistoryEntry {
+    pub id: usize, // deposit or withdraw ID
+    #[serde(rename = "type")]
+    pub kind: String, // deposit or withdrawal
+    pub currency: String, // like btc
+    pub amount: Decimal, // moved amount
+    pub fee: Option<Decimal>, // fee taken by graviex
+    pub txid: Option<String>, // blockchain transaction id
+    pub state: String, // state of the deposit or withdraw
+    pub created_at: String, // 2014-04-18T02:02:33Z formatted creation date
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DepositAddress {
+    pub currency: String, // like btc
+    pub address: Option<String>, // empty while graviex is still generating it
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FundSource {
+    pub id: usize, // unique fund source ID, used by fund_source_remove
+    pub currency: String, // like btc
+    pub uid: String, // withdraw address
+    pub extra: String, // label of the address
+}
+
+/// Graviex doesn't document this one, so anything beyond the
+/// usual fields ends up in `extra`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrencyInfo {
+    pub code: Option<String>, // like btc
+    pub name: Option<String>, // like Bitcoin
+    pub fixed: Option<usize>, // number of decimal places considered
+    pub withdraw_fee: Option<String>, // flat fee taken on withdraws
+    pub min_withdraw: Option<String>, // smallest withdraw accepted
+    pub confirmations: Option<usize>, // confirmations needed for deposits
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Trading strategy published on graviex.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Strategy {
+    pub id: usize, // unique strategy ID
+    pub name: String, // display name
+    pub description: Option<String>, // what the strategy does
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Web ui settings, see `settings_store` for the known keys.
+/// Favorite markets show up in `extra` as `"fav-vrscbtc": true`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub darkmode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub was_quick_tour: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_favorites: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markets_filter: Option<String>, // defaults to "all"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candlestick_scale: Option<String>, // defaults to 1D
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candlestick_timezone: Option<String>, // defaults to "exchange"
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+// the body of a response, or the error graviex put in it
+fn check_response(response: HttpResponse) -> Result<String, GraviexError> {
+    match GraviexError::from_response(response.status, &response.body) {
+        Some(e) => Err(e),
+        None => Ok(response.body),
     }
+}
 
-    // we're single threaded and using an unsafe block to edit a mutable static
-    // which should be memory safe
-    unsafe {
-        // get a unique ending to the unix timestamp
-        if SEED < 998 {
-            SEED += 1;
-        } else {
-            SEED = 0;
-        }
-        let mut adjusted_time = SEED.to_string();
-        while adjusted_time.len() < 3 {
-            adjusted_time.insert(0, '0');
-        }
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        let mut tonce = current_time.as_secs().to_string();
-        tonce.push_str(&adjusted_time);
-
-
-        // populate q with our tonce and access key
-        q.insert("tonce", &tonce);
-        q.insert("access_key", &GRAVIEX_KEY);
-
-        // populate query string and query dict with values from q
-        let mut query_string: String = String::new();
-        let mut query_dict: HashMap<&str, &str> = HashMap::new();
-        for (key, value) in q.iter().sorted() {
-            query_string.push_str(&key);
-            query_string.push('=');
-            query_string.push_str(&value);
-            query_string.push('&');
-            query_dict.insert(key, value);
+/// A graviex api v3 client.
+///
+/// Each client carries its own credentials, base url, timeout and tonce
+/// state, so several accounts can be driven from one process.
+pub struct GraviexClient {
+    key: String, // graviex access key
+    secret: String, // graviex secret key
+    base_url: String, // like "https://graviex.net"
+    timeout: u64, // request timeout in seconds
+    retry: RetryPolicy, // applied to GET requests only
+    rate_limiter: Option<Arc<RateLimiter>>, // paces requests when set
+    placement: PlacementPolicy, // for place_order_safely
+    registry: Option<Arc<Mutex<OrderRegistry>>>, // tracks our orders when set
+    validator: Option<Arc<Validator>>, // checks orders before they are sent when set
+    transport: Arc<dyn Transport>, // sends the requests, minreq by default
+    tonce: Arc<TonceGenerator>, // shared with every client using the same key
+}
+
+// keep credentials out of logs and panic messages
+impl fmt::Debug for GraviexClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraviexClient")
+            .field("key", &logging::REDACTED)
+            .field("secret", &logging::REDACTED)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("retry", &self.retry)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("placement", &self.placement)
+            .field("registry", &self.registry)
+            .field("validator", &self.validator)
+            .field("transport", &self.transport)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GraviexClient {
+    /// Create a client for the account owning `key` and `secret`.
+    #[must_use]
+    pub fn new(key: &str, secret: &str) -> GraviexClient {
+        GraviexClient {
+            key: key.to_string(),
+            secret: secret.to_string(),
+            base_url: GRAVIEX_URL.to_string(),
+            timeout: 2,
+            retry: RetryPolicy::default(),
+            rate_limiter: None,
+            placement: PlacementPolicy::default(),
+            registry: None,
+            validator: None,
+            transport: Arc::new(MinreqTransport),
+            tonce: TonceGenerator::for_key(key),
         }
+    }
 
-        // get rid of the trailing '&' on query_string
-        let query = if query_string.is_empty() {
-            // query string was empty, prevent neg index on empty string
-            "".to_string()
-        } else {
-            query_string.split_at(query_string.len() - 1).0.to_string()
+    /// Create a client from the `GRAVIEX_KEY` and `GRAVIEX_SECRET`
+    /// environment variables.
+    ///
+    /// # Errors
+    /// returns `env::VarError` if either variable is missing
+    pub fn from_env() -> Result<GraviexClient, env::VarError> {
+        let key = env::var("GRAVIEX_KEY")?;
+        let secret = env::var("GRAVIEX_SECRET")?;
+        Ok(GraviexClient::new(&key, &secret))
+    }
+
+    /// Send requests to `base_url` instead of `GRAVIEX_URL`.
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: &str) -> GraviexClient {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send requests to `scheme://host:port`, like `http`, `127.0.0.1` and
+    /// `Some(8080)` for a local stand-in. The port is left out when `None`.
+    #[must_use]
+    pub fn with_endpoint(self, scheme: &str, host: &str, port: Option<u16>) -> GraviexClient {
+        let base_url = match port {
+            Some(port) => format!("{}://{}:{}", scheme, host, port),
+            None => format!("{}://{}", scheme, host),
         };
+        self.with_base_url(&base_url)
+    }
 
-        // appease the API overlords with their message syntax
-        let mut message: String = String::new();
-        message.push_str(req_method);
-        message.push('|');
-        message.push_str(api_target);
-        message.push('|');
-        message.push_str(&query);
+    /// Send requests through `transport` instead of `MinreqTransport`.
+    #[must_use]
+    pub fn with_transport<T: Transport + 'static>(mut self, transport: T) -> GraviexClient {
+        self.transport = Arc::new(transport);
+        self
+    }
 
-        // give that bad boy some hmac signature action
-        // type HmacSha256 = Hmac<Sha256>;
-        let mut mac = HmacSha256::new_from_slice(GRAVIEX_SECRET.as_bytes())
-            .expect("HMAC can take key of any size");
-        mac.update(&message.as_bytes());
+    /// Give up on requests after `timeout` seconds, default is 2.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: u64) -> GraviexClient {
+        self.timeout = timeout;
+        self
+    }
 
-        // let sig = mac.finalize().into_bytes();
-        let signature = mac.finalize().into_bytes();
-        let sig = hex::encode(signature);
+    /// Retry GET requests according to `retry`, default is
+    /// `RetryPolicy::default()`. POSTs and `gen_deposit_address` are never
+    /// retried.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> GraviexClient {
+        self.retry = retry;
+        self
+    }
 
-        // add our signature to the end of the request
-        query_dict.insert("signature", &sig);
-        // println!("query_dict: {:?}", query_dict);
+    /// Pace requests with `rate_limiter`. Pass the same `Arc` to every
+    /// client that should share its budgets. Unlimited by default.
+    #[must_use]
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> GraviexClient {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
 
-        let mut url: String = "https://graviex.net".to_string();
-        url.push_str(api_target);
+    /// Settle ambiguous `place_order_safely` failures according to
+    /// `placement`, default is `PlacementPolicy::default()`.
+    #[must_use]
+    pub fn with_placement_policy(mut self, placement: PlacementPolicy) -> GraviexClient {
+        self.placement = placement;
+        self
+    }
 
+    /// Track orders in `registry`, keeping it up to date with every order
+    /// this client gets back from graviex. Pass the same `Arc` to every
+    /// client trading the account. None by default.
+    #[must_use]
+    pub fn with_registry(mut self, registry: Arc<Mutex<OrderRegistry>>) -> GraviexClient {
+        self.registry = Some(registry);
+        self
+    }
 
-        if req_method == "GET" {
-            // make a get request
-            let mut request = minreq::get(&url).with_timeout(2);
-            for (key, value) in &query_dict {
-                request = request.with_param((*key).to_string(), (*value).to_string());
-            }
-            let response = request.send()?;
+    /// Check every order against its market's rules with `validator`
+    /// before `orders_post` and `orders_multi` send it, so bad orders fail
+    /// without a round trip. Off by default.
+    #[must_use]
+    pub fn with_validator(mut self, validator: Arc<Validator>) -> GraviexClient {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// The access key this client signs requests with.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
 
-            return Ok(response.as_str()?.to_string());
+    /// The base url this client sends requests to.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
 
+    // send a request inside a logged span, returning the body or the
+    // error graviex put in it
+    fn send(&self, request: &HttpRequest) -> Result<String, GraviexError> {
+        let span = logging::request_span(request);
+        let _entered = span.enter();
+        let started = Instant::now();
+        let result = self.transport.send(request).and_then(|response| {
+            span.record("status", response.status);
+            check_response(response)
+        });
+        logging::finish(&span, started.elapsed(), &result);
+        result
+    }
+
+    // full url of an api target like "/webapi/v3/markets.json"
+    fn url(&self, api_target: &str) -> String {
+        let mut url: String = self.base_url.clone();
+        url.push_str(api_target);
+        url
+    }
+
+    // unsigned GET request for the public endpoints
+    fn public_get(&self, api_target: &str, data: Params) -> Result<String, GraviexError> {
+        let request = HttpRequest {
+            method: Method::Get,
+            url: self.url(api_target),
+            params: data,
+            timeout: self.timeout,
+        };
+        self.retry.run(|| {
+            self.throttle(Access::Public, api_target)?;
+            self.send(&request)
+        })
+    }
+
+    // wait for, or fail on, the rate limiter if there is one
+    fn throttle(&self, access: Access, api_target: &str) -> Result<(), GraviexError> {
+        match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.acquire(access, api_target),
+            None => Ok(()),
         }
+    }
+
+    /// Ask graviex for the current time and line our tonces up with it.
+    ///
+    /// # Errors
+    /// returns an error if the timestamp request fails
+    pub fn resync_tonce(&self) -> Result<(), GraviexError> {
+        let server_time = self.timestamp()?;
+        self.tonce.resync(server_time as u64);
+        Ok(())
+    }
 
-        // make a post request
-        let mut request = minreq::post(&url).with_timeout(2);
-        for (key, value) in &query_dict {
-            request = request.with_param((*key).to_string(), (*value).to_string());
+    fn graviex_handler(
+        &self,
+        req_method: &str,
+        api_target: &str,
+        data: Params)
+    -> Result<String, GraviexError> {
+        // GETs are safe to repeat, every attempt is signed with a fresh tonce
+        if req_method == "GET" {
+            self.retry.run(|| self.signed_with_resync(req_method, api_target, &data))
+        } else {
+            self.graviex_handler_once(req_method, api_target, data)
         }
-        let response = request.send()?;
-        return Ok(response.as_str()?.to_string());
+    }
 
+    // graviex_handler for requests never to be retried: POSTs, and the
+    // odd GET that changes something on graviex
+    fn graviex_handler_once(
+        &self,
+        req_method: &str,
+        api_target: &str,
+        data: Params)
+    -> Result<String, GraviexError> {
+        self.signed_with_resync(req_method, api_target, &data)
     }
-}
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn markets() -> Result<Vec<MarketList>, Box<dyn Error>> {
-    println!("markets() was called");
-    let response = minreq::get("https://graviex.net/webapi/v3/markets.json")
-        .send()?
-        .as_str()?
-        .to_string();
-    let result: Vec<MarketList> = serde_json::from_str(&response)?;
-    Ok(result)
+    // a request graviex rejected over its tonce never ran, so it can be
+    // repeated even when it was a POST
+    fn signed_with_resync(
+        &self,
+        req_method: &str,
+        api_target: &str,
+        data: &[(&'static str, String)])
+    -> Result<String, GraviexError> {
+        match self.signed_request(req_method, api_target, data) {
+            Err(e) if e.is_tonce_error() && self.resync_tonce().is_ok() => {
+                // our clock drifted from the server's, try once more on its time
+                tracing::info!(offset_ms = self.tonce.offset(), "tonce rejected, retrying on server time");
+                self.signed_request(req_method, api_target, data)
+            }
+            result => result,
+        }
+    }
 
-}
+    fn signed_request(
+        &self,
+        req_method: &str,
+        api_target: &str,
+        data: &[(&'static str, String)])
+    -> Result<String, GraviexError> {
+        // wait before taking a tonce, so it's fresh when it goes out
+        self.throttle(Access::Signed, api_target)?;
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn market(m: &str) -> Result<Market, Box<dyn Error>> {
-    println!("market({:?}) was called", &m);
-    let mut url: String = "https://graviex.net/webapi/v3/markets/".to_string();
-    url.push_str(m);
-    url.push_str(&".json");
-    let response = minreq::get(url)
-        .send()?
-        .as_str()?
-        .to_string();
-    let result: Market = serde_json::from_str(&response)?;
-    Ok(result)
-}
+        // populate q with any values that were passed via data arg,
+        // plus our tonce and access key
+        let mut q: Params = data.to_vec();
+        q.push(("tonce", self.tonce.next().to_string()));
+        q.push(("access_key", self.key.clone()));
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn tickers() -> Result<HashMap<String, Ticker>, Box<dyn Error>> {
-    println!("tickers() was called");
-    let response = minreq::get("https://graviex.net:443/webapi/v3/tickers.json")
-        .send()?
-        .as_str()?
-        .to_string();
-    let result: HashMap<String, Ticker> = serde_json::from_str(&response)?;
-    Ok(result)
-}
+        let sig = signature::sign(&self.secret, req_method, api_target, &mut q);
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn ticker(t: &str) -> Result<Ticker, Box<dyn Error>> {
-    println!("ticker({:?}) was called", &t);
-    let mut url: String = "https://graviex.net:443/webapi/v3/tickers/".to_string();
-    url.push_str(t);
-    url.push_str(&".json");
-    let response = minreq::get(url)
-        .send()?
-        .as_str()?
-        .to_string();
-    let result: Ticker = serde_json::from_str(&response)?;
-    Ok(result)
-}
+        // add our signature to the end of the request
+        q.push(("signature", sig));
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn me() -> Result<Member, Box<dyn Error>> {
-    println!("me() was called");
-    let response = graviex_handler(
-        "GET",
-        "/webapi/v3/members/me.json",
-        HashMap::new()
-    )?;
-    let result: Member = serde_json::from_str(&response)?;
-    Ok(result)
-}
+        let request = HttpRequest {
+            method: if req_method == "GET" { Method::Get } else { Method::Post },
+            url: self.url(api_target),
+            params: q,
+            timeout: self.timeout,
+        };
+        self.send(&request)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn register_device(device_id: &str) -> Result<String, minreq::Error> {
-    println!("register_device({:?}) was called", &device_id);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("device", &device_id);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/members/me/register_device.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn markets(&self) -> Result<Vec<MarketList>, GraviexError> {
+        let response = self.public_get("/webapi/v3/markets.json", Params::new())?;
+        let result: Vec<MarketList> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn history<S: BuildHasher>(map: HashMap<&str, &str, S>) -> Result<String, minreq::Error> {
-    // optional params:
-    // currency: str = any ticker name characters
-    // limit: usize = number of returned records, default is 100
-    // type: str = ["withdrawal", "deposit"]
-    // from: str = date/time
-    // to: str = date/time
-    // page: usize = specify page of paginated results
-    // order_by: str = orders results ["asc", "des"]
-    println!("history({:?}) was called", map);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/account/history.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn market(&self, m: &str) -> Result<Market, GraviexError> {
+        let api_target = format!("/webapi/v3/markets/{}.json", m);
+        let response = self.public_get(&api_target, Params::new())?;
+        let result: Market = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn deposits<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // optional params:
-    // currency: list (comma separated) = gio,btc,doge,lts,dev
-    // limit: usize = number of returned records, default 100
-    // sate: str = unknown, but likely "settled", "unlocked", etc
-    println!("deposits({:?}) was called", map);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/deposits.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn tickers(&self) -> Result<HashMap<String, Ticker>, GraviexError> {
+        let response = self.public_get("/webapi/v3/tickers.json", Params::new())?;
+        let result: HashMap<String, Ticker> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn deposit(txid: &str) -> Result<String, minreq::Error> {
-    // gets details of a specific deposit
-    println!("deposit({:?}) was called", &txid);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("txid", &txid);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/deposit.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn ticker(&self, t: &str) -> Result<Ticker, GraviexError> {
+        let api_target = format!("/webapi/v3/tickers/{}.json", t);
+        let response = self.public_get(&api_target, Params::new())?;
+        let result: Ticker = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn deposit_address(currency: &str) -> Result<String, minreq::Error> {
-    // gets your graviex deposit address for every coni in currency
-    println!("deposit_address({:?}) was called", &currency);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("currency", &currency);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/deposit_address.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn me(&self) -> Result<Member, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/members/me.json",
+            Params::new()
+        )?;
+        let result: Member = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn gen_deposit_address(currency: &str) -> Result<String, minreq::Error> {
-    // result is async so you can try to call deposit_address until wallet exists
-    println!("gen_deposit_address({:?}) was called", &currency);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("currency", &currency);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/gen_deposit_address.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn register_device(&self, device_id: &str) -> Result<serde_json::Value, GraviexError> {
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/members/me/register_device.json",
+            vec![("device", device_id.to_string())]
+        )?;
+        let result: serde_json::Value = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn orders_get<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<Vec<Order>, Box<dyn Error>> { 
-    // gets only your own orders
-    // optional params:
-    // market: str = unique market id, xxxxxx, like btcusd
-    // state: str = filter order by state, default to 'wait' (active orders)
-    // limit: usize = limit the number o freturned orders, default 100
-    // page: usize = specify page of paginated results
-    // order_by: str = if set, returned orders will be sorted ["asc", "des"]
-    println!("orders_get({:?}) was called", map);
-    let response = graviex_handler(
-        "GET",
-        "/webapi/v3/orders.json",
-        map
-    )?;
-    let result: Vec<Order> = serde_json::from_str(&response)?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/account/history.json",
+            query.to_params()
+        )?;
+        let result: Vec<HistoryEntry> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn orders_post<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // required params:
-    // market: str = unique market ID, ie "btcusd"
-    // side: str = "sell" or "buy"
-    // volume: str = the amount you want to buy/sell
-    // // an order could be paritally executed, e.g. an order to sell 5 btc
-    // // can be matched with a buy 3 btc order, left 2 btc to be sold;
-    // // in this case, the order's volume would be 5.0, remaining_volume would
-    // // be 2.0, executed volume would be 3.0.
-    //
-    // optional params:
-    // price: str = price for each unit, e.g. if you want to sell/buy one btc
-    // // at 3000 CNY, the price is 3000.0.
-    // ord_type: str = unknown
-    println!("orders_post({:?}) was called", map);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/orders.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn deposits(&self, query: &DepositsQuery) -> Result<Vec<Deposit>, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/deposits.json",
+            query.to_params()
+        )?;
+        let result: Vec<Deposit> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn orders_history<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<Vec<Order>, Box<dyn Error>> {
-    // optional params:
-    // market: str = any market name chars
-    // state: str = 'wait', 'done', 'cancel'
-    // limit: usize = number of returned results, default 100
-    // from: str = from date/time
-    // to: str = to date/time 
-    // page: usize = specify the page of paginated results
-    // order_by: str = ['des', 'asc']
-    println!("orders_history({:?}) was called", map);
-    let response = graviex_handler(
-        "GET",
-        "/webapi/v3/orders/history.json",
-        map
-    )?;
-    let result: Vec<Order> = serde_json::from_str(&response)?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn deposit(&self, txid: &str) -> Result<Deposit, GraviexError> {
+        // gets details of a specific deposit
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/deposit.json",
+            vec![("txid", txid.to_string())]
+        )?;
+        let result: Deposit = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn orders_multi<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // required params:
-    // orders: HashMap = {'side': 'buy'|'sell', 'volume': str}
-    //
-    // optional params:
-    // orders: HashMap = {'price': str, 'ord_type': str (probably 'market')}
-    println!("orders_multi({:?}) was called", map);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/orders/multi.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn deposit_address(&self, currency: &str) -> Result<DepositAddress, GraviexError> {
+        // gets your graviex deposit address for every coni in currency
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/deposit_address.json",
+            vec![("currency", currency.to_string())]
+        )?;
+        let result: DepositAddress = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn orders_clear(side: &str) -> Result<String, minreq::Error> {
-    // cancel all orders of specific type. side expects 'buy' or 'sell'
-    println!("orders_clear({:?}) was called", &side);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("side", &side);
-    let result =  graviex_handler(
-        "POST",
-        "/webapi/v3/orders/clear.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn gen_deposit_address(&self, currency: &str) -> Result<DepositAddress, GraviexError> {
+        // result is async so you can try to call deposit_address until wallet exists,
+        // never retried since it asks graviex for a new address
+        let response = self.graviex_handler_once(
+            "GET",
+            "/webapi/v3/gen_deposit_address.json",
+            vec![("currency", currency.to_string())]
+        )?;
+        let result: DepositAddress = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn order(order_id: &str) -> Result<String, minreq::Error> {
-    // get information of specified order
-    println!("order({:?}) was called", &order_id);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("order_id", &order_id);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/order.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn orders_get(&self, query: &OrdersQuery) -> Result<Vec<Order>, GraviexError> {
+        // gets only your own orders
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/orders.json",
+            query.to_params()
+        )?;
+        let result: Vec<Order> = decode(&response)?;
+        self.observe(&result);
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn order_delete(order_id: &str) -> Result<String, minreq::Error> {
-    // delete target order_id
-    println!("order_delete({:?}) was called", &order_id);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("order_id", &order_id);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/order/delete.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn orders_post(&self, order: &NewOrder) -> Result<Order, GraviexError> {
+        // an order could be paritally executed, e.g. an order to sell 5 btc
+        // can be matched with a buy 3 btc order, left 2 btc to be sold;
+        // in this case, the order's volume would be 5.0, remaining_volume would
+        // be 2.0, executed volume would be 3.0.
+        self.prevalidate(std::slice::from_ref(order))?;
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/orders.json",
+            order.to_params()
+        )?;
+        let result: Order = decode(&response)?;
+        self.observe(std::slice::from_ref(&result));
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn order_book<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<OrderBook, Box<dyn Error>> {
-    // get the order book of the specified market
-    // required keys;
-    // 'market': str = unique market id like 'btcusd'
-    //
-    // optional keys:
-    // 'asks_limit': usize = limit number of returned sell orders, default 20
-    // 'bids_limit': usize = limit number of returned buy orders, default 20
-    println!("order_book({:?}) was called", map);
-    let response = graviex_handler(
-        "GET",
-        "/webapi/v3/order_book.json",
-        map
-    )?;
-    let result: OrderBook = serde_json::from_str(&response)?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn orders_history(&self, query: &OrdersHistoryQuery) -> Result<Vec<Order>, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/orders/history.json",
+            query.to_params()
+        )?;
+        let result: Vec<Order> = decode(&response)?;
+        self.observe(&result);
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn depth<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<Depth, Box<dyn Error>> {
-    // get depth of specified market. both asks and bids are sorted high to low
-    // required keys:
-    // 'market': str = unique market id like 'btcusd'
-    //
-    // optional keys:
-    // 'limit': usize = limit number of returned price intervals, default 100
-    // 'order': 'asc' or 'des'
-    println!("depth({:?}) was called", map);
-
-    let mut request = minreq::get("https://graviex.net:443/webapi/v3/depth.json")
-        .with_timeout(2);
-    for (key, value) in map {
-        request = request.with_param(key.to_string(), &value.to_string());
-    }
-    let response = request.send()?;
-
-    let result = serde_json::from_str(&response.as_str()?)?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn orders_multi(&self, orders: &MultiOrder) -> Result<Vec<Order>, GraviexError> {
+        self.prevalidate(&orders.new_orders())?;
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/orders/multi.json",
+            orders.to_params()
+        )?;
+        let result: Vec<Order> = decode(&response)?;
+        self.observe(&result);
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn trades<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // get recent trades on market, deduplicated, reverse creation order
-    // required keys:
-    // 'market': unique market id like "btcusd"
-    //
-    // optional keys:
-    // limit: usize = default 50
-    // timestamp: usize = unix epoch like graviex_handler + 000,
-    // // return only trades that were executed before this time
-    // from: usize = trade_id. If set, only trades created after will return
-    // to: usize = trade_id. If set, only trades created before will return.
-    // order_by: &str, either 'asc' or 'des'
-    println!("trades({:?}) was called", &map);
-
-    let mut request = minreq::get("https://graviex.net/webapi/v3/trades.json")
-        .with_timeout(2);
-    for (key, value) in map {
-        request = request.with_param(key.to_string(), &value.to_string());
-    }
-    let response = request.send()?;
-    return Ok(response.as_str()?.to_string());
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn orders_clear(&self, side: Side) -> Result<Vec<Order>, GraviexError> {
+        // cancel all orders on one side of the book
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/orders/clear.json",
+            vec![("side", side.to_string())]
+        )?;
+        let result: Vec<Order> = decode(&response)?;
+        self.observe(&result);
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn trades_my<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // get your executed trades history, results are paginated
-    // required keys
-    // market: &str = "btcusd"
-    //
-    // optional keys:
-    // limit: usize = default 50
-    // timestamp: usize = unix epoch like graviex_handler + 100
-    // from: usize = trade_id. If set, only trades created after will return
-    // to: usize = trade_id. If set, only trades created before will return
-    // order_by: &str = either 'des' or 'asc'
-    println!("trades_my({:?}) was called", &map);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/trades/my.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn order(&self, order_id: usize) -> Result<Order, GraviexError> {
+        // get information of specified order
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/order.json",
+            vec![("order_id", order_id.to_string())]
+        )?;
+        let result: Order = decode(&response)?;
+        self.observe(std::slice::from_ref(&result));
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn trades_history<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // get recent trades from market, deduplicated, sorted in reverse creation order.
-    // optional keys:
-    // market, limit, from, to, page, order_by
-    println!("trades_history({:?}) was called", &map);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/trades/history.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn order_delete(&self, order_id: usize) -> Result<Order, GraviexError> {
+        // delete target order_id
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/order/delete.json",
+            vec![("order_id", order_id.to_string())]
+        )?;
+        let result: Order = decode(&response)?;
+        self.observe(std::slice::from_ref(&result));
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn trades_simple(market: &str) -> Result<String, minreq::Error> {
-    // get recent trades on market with minimal properties
-    // deduplicated, reverse creation order
-    println!("trades_simple({:?}) was called", &market);
-    let response = minreq::get("https://graviex.net/webapi/v3/trades_simple.json")
-        .with_param("market", market)
-        .with_timeout(2)
-        .send()?;
-    return Ok(response.as_str()?.to_string());
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn order_book(&self, query: &OrderBookQuery) -> Result<OrderBook, GraviexError> {
+        // get the order book of the specified market
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/order_book.json",
+            query.to_params()
+        )?;
+        let result: OrderBook = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn kline<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // required keys:
-    // market: &str = btcusd
-    // trade_id: usize = trade_id, id of the first trade you received
-    //
-    // optional keys:
-    // limit: usize = default 20
-    // period: usize = 1 (default), 5, 15, 30, 60, 120, 240, 360, 720, 1440, 4320, 10080
-    // timestamp: usize = unix timestamp, return only trades created more recently than
-    println!("kline({:?}) was called", &map);
-    let mut request = minreq::get("https://graviex.net/webapi/v3/k.json")
-        .with_timeout(2);
-    for (key, value) in map {
-        request = request.with_param(key.to_string(), &value.to_string());
-    }
-    let response = request.send()?;
-    return Ok(response.as_str()?.to_string());
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn depth(&self, query: &DepthQuery) -> Result<Depth, GraviexError> {
+        // get depth of specified market. both asks and bids are sorted high to low
+        let response = self.public_get("/webapi/v3/depth.json", query.to_params())?;
+        let result: Depth = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn kline_pending<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // required keys:
-    // market: &str = btcusd
-    // trade_id: usize = trade_id, id of the first trade you received
-    //
-    // optional keys:
-    // limit: usize = default 20
-    // period: usize = 1 (default), 5, 15, 30, 60, 120, 240, 360, 720, 1440, 4320, 10080
-    // timestamp: usize = unix timestamp, return only trades created more recently than
-    println!("kline_pending({:?}) was called", &map);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/k_with_pending_trades.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn trades(&self, query: &TradesQuery) -> Result<Vec<Trade>, GraviexError> {
+        // get recent trades on market, deduplicated, reverse creation order
+        let response = self.public_get("/webapi/v3/trades.json", query.to_params())?;
+        let result: Vec<Trade> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn timestamp() -> Result<usize, Box<dyn Error>> {
-    println!("timestamp() was called");
-    let result = minreq::get("https://graviex.net/webapi/v3/timestamp.json")
-        .send()?
-        .as_str()?
-        .parse::<usize>()?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn trades_my(&self, query: &TradesQuery) -> Result<Vec<Trade>, GraviexError> {
+        // get your executed trades history, results are paginated
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/trades/my.json",
+            query.to_params()
+        )?;
+        let result: Vec<Trade> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn settings_get() -> Result<String, minreq::Error> {
-    println!("settings_get() was called");
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/settings/get.json",
-        HashMap::new()
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn trades_history(&self, query: &TradesHistoryQuery) -> Result<Vec<Trade>, GraviexError> {
+        // get recent trades from market, deduplicated, sorted in reverse creation order.
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/trades/history.json",
+            query.to_params()
+        )?;
+        let result: Vec<Trade> = decode(&response)?;
+        Ok(result)
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn trades_simple(&self, market: &str) -> Result<Vec<SimpleTrade>, GraviexError> {
+        // get recent trades on market with minimal properties
+        // deduplicated, reverse creation order
+        let response = self.public_get(
+            "/webapi/v3/trades_simple.json",
+            vec![("market", market.to_string())]
+        )?;
+        let result: Vec<SimpleTrade> = decode(&response)?;
+        Ok(result)
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn kline(&self, query: &KlineQuery) -> Result<Vec<Candle>, GraviexError> {
+        let response = self.public_get("/webapi/v3/k.json", query.to_params())?;
+        let result: Vec<Candle> = decode(&response)?;
+        Ok(result)
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn kline_pending(&self, query: &KlinePendingQuery) -> Result<KlineWithPending, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/k_with_pending_trades.json",
+            query.to_params()
+        )?;
+        let result: KlineWithPending = decode(&response)?;
+        Ok(result)
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn timestamp(&self) -> Result<usize, GraviexError> {
+        let response = self.public_get("/webapi/v3/timestamp.json", Params::new())?;
+        let result: usize = decode(response.trim())?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn settings_store<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    println!("settings_store({:?}) was called", &map);
-    // possible keys:
-    // darkmode: bool
-    // was_quick_tour: bool
-    // filter_favorites: bool
-    // markets_filter (defaults to "all")
-    // sound: bool
-    // candlestick_scale: usize = defaults to 1D
-    // candlestick_timezone: defaults to "exchange"
-    // // and a list of favorite pairs where key name is the fav-pairname
-    // fav-vrscbtc: bool
-    println!("settings_store({:?}) was called", &map);
-    let mut map_as_str: String = "{".to_string();
-    for (key, value) in map {
-        // we need the final string to have quotes around keys/values
-        map_as_str.push_str(key);
-        map_as_str.push(':');
-        map_as_str.push_str(value);
-        map_as_str.push(',');
-    }
-    map_as_str.push('}');
-    println!("map_as_str: {:?}", &map_as_str);
-
-    let mut newmap: HashMap<&str, &str> = HashMap::new();
-    newmap.insert("data", &map_as_str);
-
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/settings/store.json",
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn settings_get(&self) -> Result<Settings, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/settings/get.json",
+            Params::new()
+        )?;
+        let result: Settings = decode(&response)?;
+        Ok(result)
+    }
+
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn settings_store(&self, settings: &Settings) -> Result<Settings, GraviexError> {
+        // only the fields that are set get stored, favorite pairs go in
+        // settings.extra with keys like "fav-vrscbtc"
         // in python this is
         // {'data': str(json.dumps(map)).replace(' ', '')}
-        newmap
-    )?;
-    Ok(result)
-}
+        let data = serde_json::to_string(settings)
+            .expect("string keys and json values always serialize");
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/settings/store.json",
+            vec![("data", data)]
+        )?;
+        let result: Settings = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn currency_info(coin: &str) -> Result<String, minreq::Error> {
-    println!("curency_info({:?}) was called", &coin);
-    let response = minreq::get("https://graviex.net/webapi/v3/currency/info.json")
-        .with_timeout(2)
-        .with_param("currency", coin)
-        .send()?
-        .as_str()?
-        .to_string();
-
-    Ok(response)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn currency_info(&self, coin: &str) -> Result<CurrencyInfo, GraviexError> {
+        let response = self.public_get(
+            "/webapi/v3/currency/info.json",
+            vec![("currency", coin.to_string())]
+        )?;
+        let result: CurrencyInfo = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn withdraws<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    // required keys:
-    // 'currency': &str = like 'btc'
-    //
-    // optional keys:
-    // limit: usize = max number of results, default is probably 100
-    // state: &str = unknown, probably 'pending' 'complete' or 'locked'
-    println!("withdraws({:?}) was called", &map);
-    let response = graviex_handler(
-        "GET",
-        "/webapi/v3/withdraws.json",
-        map
-    )?;
-    Ok(response)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn withdraws(&self, query: &WithdrawsQuery) -> Result<Vec<Withdraw>, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/withdraws.json",
+            query.to_params()
+        )?;
+        let result: Vec<Withdraw> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn create_withdraw<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    println!("create_withdraw({:?}) was called", &map);
-    // make withdrawal.
-    // required keys:
-    // currency: &str = 'btc' or 'vrsc'
-    // fund_uid: &str = the address to withdraw to
-    // sum: &str = amount, string with format "0.0000"
-    //
-    // optional keys:
-    // provider: withdaw providor, unknown what this means
-    // speed_up: accelerate window, unknown type
-    println!("create_withdraw({:?}) was called", &map);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/create_withdraw.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn create_withdraw(&self, withdraw: &NewWithdraw) -> Result<Withdraw, GraviexError> {
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/create_withdraw.json",
+            withdraw.to_params()
+        )?;
+        let result: Withdraw = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn fund_sources(currency: &str) -> Result<String, minreq::Error> {
-    println!("fund_sources({:?}) was called", currency);
-    // currency is a string like 'gio' btc' or 'vrsc'
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("currency", currency);
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/fund_sources.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn fund_sources(&self, currency: &str) -> Result<Vec<FundSource>, GraviexError> {
+        // currency is a string like 'gio' btc' or 'vrsc'
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/fund_sources.json",
+            vec![("currency", currency.to_string())]
+        )?;
+        let result: Vec<FundSource> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn fund_source_create<S: BuildHasher>(map: HashMap<&str, &str, S>)
--> Result<String, minreq::Error> {
-    println!("fund_source_create({:?}) was called", &map);
-    // required keys:
-    // currency: &str = 'gio' or 'btc' or 'doge'
-    // uid: &str = address of fund source
-    // extra: &str = label you want to assign to it
-    //
-    // optional keys:
-    // fund-uid: &str = provider, unknown what this means
-    println!("fund-source_create({:?}) was called", &map);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/create_fund_source.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn fund_source_create(&self, source: &NewFundSource) -> Result<FundSource, GraviexError> {
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/create_fund_source.json",
+            source.to_params()
+        )?;
+        let result: FundSource = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn fund_source_remove(source_id: &str) -> Result<String, minreq::Error> {
-    // delete a fund source
-    println!("fund_source_remove({:?}) was called", source_id);
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    map.insert("id", source_id);
-    let result = graviex_handler(
-        "POST",
-        "/webapi/v3/remove_fund_source.json",
-        map
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn fund_source_remove(&self, source_id: usize) -> Result<FundSource, GraviexError> {
+        // delete a fund source
+        let response = self.graviex_handler(
+            "POST",
+            "/webapi/v3/remove_fund_source.json",
+            vec![("id", source_id.to_string())]
+        )?;
+        let result: FundSource = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn strategies_list() -> Result<String, minreq::Error> {
-    println!("strategies_list() was called");
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/strategies/list.json",
-        HashMap::new()
-    )?;
-    Ok(result)
-}
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn strategies_list(&self) -> Result<Vec<Strategy>, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/strategies/list.json",
+            Params::new()
+        )?;
+        let result: Vec<Strategy> = decode(&response)?;
+        Ok(result)
+    }
 
-/// # Errors
-/// returns `minreq::Error` if anything goes wrong
-#[allow(unused)]
-pub fn strategies_list_my() -> Result<String, minreq::Error> {
-    println!("strategies_list_my() was called");
-    let result = graviex_handler(
-        "GET",
-        "/webapi/v3/strategies/my.json",
-        HashMap::new()
-    )?;
-    Ok(result)
+    /// # Errors
+    /// returns `GraviexError` if anything goes wrong
+    pub fn strategies_list_my(&self) -> Result<Vec<Strategy>, GraviexError> {
+        let response = self.graviex_handler(
+            "GET",
+            "/webapi/v3/strategies/my.json",
+            Params::new()
+        )?;
+        let result: Vec<Strategy> = decode(&response)?;
+        Ok(result)
+    }
 }
-
-
@@ -0,0 +1,83 @@
+//! Request logging through `tracing`.
+//!
+//! Every request runs inside a `graviex_request` span carrying the
+//! endpoint, method, status, latency and graviex error code. Parameters
+//! are logged redacted and response bodies not at all. Nothing is
+//! printed unless the caller installs a subscriber, which also decides the
+//! level, like `RUST_LOG=graviex_api_client=debug` with tracing-subscriber.
+
+use std::time::Duration;
+
+use tracing::{field, Span};
+
+use crate::{GraviexError, HttpRequest};
+
+/// Stands in for the value of a sensitive parameter.
+pub const REDACTED: &str = "[redacted]";
+
+// credentials and withdraw addresses never make it into the logs
+const SENSITIVE: [&str; 4] = ["access_key", "signature", "fund_uid", "uid"];
+
+/// Whether the value of parameter `key` must stay out of the logs.
+#[must_use]
+pub fn is_sensitive(key: &str) -> bool {
+    SENSITIVE.contains(&key)
+}
+
+/// `key=value` pairs joined with `&`, with sensitive values redacted.
+#[must_use]
+pub fn redact<K: AsRef<str>>(params: &[(K, String)]) -> String {
+    let mut query_string = String::new();
+    for (key, value) in params {
+        if !query_string.is_empty() {
+            query_string.push('&');
+        }
+        query_string.push_str(key.as_ref());
+        query_string.push('=');
+        query_string.push_str(if is_sensitive(key.as_ref()) { REDACTED } else { value });
+    }
+    query_string
+}
+
+// "https://graviex.net/webapi/v3/orders.json" logs as "/webapi/v3/orders.json"
+fn endpoint(url: &str) -> &str {
+    let path = url.split_once("://").map_or(url, |(_, rest)| rest);
+    path.find('/').map_or("/", |start| &path[start..])
+}
+
+pub(crate) fn request_span(request: &HttpRequest) -> Span {
+    tracing::debug_span!(
+        "graviex_request",
+        endpoint = endpoint(&request.url),
+        method = %request.method,
+        params = %redact(&request.params),
+        status = field::Empty,
+        latency_ms = field::Empty,
+        code = field::Empty,
+    )
+}
+
+pub(crate) fn finish(span: &Span, latency: Duration, result: &Result<String, GraviexError>) {
+    span.record("latency_ms", u64::try_from(latency.as_millis()).unwrap_or(u64::MAX));
+    match result {
+        Ok(_) => tracing::debug!("request completed"),
+        Err(e) => {
+            if let Some(code) = e.code() {
+                span.record("code", code);
+            }
+            tracing::warn!(error = %summary(e), "request failed");
+        }
+    }
+}
+
+// what a failure logs as, without the response body, which can hold
+// withdraw addresses like any other part of the response
+pub(crate) fn summary(error: &GraviexError) -> String {
+    match error {
+        GraviexError::Status { status, .. } => format!("http status {}", status),
+        GraviexError::Decode { source, .. } => {
+            format!("could not decode the response at line {} column {}", source.line(), source.column())
+        }
+        e => e.to_string(),
+    }
+}
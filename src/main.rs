@@ -1,30 +1,46 @@
-use std::collections::HashMap;
-use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
 
-extern crate serde;
-// use serde::{Serialize, Deserialize};
+use graviex_api_client::strategy::Strategy;
+use graviex_api_client::*;
 
-mod lib;
+// your algo here: implement the hooks it needs, see strategy::Strategy
+struct Algo {
+    sma: Sma,
+    open: Option<Candle>, // the bar still filling
+}
 
-#[allow(clippy::never_loop)]
-fn main() {
-    // library tests
-    // run_tests();
-    loop {
-	// your algo here
-	break
-	//
+impl Strategy for Algo {
+    fn on_candle(&mut self, _ctx: &mut dyn Context, market: &str, _period: Period, candle: &Candle) {
+        // bars come again while they fill, average them once they close
+        if self.open.as_ref().is_some_and(|open| open.timestamp < candle.timestamp) {
+            let closed = self.open.take().unwrap();
+            if let Some(average) = self.sma.next(&closed) {
+                println!("{} closed at {}, 20 bar average {}", market, closed.close, average);
+            }
+        }
+        self.open = Some(candle.clone());
+    }
 
+    fn on_account(&mut self, _ctx: &mut dyn Context, member: &Member) {
+        for account in &member.accounts_filtered {
+            println!("{} balance {}", account.currency, account.balance);
+        }
     }
-    println!("The main loop was broken, program has exited.");
 }
 
+fn main() {
+    // credentials are read from GRAVIEX_KEY and GRAVIEX_SECRET
+    let client = match GraviexClient::from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            println!("could not load graviex credentials: {}", e);
+            return;
+        }
+    };
 
-#[allow(unused)]
-fn run_tests() {
-    lib::test_no_params();
-    lib::test_params();
-    // lib::mutable_state_tests();
-    println!("tests complete");
+    let engine = Engine::new(Arc::new(client), Algo { sma: Sma::new(20), open: None })
+        .subscribe(Topic::Kline("ethbtc".to_string(), Period::Minute1), Duration::from_secs(10));
+    engine.run();
+    println!("The strategy stopped, program has exited.");
 }
-
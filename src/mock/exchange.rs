@@ -0,0 +1,1138 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::time::{self, format_timestamp};
+use crate::*;
+
+use super::sign::expected_signature;
+
+/// How far a tonce may stray from the mock's clock, in milliseconds.
+pub const TONCE_WINDOW: u64 = 30_000;
+
+/// A market listed on the mock exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockMarket {
+    pub id: String, // like "ethbtc"
+    pub base: String, // like "eth"
+    pub quote: String, // like "btc"
+    pub base_fixed: usize,
+    pub quote_fixed: usize,
+    pub base_min: Decimal, // smallest volume accepted
+    pub quote_min: Decimal, // smallest price * volume accepted
+    pub fee: Decimal, // charged on what each side receives
+    pub api: bool, // whether orders are accepted over the api
+}
+
+impl MockMarket {
+    /// A market trading `base` for `quote` with graviex's usual settings.
+    #[must_use]
+    pub fn new(base: &str, quote: &str) -> MockMarket {
+        MockMarket {
+            id: format!("{}{}", base, quote),
+            base: base.to_string(),
+            quote: quote.to_string(),
+            base_fixed: 8,
+            quote_fixed: 8,
+            base_min: Decimal::new(1, 4),
+            quote_min: Decimal::new(1, 6),
+            fee: Decimal::new(2, 3),
+            api: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockBalance {
+    pub balance: Decimal, // available
+    pub locked: Decimal, // held by open orders
+}
+
+#[derive(Debug)]
+struct MockMember {
+    secret: String,
+    sn: String,
+    balances: BTreeMap<String, MockBalance>,
+    deposits: Vec<Deposit>,
+    withdraws: Vec<Withdraw>,
+    fund_sources: Vec<FundSource>,
+    settings: serde_json::Map<String, serde_json::Value>,
+    tonces: HashSet<u64>,
+}
+
+impl MockMember {
+    fn balance(&mut self, currency: &str) -> &mut MockBalance {
+        self.balances.entry(currency.to_string()).or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockOrder {
+    id: usize,
+    owner: String, // access key
+    market: String,
+    side: Side,
+    price: Decimal,
+    volume: Decimal,
+    remaining: Decimal,
+    executed: Decimal,
+    funds: Decimal, // quote currency traded so far
+    state: OrderState,
+    created_at: u64,
+}
+
+#[derive(Debug, Clone)]
+struct MockTrade {
+    id: usize,
+    market: String,
+    price: Decimal,
+    volume: Decimal,
+    created_at: u64,
+    ask_id: usize,
+    bid_id: usize,
+    taker: Side, // side of the order that crossed the book
+}
+
+/// A failure the mock plays instead of answering a request normally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockFailure {
+    Unavailable, // answer 503 without acting on the request
+    LostResponse, // act on the request, then hang up without answering
+}
+
+/// Why the mock refused a request, rendered as a graviex error body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockError {
+    pub status: u16,
+    pub code: i64,
+    pub message: String,
+}
+
+impl MockError {
+    fn new(status: u16, code: i64, message: &str) -> MockError {
+        MockError { status, code, message: message.to_string() }
+    }
+
+    fn bad_param(message: &str) -> MockError {
+        MockError::new(400, 1001, message)
+    }
+
+    fn body(&self) -> String {
+        json!({"error": {"code": self.code, "message": self.message}}).to_string()
+    }
+}
+
+type MockResult = Result<String, MockError>;
+
+fn to_json<T: Serialize>(value: &T) -> MockResult {
+    serde_json::to_string(value).map_err(|e| MockError::new(500, 1000, &e.to_string()))
+}
+
+/// In-memory stand-in for graviex: markets, members with balances, and a
+/// price-time priority matching engine for limit orders.
+#[derive(Debug)]
+pub struct MockExchange {
+    markets: Vec<MockMarket>,
+    members: HashMap<String, MockMember>, // by access key
+    orders: Vec<MockOrder>, // order id - 1 is the index
+    trades: Vec<MockTrade>, // trade id - 1 is the index
+    next_id: usize, // for deposits, withdraws and fund sources
+    clock: Option<u64>, // fixed time for created_at, real time when None
+    failures: Vec<(String, String, MockFailure)>, // method, path, failure
+}
+
+impl Default for MockExchange {
+    fn default() -> MockExchange {
+        MockExchange::new()
+    }
+}
+
+impl MockExchange {
+    /// An exchange listing `ethbtc` and `giobtc`, with no members yet.
+    #[must_use]
+    pub fn new() -> MockExchange {
+        MockExchange {
+            markets: vec![MockMarket::new("eth", "btc"), MockMarket::new("gio", "btc")],
+            members: HashMap::new(),
+            orders: Vec::new(),
+            trades: Vec::new(),
+            next_id: 1,
+            clock: None,
+            failures: Vec::new(),
+        }
+    }
+
+    /// List another market, replacing any market with the same id.
+    pub fn add_market(&mut self, market: MockMarket) {
+        self.markets.retain(|m| m.id != market.id);
+        self.markets.push(market);
+    }
+
+    /// Register a member who signs with `key` and `secret`.
+    pub fn add_member(&mut self, key: &str, secret: &str) {
+        let sn = format!("MOCK{:06}", self.members.len() + 1);
+        self.members.insert(key.to_string(), MockMember {
+            secret: secret.to_string(),
+            sn,
+            balances: BTreeMap::new(),
+            deposits: Vec::new(),
+            withdraws: Vec::new(),
+            fund_sources: Vec::new(),
+            settings: serde_json::Map::new(),
+            tonces: HashSet::new(),
+        });
+    }
+
+    #[must_use]
+    pub fn has_member(&self, key: &str) -> bool {
+        self.members.contains_key(key)
+    }
+
+    /// Deposit `amount` of `currency` into the member's account.
+    ///
+    /// # Panics
+    /// panics if no member signs with `key`
+    pub fn credit(&mut self, key: &str, currency: &str, amount: Decimal) {
+        let id = self.take_id();
+        let now = self.now();
+        let member = self.members.get_mut(key).expect("unknown mock member");
+        member.balance(currency).balance += amount;
+        member.deposits.push(Deposit {
+            id,
+            currency: currency.to_string(),
+            amount,
+            fee: Some(Decimal::ZERO),
+            txid: Some(format!("mocktx{}", id)),
+            confirmations: Some(6),
+            state: "accepted".to_string(),
+            created_at: format_timestamp(now),
+            done_at: Some(format_timestamp(now)),
+        });
+    }
+
+    /// The member's balance in `currency`.
+    #[must_use]
+    pub fn balance(&self, key: &str, currency: &str) -> MockBalance {
+        self.members
+            .get(key)
+            .and_then(|member| member.balances.get(currency))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Freeze the clock used for `created_at`, trades and k lines.
+    pub fn set_time(&mut self, timestamp: u64) {
+        self.clock = Some(timestamp);
+    }
+
+    /// Play `failure` on the next `method` request to `path`, like
+    /// `"POST"` and `"/webapi/v3/orders.json"`. Queued failures for the
+    /// same request play in order.
+    pub fn fail_next(&mut self, method: &str, path: &str, failure: MockFailure) {
+        self.failures.push((method.to_string(), path.to_string(), failure));
+    }
+
+    /// Take the failure queued for this request, if there is one.
+    pub fn take_failure(&mut self, method: &str, path: &str) -> Option<MockFailure> {
+        let index = self.failures.iter().position(|(m, p, _)| m == method && p == path)?;
+        Some(self.failures.remove(index).2)
+    }
+
+    /// Current time on the mock, in seconds.
+    #[must_use]
+    pub fn now(&self) -> u64 {
+        self.clock.unwrap_or_else(time::now)
+    }
+
+    /// Place a limit order for the member, as if it came in over the api.
+    ///
+    /// # Errors
+    /// returns the graviex error the api would have answered with
+    pub fn place_order(
+        &mut self,
+        key: &str,
+        market: &str,
+        side: Side,
+        volume: Decimal,
+        price: Decimal)
+    -> Result<usize, MockError> {
+        let market = self.market(market)?.clone();
+        if !market.api {
+            return Err(MockError::new(400, 2002, "Failed to create order. Market is not available over api."));
+        }
+        if volume <= Decimal::ZERO || price <= Decimal::ZERO {
+            return Err(MockError::new(400, 2002, "Failed to create order. Volume and price must be positive."));
+        }
+        if volume < market.base_min || volume * price < market.quote_min {
+            return Err(MockError::new(400, 2002, "Failed to create order. Order is too small."));
+        }
+        let (currency, amount) = match side {
+            Side::Buy => (&market.quote, price * volume),
+            Side::Sell => (&market.base, volume),
+        };
+        let member = self.members
+            .get_mut(key)
+            .ok_or_else(|| MockError::new(401, 2008, "The access key does not exist."))?;
+        let balance = member.balance(currency);
+        if balance.balance < amount {
+            return Err(MockError::new(400, 2002, "Failed to create order. Insufficient balance."));
+        }
+        balance.balance -= amount;
+        balance.locked += amount;
+
+        let id = self.orders.len() + 1;
+        let created_at = self.now();
+        self.orders.push(MockOrder {
+            id,
+            owner: key.to_string(),
+            market: market.id.clone(),
+            side,
+            price,
+            volume,
+            remaining: volume,
+            executed: Decimal::ZERO,
+            funds: Decimal::ZERO,
+            state: OrderState::Wait,
+            created_at,
+        });
+        self.match_order(id, &market);
+        Ok(id)
+    }
+
+    /// Cancel an open order, returning its locked funds.
+    ///
+    /// # Errors
+    /// returns the graviex error the api would have answered with
+    pub fn cancel_order(&mut self, key: &str, id: usize) -> Result<(), MockError> {
+        let order = self.orders
+            .get(id.wrapping_sub(1))
+            .filter(|o| o.owner == key)
+            .ok_or_else(|| MockError::new(404, 2004, "Order not found."))?
+            .clone();
+        if order.state != OrderState::Wait {
+            return Err(MockError::new(400, 2003, "Failed to cancel order."));
+        }
+        let market = self.market(&order.market)?.clone();
+        let (currency, amount) = match order.side {
+            Side::Buy => (&market.quote, order.price * order.remaining),
+            Side::Sell => (&market.base, order.remaining),
+        };
+        if let Some(member) = self.members.get_mut(key) {
+            let balance = member.balance(currency);
+            balance.locked -= amount;
+            balance.balance += amount;
+        }
+        self.orders[id - 1].state = OrderState::Cancel;
+        Ok(())
+    }
+
+    // cross order `id` against the book until it stops overlapping
+    fn match_order(&mut self, id: usize, market: &MockMarket) {
+        loop {
+            let taker = &self.orders[id - 1];
+            if taker.remaining <= Decimal::ZERO {
+                break;
+            }
+            let maker = self.orders
+                .iter()
+                .filter(|o| {
+                    o.market == taker.market && o.side != taker.side && o.state == OrderState::Wait
+                        && match taker.side {
+                            Side::Buy => o.price <= taker.price,
+                            Side::Sell => o.price >= taker.price,
+                        }
+                })
+                .min_by(|a, b| match taker.side {
+                    Side::Buy => a.price.cmp(&b.price).then(a.id.cmp(&b.id)),
+                    Side::Sell => b.price.cmp(&a.price).then(a.id.cmp(&b.id)),
+                });
+            let Some(maker) = maker else { break };
+            let (maker_id, price) = (maker.id, maker.price);
+            let volume = maker.remaining.min(taker.remaining);
+            let taker_side = taker.side;
+            let (bid_id, ask_id) = match taker_side {
+                Side::Buy => (id, maker_id),
+                Side::Sell => (maker_id, id),
+            };
+            self.fill(bid_id, ask_id, price, volume, market);
+            self.trades.push(MockTrade {
+                id: self.trades.len() + 1,
+                market: market.id.clone(),
+                price,
+                volume,
+                created_at: self.now(),
+                ask_id,
+                bid_id,
+                taker: taker_side,
+            });
+        }
+    }
+
+    // move funds between the two members and update both orders
+    fn fill(&mut self, bid_id: usize, ask_id: usize, price: Decimal, volume: Decimal, market: &MockMarket) {
+        let one = Decimal::ONE;
+        let bid = self.orders[bid_id - 1].clone();
+        let ask = self.orders[ask_id - 1].clone();
+        if let Some(buyer) = self.members.get_mut(&bid.owner) {
+            let quote = buyer.balance(&market.quote);
+            quote.locked -= bid.price * volume;
+            quote.balance += (bid.price - price) * volume;
+            buyer.balance(&market.base).balance += volume * (one - market.fee);
+        }
+        if let Some(seller) = self.members.get_mut(&ask.owner) {
+            seller.balance(&market.base).locked -= volume;
+            seller.balance(&market.quote).balance += price * volume * (one - market.fee);
+        }
+        for id in [bid_id, ask_id] {
+            let order = &mut self.orders[id - 1];
+            order.remaining -= volume;
+            order.executed += volume;
+            order.funds += price * volume;
+            if order.remaining <= Decimal::ZERO {
+                order.state = OrderState::Done;
+            }
+        }
+    }
+
+    fn take_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    fn market(&self, id: &str) -> Result<&MockMarket, MockError> {
+        self.markets
+            .iter()
+            .find(|m| m.id == id)
+            .ok_or_else(|| MockError::bad_param("Market does not exist."))
+    }
+
+    /// Answer a request the way graviex would, as an http status and body.
+    /// `params` are the decoded query and form parameters, signature included.
+    pub fn handle(&mut self, method: &str, path: &str, params: &[(String, String)]) -> (u16, String) {
+        match self.route(method, path, params) {
+            Ok(body) => (200, body),
+            Err(e) => (e.status, e.body()),
+        }
+    }
+
+    fn route(&mut self, method: &str, path: &str, params: &[(String, String)]) -> MockResult {
+        let Some(endpoint) = path.strip_prefix("/webapi/v3/") else {
+            return Err(MockError::new(404, 1000, "Not found."));
+        };
+        let q = Query(params);
+
+        // public endpoints
+        match (method, endpoint) {
+            ("GET", "markets.json") => return self.markets_json(),
+            ("GET", "tickers.json") => return self.tickers_json(),
+            ("GET", "timestamp.json") => return Ok(self.now().to_string()),
+            ("GET", "depth.json") => return self.depth_json(&q),
+            ("GET", "trades.json") => return self.trades_json(&q, None),
+            ("GET", "trades_simple.json") => return self.trades_simple_json(&q),
+            ("GET", "k.json") => return self.k_json(&q),
+            ("GET", "currency/info.json") => return self.currency_info_json(&q),
+            ("GET", _) => {
+                if let Some(id) = endpoint.strip_prefix("markets/").and_then(|e| e.strip_suffix(".json")) {
+                    return self.market_json(id);
+                }
+                if let Some(id) = endpoint.strip_prefix("tickers/").and_then(|e| e.strip_suffix(".json")) {
+                    return to_json(&self.ticker(id)?);
+                }
+            }
+            _ => {}
+        }
+
+        let key = self.authenticate(method, path, params)?;
+        match (method, endpoint) {
+            ("GET", "members/me.json") => self.me_json(&key),
+            ("POST", "members/me/register_device.json") => Ok(json!({"device": q.get("device")}).to_string()),
+            ("GET", "orders.json") => self.orders_json(&key, &q, Some(OrderState::Wait)),
+            ("GET", "orders/history.json") => self.orders_json(&key, &q, None),
+            ("POST", "orders.json") => self.orders_post_json(&key, &q),
+            ("POST", "orders/multi.json") => self.orders_multi_json(&key, &q),
+            ("POST", "orders/clear.json") => self.orders_clear_json(&key, &q),
+            ("GET", "order.json") => self.order_json(&key, &q, false),
+            ("POST", "order/delete.json") => self.order_json(&key, &q, true),
+            ("GET", "order_book.json") => self.order_book_json(&q),
+            ("GET", "trades/my.json") => self.trades_json(&q, Some(&key)),
+            ("GET", "trades/history.json") => self.trades_history_json(&key, &q),
+            ("GET", "k_with_pending_trades.json") => self.k_pending_json(&q),
+            ("GET", "account/history.json") => self.history_json(&key, &q),
+            ("GET", "deposits.json") => self.deposits_json(&key, &q),
+            ("GET", "deposit.json") => self.deposit_json(&key, &q),
+            ("GET", "deposit_address.json" | "gen_deposit_address.json") => self.deposit_address_json(&key, &q),
+            ("GET", "withdraws.json") => self.withdraws_json(&key, &q),
+            ("POST", "create_withdraw.json") => self.create_withdraw_json(&key, &q),
+            ("GET", "fund_sources.json") => self.fund_sources_json(&key, &q),
+            ("POST", "create_fund_source.json") => self.create_fund_source_json(&key, &q),
+            ("POST", "remove_fund_source.json") => self.remove_fund_source_json(&key, &q),
+            ("GET", "settings/get.json") => to_json(&self.members[&key].settings),
+            ("POST", "settings/store.json") => self.settings_store_json(&key, &q),
+            ("GET", "strategies/list.json" | "strategies/my.json") => Ok("[]".to_string()),
+            _ => Err(MockError::new(404, 1000, "Not found.")),
+        }
+    }
+
+    // check access key, tonce and signature exactly like graviex does
+    fn authenticate(&mut self, method: &str, path: &str, params: &[(String, String)]) -> Result<String, MockError> {
+        let q = Query(params);
+        let key = q.require("access_key")
+            .map_err(|_| MockError::new(401, 2001, "Authorization failed."))?
+            .to_string();
+        let tonce: u64 = q.require("tonce")
+            .ok()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| MockError::new(401, 2001, "Authorization failed."))?;
+        let signature = q.require("signature")
+            .map_err(|_| MockError::new(401, 2001, "Authorization failed."))?
+            .to_string();
+        let member = self.members
+            .get_mut(&key)
+            .ok_or_else(|| MockError::new(401, 2008, "The access key does not exist."))?;
+
+        let now = time::now() * 1000;
+        if tonce.abs_diff(now) > TONCE_WINDOW {
+            return Err(MockError::new(401, 2007, &format!("The tonce {} is invalid, current timestamp is {}.", tonce, now)));
+        }
+        if member.tonces.contains(&tonce) {
+            return Err(MockError::new(401, 2006, &format!("The tonce {} has already been used by access key {}.", tonce, key)));
+        }
+
+        if expected_signature(&member.secret, method, path, params) != signature {
+            return Err(MockError::new(401, 2005, "The signature is incorrect."));
+        }
+        member.tonces.insert(tonce);
+        Ok(key)
+    }
+
+    fn markets_json(&self) -> MockResult {
+        let markets: Vec<MarketList> = self.markets
+            .iter()
+            .map(|m| MarketList {
+                id: m.id.clone(),
+                name: format!("{}/{}", m.base.to_uppercase(), m.quote.to_uppercase()),
+            })
+            .collect();
+        to_json(&markets)
+    }
+
+    fn market_json(&self, id: &str) -> MockResult {
+        let (index, m) = self.markets
+            .iter()
+            .enumerate()
+            .find(|(_, m)| m.id == id)
+            .ok_or_else(|| MockError::bad_param("Market does not exist."))?;
+        to_json(&Market {
+            attributes: Attributes {
+                id: m.id.clone(),
+                code: index + 1,
+                name: format!("{}/{}", m.base.to_uppercase(), m.quote.to_uppercase()),
+                base_unit: m.base.clone(),
+                quote_unit: m.quote.clone(),
+                bid: Bid { fee: m.fee, currency: m.quote.clone(), fixed: m.quote_fixed, lot: Decimal::ZERO },
+                ask: Ask { fee: m.fee, currency: m.base.clone(), fixed: m.base_fixed, lot: Decimal::ZERO },
+                sort_order: index,
+            },
+        })
+    }
+
+    fn ticker(&self, id: &str) -> Result<Ticker, MockError> {
+        let m = self.market(id)?;
+        let now = self.now();
+        let day: Vec<&MockTrade> = self.trades
+            .iter()
+            .filter(|t| t.market == m.id && t.created_at + 86400 > now)
+            .collect();
+        let last = self.trades.iter().rev().find(|t| t.market == m.id).map(|t| t.price);
+        let best = |side: Side| {
+            let open = self.open_orders(&m.id, side).map(|o| o.price);
+            match side {
+                Side::Buy => open.max(),
+                Side::Sell => open.min(),
+            }
+        };
+        Ok(Ticker {
+            name: format!("{}/{}", m.base.to_uppercase(), m.quote.to_uppercase()),
+            base_unit: m.base.clone(),
+            base_fixed: m.base_fixed,
+            base_fee: m.fee,
+            quote_unit: m.quote.clone(),
+            quote_fixed: m.quote_fixed,
+            quote_fee: m.fee,
+            api: m.api,
+            base_lot: None,
+            quote_lot: None,
+            base_min: m.base_min,
+            quote_min: m.quote_min,
+            blocks: 0,
+            block_time: String::new(),
+            wstatus: "on".to_string(),
+            low: day.iter().map(|t| t.price).min().unwrap_or_default(),
+            high: day.iter().map(|t| t.price).max().unwrap_or_default(),
+            last: last.unwrap_or_default(),
+            open: day.first().map(|t| t.price).unwrap_or_default(),
+            volume: day.iter().map(|t| t.volume).sum(),
+            volume2: day.iter().map(|t| t.price * t.volume).sum(),
+            sell: best(Side::Sell).unwrap_or_default(),
+            buy: best(Side::Buy).unwrap_or_default(),
+            at: usize::try_from(now).unwrap_or(usize::MAX),
+        })
+    }
+
+    fn tickers_json(&self) -> MockResult {
+        let mut tickers = BTreeMap::new();
+        for m in &self.markets {
+            tickers.insert(m.id.clone(), self.ticker(&m.id)?);
+        }
+        to_json(&tickers)
+    }
+
+    fn open_orders<'a>(&'a self, market: &'a str, side: Side) -> impl Iterator<Item = &'a MockOrder> + 'a {
+        self.orders
+            .iter()
+            .filter(move |o| o.market == market && o.side == side && o.state == OrderState::Wait)
+    }
+
+    fn depth_json(&self, q: &Query) -> MockResult {
+        let market = self.market(q.require("market")?)?;
+        let limit = q.number("limit")?.unwrap_or(100);
+        let levels = |side: Side| {
+            let mut levels: BTreeMap<Decimal, Decimal> = BTreeMap::new();
+            for o in self.open_orders(&market.id, side) {
+                *levels.entry(o.price).or_default() += o.remaining;
+            }
+            // bids are best first, asks are kept high to low like graviex
+            let mut levels: Vec<[String; 2]> = levels
+                .into_iter()
+                .rev()
+                .map(|(price, vol)| [price.to_string(), vol.to_string()])
+                .collect();
+            match side {
+                Side::Buy => levels.truncate(limit),
+                Side::Sell => {
+                    let skip = levels.len().saturating_sub(limit);
+                    levels.drain(..skip);
+                }
+            }
+            levels
+        };
+        Ok(json!({
+            "timestamp": self.now(),
+            "asks": levels(Side::Sell),
+            "bids": levels(Side::Buy),
+        }).to_string())
+    }
+
+    fn order_book_json(&self, q: &Query) -> MockResult {
+        let market = self.market(q.require("market")?)?;
+        let mut asks: Vec<&MockOrder> = self.open_orders(&market.id, Side::Sell).collect();
+        asks.sort_by(|a, b| a.price.cmp(&b.price).then(a.id.cmp(&b.id)));
+        asks.truncate(q.number("asks_limit")?.unwrap_or(20));
+        let mut bids: Vec<&MockOrder> = self.open_orders(&market.id, Side::Buy).collect();
+        bids.sort_by(|a, b| b.price.cmp(&a.price).then(a.id.cmp(&b.id)));
+        bids.truncate(q.number("bids_limit")?.unwrap_or(20));
+        to_json(&OrderBook {
+            asks: asks.into_iter().map(|o| self.order_model(o, false)).collect(),
+            bids: bids.into_iter().map(|o| self.order_model(o, false)).collect(),
+        })
+    }
+
+    fn order_model(&self, o: &MockOrder, with_trades: bool) -> Order {
+        let trades = with_trades.then(|| {
+            self.trades
+                .iter()
+                .filter(|t| t.ask_id == o.id || t.bid_id == o.id)
+                .map(|t| self.trade_model(t, Some(o.side)))
+                .collect()
+        });
+        Order {
+            id: o.id,
+            side: o.side.to_string(),
+            price: o.price,
+            avg_price: if o.executed > Decimal::ZERO { o.funds / o.executed } else { Decimal::ZERO },
+            state: o.state.to_string(),
+            market: o.market.clone(),
+            created_at: format_timestamp(o.created_at),
+            volume: o.volume,
+            remaining_volume: o.remaining,
+            executed_volume: o.executed,
+            trades,
+        }
+    }
+
+    // `side` is the member's side for their own trades, None for public ones
+    fn trade_model(&self, t: &MockTrade, side: Option<Side>) -> Trade {
+        Trade {
+            id: t.id,
+            price: t.price,
+            volume: t.volume,
+            funds: Some(t.price * t.volume),
+            market: t.market.clone(),
+            created_at: format_timestamp(t.created_at),
+            side: Some(side.unwrap_or(t.taker).to_string()),
+            order_id: side.map(|side| match side {
+                Side::Buy => t.bid_id,
+                Side::Sell => t.ask_id,
+            }),
+        }
+    }
+
+    // the side `key` was on in trade `t`, if they were in it at all
+    fn member_side(&self, t: &MockTrade, key: &str) -> Option<Side> {
+        if self.orders[t.bid_id - 1].owner == key {
+            Some(Side::Buy)
+        } else if self.orders[t.ask_id - 1].owner == key {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+
+    fn trades_json(&self, q: &Query, key: Option<&str>) -> MockResult {
+        let market = self.market(q.require("market")?)?;
+        let trades = self.filter_trades(q, Some(&market.id), key)?;
+        to_json(&trades)
+    }
+
+    fn trades_history_json(&self, key: &str, q: &Query) -> MockResult {
+        let market = q.get("market");
+        let mut trades = self.filter_trades(q, market, Some(key))?;
+        let page = q.number("page")?.unwrap_or(1).max(1);
+        let limit = q.number("limit")?.unwrap_or(50);
+        trades = trades.into_iter().skip((page - 1) * limit).collect();
+        to_json(&trades)
+    }
+
+    fn filter_trades(&self, q: &Query, market: Option<&str>, key: Option<&str>) -> Result<Vec<Trade>, MockError> {
+        let limit = q.number("limit")?.unwrap_or(50);
+        let timestamp = q.number("timestamp")?;
+        let from = q.number("from")?;
+        let to = q.number("to")?;
+        let mut trades: Vec<Trade> = self.trades
+            .iter()
+            .filter(|t| market.is_none_or(|m| t.market == m))
+            .filter(|t| timestamp.is_none_or(|ts| (t.created_at as usize) < ts))
+            .filter(|t| from.is_none_or(|from| t.id > from))
+            .filter(|t| to.is_none_or(|to| t.id < to))
+            .filter_map(|t| match key {
+                Some(key) => self.member_side(t, key).map(|side| self.trade_model(t, Some(side))),
+                None => Some(self.trade_model(t, None)),
+            })
+            .collect();
+        if q.get("order_by") != Some("asc") {
+            trades.reverse();
+        }
+        trades.truncate(limit);
+        Ok(trades)
+    }
+
+    fn trades_simple_json(&self, q: &Query) -> MockResult {
+        let market = self.market(q.require("market")?)?;
+        let trades: Vec<SimpleTrade> = self.trades
+            .iter()
+            .rev()
+            .filter(|t| t.market == market.id)
+            .map(|t| SimpleTrade {
+                tid: t.id,
+                price: t.price,
+                amount: t.volume,
+                date: usize::try_from(t.created_at).unwrap_or(usize::MAX),
+                side: t.taker.to_string(),
+            })
+            .collect();
+        to_json(&trades)
+    }
+
+    // candles for every period from the first trade up to now, empty
+    // periods carry the previous close with no volume
+    fn candles(&self, market: &str, period: usize) -> Vec<Candle> {
+        let secs = (period * 60) as u64;
+        let trades: Vec<&MockTrade> = self.trades.iter().filter(|t| t.market == market).collect();
+        let Some(first) = trades.first() else { return Vec::new() };
+        let mut candles = Vec::new();
+        let mut start = first.created_at - first.created_at % secs;
+        let mut close = first.price;
+        let now = self.now();
+        while start <= now {
+            let bucket: Vec<&&MockTrade> = trades
+                .iter()
+                .filter(|t| t.created_at >= start && t.created_at < start + secs)
+                .collect();
+            let candle = match (bucket.first(), bucket.last()) {
+                (Some(open), Some(last)) => Candle {
+                    timestamp: usize::try_from(start).unwrap_or(usize::MAX),
+                    open: open.price,
+                    high: bucket.iter().map(|t| t.price).max().unwrap_or_default(),
+                    low: bucket.iter().map(|t| t.price).min().unwrap_or_default(),
+                    close: last.price,
+                    volume: bucket.iter().map(|t| t.volume).sum(),
+                },
+                _ => Candle {
+                    timestamp: usize::try_from(start).unwrap_or(usize::MAX),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: Decimal::ZERO,
+                },
+            };
+            close = candle.close;
+            candles.push(candle);
+            start += secs;
+        }
+        candles
+    }
+
+    fn kline(&self, q: &Query) -> Result<Vec<Candle>, MockError> {
+        let market = self.market(q.require("market")?)?;
+        let period = q.number("period")?.unwrap_or(1);
+        if Period::from_minutes(period).is_none() {
+            return Err(MockError::bad_param("Invalid period."));
+        }
+        let limit = q.number("limit")?.unwrap_or(30);
+        let mut candles = self.candles(&market.id, period);
+        match q.number("timestamp")? {
+            Some(timestamp) => {
+                candles.retain(|c| c.timestamp >= timestamp);
+                candles.truncate(limit);
+            }
+            None => {
+                let skip = candles.len().saturating_sub(limit);
+                candles.drain(..skip);
+            }
+        }
+        Ok(candles)
+    }
+
+    fn k_json(&self, q: &Query) -> MockResult {
+        to_json(&self.kline(q)?)
+    }
+
+    fn k_pending_json(&self, q: &Query) -> MockResult {
+        let k = self.kline(q)?;
+        let market = q.require("market")?;
+        let trade_id = q.number("trade_id")?.unwrap_or(0);
+        // like graviex, the trades since the last bar opened, which it
+        // already counts
+        let since = k.last().map_or(0, |c| c.timestamp as u64);
+        let trades: Vec<Trade> = self.trades
+            .iter()
+            .filter(|t| t.market == market && t.id > trade_id && t.created_at >= since)
+            .map(|t| self.trade_model(t, None))
+            .collect();
+        to_json(&KlineWithPending { k, trades })
+    }
+
+    fn currency_info_json(&self, q: &Query) -> MockResult {
+        let code = q.require("currency")?;
+        let listed = self.markets.iter().any(|m| m.base == code || m.quote == code);
+        if !listed {
+            return Err(MockError::bad_param("Currency does not exist."));
+        }
+        to_json(&CurrencyInfo {
+            code: Some(code.to_string()),
+            name: Some(code.to_uppercase()),
+            fixed: Some(8),
+            withdraw_fee: Some("0.0".to_string()),
+            min_withdraw: Some("0.0".to_string()),
+            confirmations: Some(6),
+            extra: HashMap::new(),
+        })
+    }
+
+    fn me_json(&self, key: &str) -> MockResult {
+        let member = &self.members[key];
+        to_json(&Member {
+            sn: member.sn.clone(),
+            name: None,
+            email: format!("{}@mock.graviex", member.sn.to_lowercase()),
+            activated: true,
+            accounts_filtered: member.balances
+                .iter()
+                .map(|(currency, b)| Account {
+                    currency: currency.clone(),
+                    balance: b.balance,
+                    locked: b.locked,
+                })
+                .collect(),
+        })
+    }
+
+    fn orders_json(&self, key: &str, q: &Query, default_state: Option<OrderState>) -> MockResult {
+        let market = q.get("market");
+        let state = match q.get("state") {
+            Some(s) => Some(parse_state(s)?),
+            None => default_state,
+        };
+        let limit = q.number("limit")?.unwrap_or(100);
+        let page = q.number("page")?.unwrap_or(1).max(1);
+        let mut orders: Vec<Order> = self.orders
+            .iter()
+            .filter(|o| o.owner == key)
+            .filter(|o| market.is_none_or(|m| o.market == m))
+            .filter(|o| state.is_none_or(|s| o.state == s))
+            .map(|o| self.order_model(o, false))
+            .collect();
+        if q.get("order_by") == Some("desc") {
+            orders.reverse();
+        }
+        let orders: Vec<Order> = orders.into_iter().skip((page - 1) * limit).take(limit).collect();
+        to_json(&orders)
+    }
+
+    fn new_order(&mut self, key: &str, market: &str, leg: &Query) -> Result<Order, MockError> {
+        let side = parse_side(leg.require("side")?)?;
+        let volume = leg.decimal("volume")?;
+        if leg.get("ord_type").is_some_and(|t| t != "limit") {
+            return Err(MockError::new(400, 2002, "Failed to create order. The mock only matches limit orders."));
+        }
+        let price = leg.decimal("price")?;
+        let id = self.place_order(key, market, side, volume, price)?;
+        Ok(self.order_model(&self.orders[id - 1], false))
+    }
+
+    fn orders_post_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let market = q.require("market")?.to_string();
+        let order = self.new_order(key, &market, q)?;
+        to_json(&order)
+    }
+
+    fn orders_multi_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let market = q.require("market")?.to_string();
+        // every orders[][side] starts a new order
+        let mut legs: Vec<Vec<(String, String)>> = Vec::new();
+        for (k, v) in q.0 {
+            let Some(field) = k.strip_prefix("orders[][").and_then(|k| k.strip_suffix(']')) else {
+                continue;
+            };
+            if field == "side" || legs.is_empty() {
+                legs.push(Vec::new());
+            }
+            if let Some(leg) = legs.last_mut() {
+                leg.push((field.to_string(), v.clone()));
+            }
+        }
+        if legs.is_empty() {
+            return Err(MockError::bad_param("Missing orders."));
+        }
+        let mut orders = Vec::new();
+        for leg in &legs {
+            orders.push(self.new_order(key, &market, &Query(leg))?);
+        }
+        to_json(&orders)
+    }
+
+    fn orders_clear_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let side = q.get("side").map(parse_side).transpose()?;
+        let ids: Vec<usize> = self.orders
+            .iter()
+            .filter(|o| o.owner == key && o.state == OrderState::Wait)
+            .filter(|o| side.is_none_or(|s| o.side == s))
+            .map(|o| o.id)
+            .collect();
+        for id in &ids {
+            self.cancel_order(key, *id)?;
+        }
+        let orders: Vec<Order> = ids.iter().map(|id| self.order_model(&self.orders[id - 1], false)).collect();
+        to_json(&orders)
+    }
+
+    fn order_json(&mut self, key: &str, q: &Query, delete: bool) -> MockResult {
+        let id = q.number("order_id")?
+            .or(q.number("id")?)
+            .ok_or_else(|| MockError::bad_param("Missing order_id."))?;
+        if self.orders.get(id.wrapping_sub(1)).is_none_or(|o| o.owner != key) {
+            return Err(MockError::new(404, 2004, "Order not found."));
+        }
+        if delete {
+            self.cancel_order(key, id)?;
+        }
+        to_json(&self.order_model(&self.orders[id - 1], !delete))
+    }
+
+    fn history_json(&self, key: &str, q: &Query) -> MockResult {
+        let member = &self.members[key];
+        let mut entries: Vec<HistoryEntry> = Vec::new();
+        for d in &member.deposits {
+            entries.push(HistoryEntry {
+                id: d.id,
+                kind: "deposit".to_string(),
+                currency: d.currency.clone(),
+                amount: d.amount,
+                fee: d.fee,
+                txid: d.txid.clone(),
+                state: d.state.clone(),
+                created_at: d.created_at.clone(),
+            });
+        }
+        for w in &member.withdraws {
+            entries.push(HistoryEntry {
+                id: w.id,
+                kind: "withdrawal".to_string(),
+                currency: w.currency.clone(),
+                amount: w.amount,
+                fee: w.fee,
+                txid: w.txid.clone(),
+                state: w.state.clone(),
+                created_at: w.created_at.clone(),
+            });
+        }
+        entries.retain(|e| q.get("currency").is_none_or(|c| e.currency == c));
+        entries.retain(|e| q.get("type").is_none_or(|t| e.kind == t));
+        entries.sort_by_key(|e| e.id);
+        if q.get("order_by") == Some("desc") {
+            entries.reverse();
+        }
+        entries.truncate(q.number("limit")?.unwrap_or(100));
+        to_json(&entries)
+    }
+
+    fn deposits_json(&self, key: &str, q: &Query) -> MockResult {
+        let currencies: Vec<&str> = q.get("currency").map(|c| c.split(',').collect()).unwrap_or_default();
+        let mut deposits: Vec<&Deposit> = self.members[key].deposits
+            .iter()
+            .filter(|d| currencies.is_empty() || currencies.contains(&d.currency.as_str()))
+            .filter(|d| q.get("state").is_none_or(|s| d.state == s))
+            .collect();
+        deposits.truncate(q.number("limit")?.unwrap_or(100));
+        to_json(&deposits)
+    }
+
+    fn deposit_json(&self, key: &str, q: &Query) -> MockResult {
+        let txid = q.require("txid")?;
+        let deposit = self.members[key].deposits
+            .iter()
+            .find(|d| d.txid.as_deref() == Some(txid))
+            .ok_or_else(|| MockError::new(404, 2012, "Deposit not found."))?;
+        to_json(deposit)
+    }
+
+    fn deposit_address_json(&self, key: &str, q: &Query) -> MockResult {
+        let currency = q.require("currency")?;
+        to_json(&DepositAddress {
+            currency: currency.to_string(),
+            address: Some(format!("mock-{}-{}", currency, self.members[key].sn.to_lowercase())),
+        })
+    }
+
+    fn withdraws_json(&self, key: &str, q: &Query) -> MockResult {
+        let currency = q.require("currency")?;
+        let mut withdraws: Vec<&Withdraw> = self.members[key].withdraws
+            .iter()
+            .filter(|w| w.currency == currency)
+            .filter(|w| q.get("state").is_none_or(|s| w.state == s))
+            .collect();
+        withdraws.truncate(q.number("limit")?.unwrap_or(100));
+        to_json(&withdraws)
+    }
+
+    fn create_withdraw_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let currency = q.require("currency")?.to_string();
+        let fund_uid = q.require("fund_uid")?.to_string();
+        let sum = q.decimal("sum")?;
+        let id = self.take_id();
+        let now = self.now();
+        let member = self.members.get_mut(key).expect("authenticated member");
+        let balance = member.balance(&currency);
+        if sum <= Decimal::ZERO || balance.balance < sum {
+            return Err(MockError::new(400, 2002, "Failed to create withdraw. Insufficient balance."));
+        }
+        balance.balance -= sum;
+        let withdraw = Withdraw {
+            id,
+            currency,
+            amount: sum,
+            fee: Some(Decimal::ZERO),
+            fund_uid: Some(fund_uid),
+            fund_extra: None,
+            txid: None,
+            state: "submitted".to_string(),
+            created_at: format_timestamp(now),
+            done_at: None,
+        };
+        let body = to_json(&withdraw)?;
+        member.withdraws.push(withdraw);
+        Ok(body)
+    }
+
+    fn fund_sources_json(&self, key: &str, q: &Query) -> MockResult {
+        let currency = q.require("currency")?;
+        let sources: Vec<&FundSource> = self.members[key].fund_sources
+            .iter()
+            .filter(|s| s.currency == currency)
+            .collect();
+        to_json(&sources)
+    }
+
+    fn create_fund_source_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let source = FundSource {
+            id: self.take_id(),
+            currency: q.require("currency")?.to_string(),
+            uid: q.require("uid")?.to_string(),
+            extra: q.get("extra").unwrap_or_default().to_string(),
+        };
+        let body = to_json(&source)?;
+        self.members.get_mut(key).expect("authenticated member").fund_sources.push(source);
+        Ok(body)
+    }
+
+    fn remove_fund_source_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let id = q.number("id")?.ok_or_else(|| MockError::bad_param("Missing id."))?;
+        let sources = &mut self.members.get_mut(key).expect("authenticated member").fund_sources;
+        let index = sources
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| MockError::new(404, 1000, "Fund source not found."))?;
+        to_json(&sources.remove(index))
+    }
+
+    fn settings_store_json(&mut self, key: &str, q: &Query) -> MockResult {
+        let data: serde_json::Map<String, serde_json::Value> = serde_json::from_str(q.require("data")?)
+            .map_err(|_| MockError::bad_param("Invalid data."))?;
+        let settings = &mut self.members.get_mut(key).expect("authenticated member").settings;
+        settings.extend(data);
+        to_json(settings)
+    }
+}
+
+// decoded request parameters
+struct Query<'a>(&'a [(String, String)]);
+
+impl Query<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn require(&self, key: &str) -> Result<&str, MockError> {
+        self.get(key).ok_or_else(|| MockError::bad_param(&format!("Missing {}.", key)))
+    }
+
+    fn number(&self, key: &str) -> Result<Option<usize>, MockError> {
+        self.get(key)
+            .map(|v| v.parse().map_err(|_| MockError::bad_param(&format!("Invalid {}.", key))))
+            .transpose()
+    }
+
+    fn decimal(&self, key: &str) -> Result<Decimal, MockError> {
+        Decimal::from_str(self.require(key)?).map_err(|_| MockError::bad_param(&format!("Invalid {}.", key)))
+    }
+}
+
+fn parse_side(side: &str) -> Result<Side, MockError> {
+    match side {
+        "buy" => Ok(Side::Buy),
+        "sell" => Ok(Side::Sell),
+        _ => Err(MockError::bad_param("Invalid side.")),
+    }
+}
+
+fn parse_state(state: &str) -> Result<OrderState, MockError> {
+    match state {
+        "wait" => Ok(OrderState::Wait),
+        "done" => Ok(OrderState::Done),
+        "cancel" => Ok(OrderState::Cancel),
+        _ => Err(MockError::bad_param("Invalid state.")),
+    }
+}
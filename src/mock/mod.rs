@@ -0,0 +1,227 @@
+//! A local stand-in for graviex, for testing without touching the real
+//! exchange. `MockServer` answers the v3 api over plain http on localhost,
+//! checking signatures and tonces and matching limit orders against an
+//! in-memory `MockExchange`. With the `stream` feature, `MockStreamServer`
+//! stands in for the websocket feed.
+//!
+//! Enabled with the `mock` feature.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use crate::GraviexClient;
+
+mod exchange;
+mod sign;
+#[cfg(feature = "stream")]
+mod stream;
+
+pub use exchange::{MockBalance, MockError, MockExchange, MockFailure, MockMarket, TONCE_WINDOW};
+#[cfg(feature = "stream")]
+pub use stream::MockStreamServer;
+
+/// Serves a `MockExchange` over http until dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    exchange: Arc<Mutex<MockExchange>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Serve `exchange` on a free port on 127.0.0.1.
+    ///
+    /// # Errors
+    /// returns `io::Error` if the port could not be bound
+    pub fn start(exchange: MockExchange) -> io::Result<MockServer> {
+        MockServer::bind("127.0.0.1:0", exchange)
+    }
+
+    /// Serve `exchange` on `addr`.
+    ///
+    /// # Errors
+    /// returns `io::Error` if the address could not be bound
+    pub fn bind<A: ToSocketAddrs>(addr: A, exchange: MockExchange) -> io::Result<MockServer> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let exchange = Arc::new(Mutex::new(exchange));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let exchange = Arc::clone(&exchange);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let exchange = Arc::clone(&exchange);
+                    thread::spawn(move || {
+                        // a client hanging up early is not our problem
+                        let _ = serve(stream, &exchange);
+                    });
+                }
+            })
+        };
+
+        Ok(MockServer { addr, exchange, shutdown, handle: Some(handle) })
+    }
+
+    /// Base url to hand to `GraviexClient::with_base_url`, like
+    /// `http://127.0.0.1:43117`.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Lock the exchange to seed or inspect it between requests.
+    ///
+    /// # Panics
+    /// panics if a request handler panicked while holding the lock
+    pub fn exchange(&self) -> MutexGuard<'_, MockExchange> {
+        self.exchange.lock().expect("mock exchange poisoned")
+    }
+
+    /// A client pointed at this server, registering the member if needed.
+    #[must_use]
+    pub fn client(&self, key: &str, secret: &str) -> GraviexClient {
+        let mut exchange = self.exchange();
+        if !exchange.has_member(key) {
+            exchange.add_member(key, secret);
+        }
+        GraviexClient::new(key, secret).with_base_url(&self.url())
+    }
+
+    /// Block the current thread serving requests until the process exits.
+    pub fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, exchange: &Mutex<MockExchange>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+    let method = method.to_string();
+    let target = target.to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let mut params = parse_query(query);
+    params.extend(parse_query(&String::from_utf8_lossy(&body)));
+
+    let answer = exchange.lock().map(|mut exchange| {
+        match exchange.take_failure(&method, path) {
+            Some(MockFailure::Unavailable) => Some((503, "<html>503 Service Unavailable</html>".to_string())),
+            Some(MockFailure::LostResponse) => {
+                exchange.handle(&method, path, &params);
+                None
+            }
+            None => Some(exchange.handle(&method, path, &params)),
+        }
+    });
+    let (status, body) = match answer {
+        Ok(Some(answer)) => answer,
+        // hang up, the request went through but the client never hears
+        Ok(None) => return Ok(()),
+        Err(_) => (500, "{\"error\":{\"code\":1000,\"message\":\"mock exchange poisoned\"}}".to_string()),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason(status), body.len(), body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Split a query string or form body into decoded `key=value` pairs,
+/// keeping their order.
+#[must_use]
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
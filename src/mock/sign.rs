@@ -0,0 +1,36 @@
+// the mock's own take on graviex's signatures, written apart from
+// `crate::signature` so a mistake there can't be mirrored here and pass
+// every test; both are held to the golden values in tests/signature.rs
+
+use sha2::{Digest, Sha256};
+
+const BLOCK: usize = 64; // sha256 block size in bytes
+
+// HMAC-SHA256 as RFC 2104 lays it out, hex encoded
+pub(crate) fn hmac_sha256_hex(secret: &str, message: &str) -> String {
+    let mut key = [0u8; BLOCK];
+    if secret.len() > BLOCK {
+        key[..32].copy_from_slice(&Sha256::digest(secret.as_bytes()));
+    } else {
+        key[..secret.len()].copy_from_slice(secret.as_bytes());
+    }
+    let inner = Sha256::new()
+        .chain_update(key.map(|b| b ^ 0x36))
+        .chain_update(message.as_bytes())
+        .finalize();
+    let outer = Sha256::new()
+        .chain_update(key.map(|b| b ^ 0x5c))
+        .chain_update(inner)
+        .finalize();
+    hex::encode(outer)
+}
+
+// what the client should have signed `params` with, signature left out:
+// `METHOD|path|query`, the query sorted by key with repeated keys like
+// `orders[][side]` kept in the order they came
+pub(crate) fn expected_signature(secret: &str, method: &str, path: &str, params: &[(String, String)]) -> String {
+    let mut params: Vec<&(String, String)> = params.iter().filter(|(k, _)| k != "signature").collect();
+    params.sort_by_key(|(k, _)| k.split('[').next().unwrap_or_default());
+    let query: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    hmac_sha256_hex(secret, &format!("{}|{}|{}", method, path, query.join("&")))
+}
@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tungstenite::{Message, WebSocket};
+
+use super::sign::hmac_sha256_hex;
+use crate::StreamError;
+
+// how long a connection thread waits on its socket before checking its outbox
+const TICK: Duration = Duration::from_millis(10);
+
+// what the server knows about one open connection
+#[derive(Debug)]
+struct Peer {
+    member: Option<String>, // key it authenticated as
+    streams: HashSet<String>,
+    outbox: Sender<Message>,
+    kicked: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    members: HashMap<String, String>, // secret by key
+    peers: HashMap<usize, Peer>, // by connection number
+    connections: usize, // ever accepted
+}
+
+/// A local stand-in for graviex's websocket feed, speaking the protocol
+/// described in `crate::stream`, until dropped.
+///
+/// Nothing is pushed on its own: tests `publish` frames to whoever is
+/// subscribed, and `drop_connections` to exercise reconnects.
+#[derive(Debug)]
+pub struct MockStreamServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockStreamServer {
+    /// Serve on a free port on 127.0.0.1.
+    ///
+    /// # Errors
+    /// returns `io::Error` if the port could not be bound
+    pub fn start() -> io::Result<MockStreamServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = Arc::clone(&state);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let state = Arc::clone(&state);
+                    let shutdown = Arc::clone(&shutdown);
+                    thread::spawn(move || {
+                        // a client hanging up is not our problem
+                        let _ = serve(stream, &state, &shutdown);
+                    });
+                }
+            })
+        };
+
+        Ok(MockStreamServer { addr, state, shutdown, handle: Some(handle) })
+    }
+
+    /// Url to hand to `StreamClient::new`, like `ws://127.0.0.1:43117`.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Let `key` authenticate with `secret`.
+    pub fn add_member(&self, key: &str, secret: &str) {
+        self.state().members.insert(key.to_string(), secret.to_string());
+    }
+
+    /// Push `{stream: payload}` to every connection subscribed to the
+    /// public `stream`, like `ethbtc.trades`, returning how many got it.
+    pub fn publish(&self, stream: &str, payload: &Value) -> usize {
+        self.push(stream, payload, |_| true)
+    }
+
+    /// Push `{stream: payload}` to `key`'s connections subscribed to the
+    /// private `stream`, `order` or `trade`, returning how many got it.
+    pub fn publish_to(&self, key: &str, stream: &str, payload: &Value) -> usize {
+        self.push(stream, payload, |peer| peer.member.as_deref() == Some(key))
+    }
+
+    fn push(&self, stream: &str, payload: &Value, to: impl Fn(&Peer) -> bool) -> usize {
+        let frame = Message::Text(json!({ stream: payload }).to_string());
+        self.state()
+            .peers
+            .values()
+            .filter(|peer| peer.streams.contains(stream) && to(peer))
+            .filter(|peer| peer.outbox.send(frame.clone()).is_ok())
+            .count()
+    }
+
+    /// Wait up to `timeout` for `count` connections to subscribe to
+    /// `stream`, returning whether they did.
+    #[must_use]
+    pub fn wait_for_subscribers(&self, stream: &str, count: usize, timeout: Duration) -> bool {
+        let until = Instant::now() + timeout;
+        loop {
+            let subscribed = self.state().peers.values().filter(|peer| peer.streams.contains(stream)).count();
+            if subscribed >= count {
+                return true;
+            }
+            if Instant::now() >= until {
+                return false;
+            }
+            thread::sleep(TICK);
+        }
+    }
+
+    /// Hang up on every open connection without a close frame, like a
+    /// network failure would.
+    pub fn drop_connections(&self) {
+        let mut state = self.state();
+        for peer in state.peers.values() {
+            peer.kicked.store(true, Ordering::SeqCst);
+        }
+        state.peers.clear();
+    }
+
+    /// Connections accepted so far, counting reconnects.
+    #[must_use]
+    pub fn connections(&self) -> usize {
+        self.state().connections
+    }
+}
+
+impl Drop for MockStreamServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.drop_connections();
+        // wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, state: &Mutex<State>, shutdown: &AtomicBool) -> Result<(), StreamError> {
+    let tcp = stream.try_clone()?;
+    let mut socket = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => StreamError::from(e),
+        tungstenite::HandshakeError::Interrupted(_) => StreamError::from(io::Error::from(io::ErrorKind::TimedOut)),
+    })?;
+    tcp.set_read_timeout(Some(TICK))?;
+
+    let (outbox, inbox) = mpsc::channel();
+    let kicked = Arc::new(AtomicBool::new(false));
+    let id = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.connections += 1;
+        let id = state.connections;
+        let peer = Peer { member: None, streams: HashSet::new(), outbox, kicked: Arc::clone(&kicked) };
+        state.peers.insert(id, peer);
+        id
+    };
+    let challenge = format!("{:016x}", fastrand::u64(..));
+    socket.send(Message::Text(json!({ "challenge": challenge }).to_string()))?;
+
+    let result = pump(&mut socket, &inbox, &kicked, shutdown, |frame| answer(state, id, &challenge, frame));
+    state.lock().unwrap_or_else(|e| e.into_inner()).peers.remove(&id);
+    if kicked.load(Ordering::SeqCst) {
+        // no close frame, just gone
+        let _ = tcp.shutdown(std::net::Shutdown::Both);
+    }
+    result
+}
+
+// shuttle frames both ways until either side is done
+fn pump(
+    socket: &mut WebSocket<TcpStream>,
+    inbox: &Receiver<Message>,
+    kicked: &AtomicBool,
+    shutdown: &AtomicBool,
+    mut answer: impl FnMut(&Value) -> Value)
+-> Result<(), StreamError> {
+    loop {
+        if kicked.load(Ordering::SeqCst) || shutdown.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        while let Ok(frame) = inbox.try_recv() {
+            socket.send(frame)?;
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let frame = serde_json::from_str(&text).unwrap_or(Value::Null);
+                socket.send(Message::Text(answer(&frame).to_string()))?;
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn error(code: i64, message: &str) -> Value {
+    json!({ "error": { "code": code, "message": message } })
+}
+
+// the reply to an auth or subscribe frame
+fn answer(state: &Mutex<State>, id: usize, challenge: &str, frame: &Value) -> Value {
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(auth) = frame.get("auth") {
+        let key = auth.get("access_key").and_then(Value::as_str).unwrap_or_default();
+        let given = auth.get("answer").and_then(Value::as_str).unwrap_or_default();
+        let Some(secret) = state.members.get(key) else {
+            return error(2008, "The access key does not exist.");
+        };
+        if given != hmac_sha256_hex(secret, &format!("{}{}", key, challenge)) {
+            return error(2005, "Signature is incorrect.");
+        }
+        let key = key.to_string();
+        if let Some(peer) = state.peers.get_mut(&id) {
+            peer.member = Some(key);
+        }
+        return json!({ "success": { "message": "Authenticated." } });
+    }
+    if frame.get("event").and_then(Value::as_str) == Some("subscribe") {
+        let streams: Vec<String> = frame
+            .get("streams")
+            .and_then(Value::as_array)
+            .map(|streams| streams.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        let Some(peer) = state.peers.get_mut(&id) else {
+            return error(2001, "Authorization failed.");
+        };
+        if peer.member.is_none() && streams.iter().any(|s| s == "order" || s == "trade") {
+            return error(2001, "Authorization failed.");
+        }
+        peer.streams.extend(streams.iter().cloned());
+        return json!({ "success": { "message": "subscribed", "streams": streams } });
+    }
+    error(1001, "Unknown frame.")
+}
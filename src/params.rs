@@ -0,0 +1,1021 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Decimal;
+
+/// Request parameters as `(key, value)` pairs, in the order they were added.
+/// Keys may repeat, like `orders[][side]` in `orders_multi`.
+pub type Params = Vec<(&'static str, String)>;
+
+/// Anything that can be turned into request parameters for graviex.
+pub trait ToParams {
+    fn to_params(&self) -> Params;
+}
+
+// push key=value when the optional parameter was set
+fn push_opt<T: ToString>(params: &mut Params, key: &'static str, value: Option<&T>) {
+    if let Some(value) = value {
+        params.push((key, value.to_string()));
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderType {
+    Limit,
+    Market,
+}
+
+impl OrderType {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderType::Limit => "limit",
+            OrderType::Market => "market",
+        }
+    }
+}
+
+/// What kind of order to place, and its price if it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderKind {
+    Limit { price: Decimal }, // price per unit
+    Market, // at whatever the book offers
+}
+
+impl OrderKind {
+    /// The limit price, `None` for market orders.
+    #[must_use]
+    pub fn price(self) -> Option<Decimal> {
+        match self {
+            OrderKind::Limit { price } => Some(price),
+            OrderKind::Market => None,
+        }
+    }
+
+    #[must_use]
+    pub fn ord_type(self) -> OrderType {
+        match self {
+            OrderKind::Limit { .. } => OrderType::Limit,
+            OrderKind::Market => OrderType::Market,
+        }
+    }
+
+    // the price or ord_type param graviex wants for this kind, under the
+    // given keys
+    fn push_params(self, params: &mut Params, price: &'static str, ord_type: &'static str) {
+        match self {
+            OrderKind::Limit { price: limit } => params.push((price, limit.to_string())),
+            // graviex takes orders as limit orders unless told otherwise
+            OrderKind::Market => params.push((ord_type, OrderType::Market.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderState {
+    Wait, // active orders
+    Done, // fully executed
+    Cancel, // canceled
+}
+
+impl OrderState {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderState::Wait => "wait",
+            OrderState::Done => "done",
+            OrderState::Cancel => "cancel",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderBy {
+    Asc,
+    Desc,
+}
+
+impl OrderBy {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderBy::Asc => "asc",
+            OrderBy::Desc => "desc",
+        }
+    }
+}
+
+/// Which side of the funding history to list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HistoryKind {
+    Deposit,
+    Withdrawal,
+}
+
+impl HistoryKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HistoryKind::Deposit => "deposit",
+            HistoryKind::Withdrawal => "withdrawal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DepositState {
+    Submitting, // seen on chain, not yet confirmed
+    Cancelled,
+    Submitted, // confirmed, waiting for graviex
+    Rejected,
+    Accepted, // credited
+    Checked,
+    Warning,
+}
+
+impl DepositState {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DepositState::Submitting => "submitting",
+            DepositState::Cancelled => "cancelled",
+            DepositState::Submitted => "submitted",
+            DepositState::Rejected => "rejected",
+            DepositState::Accepted => "accepted",
+            DepositState::Checked => "checked",
+            DepositState::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WithdrawState {
+    Submitting,
+    Submitted, // waiting for graviex
+    Canceled,
+    Accepted,
+    Suspect, // held for review
+    Rejected,
+    Processing, // being sent
+    AlmostDone,
+    Done, // sent
+    Failed,
+}
+
+impl WithdrawState {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WithdrawState::Submitting => "submitting",
+            WithdrawState::Submitted => "submitted",
+            WithdrawState::Canceled => "canceled",
+            WithdrawState::Accepted => "accepted",
+            WithdrawState::Suspect => "suspect",
+            WithdrawState::Rejected => "rejected",
+            WithdrawState::Processing => "processing",
+            WithdrawState::AlmostDone => "almost_done",
+            WithdrawState::Done => "done",
+            WithdrawState::Failed => "failed",
+        }
+    }
+}
+
+/// k line bar sizes graviex serves.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Period {
+    Minute1,
+    Minute5,
+    Minute15,
+    Minute30,
+    Hour1,
+    Hour2,
+    Hour4,
+    Hour6,
+    Hour12,
+    Day1,
+    Day3,
+    Week1,
+}
+
+impl Period {
+    pub const ALL: [Period; 12] = [
+        Period::Minute1, Period::Minute5, Period::Minute15, Period::Minute30,
+        Period::Hour1, Period::Hour2, Period::Hour4, Period::Hour6,
+        Period::Hour12, Period::Day1, Period::Day3, Period::Week1,
+    ];
+
+    /// Length of the bar in minutes, which is what graviex expects.
+    #[must_use]
+    pub fn minutes(self) -> usize {
+        match self {
+            Period::Minute1 => 1,
+            Period::Minute5 => 5,
+            Period::Minute15 => 15,
+            Period::Minute30 => 30,
+            Period::Hour1 => 60,
+            Period::Hour2 => 120,
+            Period::Hour4 => 240,
+            Period::Hour6 => 360,
+            Period::Hour12 => 720,
+            Period::Day1 => 1440,
+            Period::Day3 => 4320,
+            Period::Week1 => 10080,
+        }
+    }
+
+    /// Length of the bar in seconds, the step between k line timestamps.
+    #[must_use]
+    pub fn seconds(self) -> usize {
+        self.minutes() * 60
+    }
+
+    /// The period lasting `minutes`, if graviex serves one.
+    #[must_use]
+    pub fn from_minutes(minutes: usize) -> Option<Period> {
+        Period::ALL.into_iter().find(|p| p.minutes() == minutes)
+    }
+}
+
+macro_rules! display_as_str {
+    ($($t:ty),*) => {
+        $(impl fmt::Display for $t {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        })*
+    };
+}
+
+display_as_str!(Side, OrderType, OrderState, OrderBy, HistoryKind, DepositState, WithdrawState);
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.minutes())
+    }
+}
+
+/// A new order for `orders_post`, built with `NewOrder::limit` or
+/// `NewOrder::market`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewOrder {
+    pub market: String, // unique market id like "btcusd"
+    pub side: Side,
+    pub volume: Decimal, // amount to buy or sell
+    pub kind: OrderKind,
+}
+
+impl NewOrder {
+    /// A limit order to `side` `volume` at `price`.
+    #[must_use]
+    pub fn limit(market: &str, side: Side, volume: Decimal, price: Decimal) -> NewOrder {
+        NewOrder {
+            market: market.to_string(),
+            side,
+            volume,
+            kind: OrderKind::Limit { price },
+        }
+    }
+
+    /// A market order to `side` `volume` at whatever the book offers.
+    #[must_use]
+    pub fn market(market: &str, side: Side, volume: Decimal) -> NewOrder {
+        NewOrder {
+            market: market.to_string(),
+            side,
+            volume,
+            kind: OrderKind::Market,
+        }
+    }
+}
+
+impl ToParams for NewOrder {
+    fn to_params(&self) -> Params {
+        let mut params = vec![
+            ("market", self.market.clone()),
+            ("side", self.side.to_string()),
+            ("volume", self.volume.to_string()),
+        ];
+        self.kind.push_params(&mut params, "price", "ord_type");
+        params
+    }
+}
+
+/// One order of a `MultiOrder`, built with `OrderLeg::limit` or
+/// `OrderLeg::market`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderLeg {
+    pub side: Side,
+    pub volume: Decimal,
+    pub kind: OrderKind,
+}
+
+impl OrderLeg {
+    #[must_use]
+    pub fn limit(side: Side, volume: Decimal, price: Decimal) -> OrderLeg {
+        OrderLeg { side, volume, kind: OrderKind::Limit { price } }
+    }
+
+    #[must_use]
+    pub fn market(side: Side, volume: Decimal) -> OrderLeg {
+        OrderLeg { side, volume, kind: OrderKind::Market }
+    }
+}
+
+/// Several orders on one market for `orders_multi`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiOrder {
+    pub market: String,
+    pub orders: Vec<OrderLeg>,
+}
+
+impl MultiOrder {
+    #[must_use]
+    pub fn new(market: &str) -> MultiOrder {
+        MultiOrder { market: market.to_string(), orders: Vec::new() }
+    }
+
+    #[must_use]
+    pub fn push(mut self, leg: OrderLeg) -> MultiOrder {
+        self.orders.push(leg);
+        self
+    }
+
+    /// Each leg as a standalone `NewOrder` on this market.
+    #[must_use]
+    pub fn new_orders(&self) -> Vec<NewOrder> {
+        self.orders
+            .iter()
+            .map(|leg| NewOrder {
+                market: self.market.clone(),
+                side: leg.side,
+                volume: leg.volume,
+                kind: leg.kind,
+            })
+            .collect()
+    }
+}
+
+impl ToParams for MultiOrder {
+    fn to_params(&self) -> Params {
+        let mut params = vec![("market", self.market.clone())];
+        for leg in &self.orders {
+            params.push(("orders[][side]", leg.side.to_string()));
+            params.push(("orders[][volume]", leg.volume.to_string()));
+            leg.kind.push_params(&mut params, "orders[][price]", "orders[][ord_type]");
+        }
+        params
+    }
+}
+
+/// Filters for `orders_get`, every field is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrdersQuery {
+    pub market: Option<String>,
+    pub state: Option<OrderState>, // graviex defaults to wait
+    pub limit: Option<usize>, // default 100
+    pub page: Option<usize>,
+    pub order_by: Option<OrderBy>,
+}
+
+impl OrdersQuery {
+    #[must_use]
+    pub fn new() -> OrdersQuery {
+        OrdersQuery::default()
+    }
+
+    #[must_use]
+    pub fn market(mut self, market: &str) -> OrdersQuery {
+        self.market = Some(market.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn state(mut self, state: OrderState) -> OrdersQuery {
+        self.state = Some(state);
+        self
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> OrdersQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn page(mut self, page: usize) -> OrdersQuery {
+        self.page = Some(page);
+        self
+    }
+
+    #[must_use]
+    pub fn order_by(mut self, order_by: OrderBy) -> OrdersQuery {
+        self.order_by = Some(order_by);
+        self
+    }
+}
+
+impl ToParams for OrdersQuery {
+    fn to_params(&self) -> Params {
+        let mut params = Params::new();
+        push_opt(&mut params, "market", self.market.as_ref());
+        push_opt(&mut params, "state", self.state.as_ref());
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "page", self.page.as_ref());
+        push_opt(&mut params, "order_by", self.order_by.as_ref());
+        params
+    }
+}
+
+/// Filters for `orders_history`, every field is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrdersHistoryQuery {
+    pub market: Option<String>,
+    pub state: Option<OrderState>,
+    pub limit: Option<usize>, // default 100
+    pub from: Option<String>, // date/time
+    pub to: Option<String>, // date/time
+    pub page: Option<usize>,
+    pub order_by: Option<OrderBy>,
+}
+
+impl OrdersHistoryQuery {
+    #[must_use]
+    pub fn new() -> OrdersHistoryQuery {
+        OrdersHistoryQuery::default()
+    }
+
+    #[must_use]
+    pub fn market(mut self, market: &str) -> OrdersHistoryQuery {
+        self.market = Some(market.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn state(mut self, state: OrderState) -> OrdersHistoryQuery {
+        self.state = Some(state);
+        self
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> OrdersHistoryQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn from(mut self, from: &str) -> OrdersHistoryQuery {
+        self.from = Some(from.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn to(mut self, to: &str) -> OrdersHistoryQuery {
+        self.to = Some(to.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn page(mut self, page: usize) -> OrdersHistoryQuery {
+        self.page = Some(page);
+        self
+    }
+
+    #[must_use]
+    pub fn order_by(mut self, order_by: OrderBy) -> OrdersHistoryQuery {
+        self.order_by = Some(order_by);
+        self
+    }
+}
+
+impl ToParams for OrdersHistoryQuery {
+    fn to_params(&self) -> Params {
+        let mut params = Params::new();
+        push_opt(&mut params, "market", self.market.as_ref());
+        push_opt(&mut params, "state", self.state.as_ref());
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "from", self.from.as_ref());
+        push_opt(&mut params, "to", self.to.as_ref());
+        push_opt(&mut params, "page", self.page.as_ref());
+        push_opt(&mut params, "order_by", self.order_by.as_ref());
+        params
+    }
+}
+
+/// Parameters for `order_book`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBookQuery {
+    pub market: String,
+    pub asks_limit: Option<usize>, // default 20
+    pub bids_limit: Option<usize>, // default 20
+}
+
+impl OrderBookQuery {
+    #[must_use]
+    pub fn new(market: &str) -> OrderBookQuery {
+        OrderBookQuery { market: market.to_string(), asks_limit: None, bids_limit: None }
+    }
+
+    #[must_use]
+    pub fn asks_limit(mut self, limit: usize) -> OrderBookQuery {
+        self.asks_limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn bids_limit(mut self, limit: usize) -> OrderBookQuery {
+        self.bids_limit = Some(limit);
+        self
+    }
+}
+
+impl ToParams for OrderBookQuery {
+    fn to_params(&self) -> Params {
+        let mut params = vec![("market", self.market.clone())];
+        push_opt(&mut params, "asks_limit", self.asks_limit.as_ref());
+        push_opt(&mut params, "bids_limit", self.bids_limit.as_ref());
+        params
+    }
+}
+
+/// Parameters for `depth`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthQuery {
+    pub market: String,
+    pub limit: Option<usize>, // number of price levels, default 100
+    pub order: Option<OrderBy>,
+}
+
+impl DepthQuery {
+    #[must_use]
+    pub fn new(market: &str) -> DepthQuery {
+        DepthQuery { market: market.to_string(), limit: None, order: None }
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> DepthQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn order(mut self, order: OrderBy) -> DepthQuery {
+        self.order = Some(order);
+        self
+    }
+}
+
+impl ToParams for DepthQuery {
+    fn to_params(&self) -> Params {
+        let mut params = vec![("market", self.market.clone())];
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "order", self.order.as_ref());
+        params
+    }
+}
+
+/// Parameters for `trades` and `trades_my`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradesQuery {
+    pub market: String,
+    pub limit: Option<usize>, // default 50
+    pub timestamp: Option<usize>, // only trades executed before this unix time
+    pub from: Option<usize>, // only trades with a greater id
+    pub to: Option<usize>, // only trades with a smaller id
+    pub order_by: Option<OrderBy>,
+}
+
+impl TradesQuery {
+    #[must_use]
+    pub fn new(market: &str) -> TradesQuery {
+        TradesQuery {
+            market: market.to_string(),
+            limit: None,
+            timestamp: None,
+            from: None,
+            to: None,
+            order_by: None,
+        }
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> TradesQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn timestamp(mut self, timestamp: usize) -> TradesQuery {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    #[must_use]
+    pub fn from(mut self, trade_id: usize) -> TradesQuery {
+        self.from = Some(trade_id);
+        self
+    }
+
+    #[must_use]
+    pub fn to(mut self, trade_id: usize) -> TradesQuery {
+        self.to = Some(trade_id);
+        self
+    }
+
+    #[must_use]
+    pub fn order_by(mut self, order_by: OrderBy) -> TradesQuery {
+        self.order_by = Some(order_by);
+        self
+    }
+}
+
+impl ToParams for TradesQuery {
+    fn to_params(&self) -> Params {
+        let mut params = vec![("market", self.market.clone())];
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "timestamp", self.timestamp.as_ref());
+        push_opt(&mut params, "from", self.from.as_ref());
+        push_opt(&mut params, "to", self.to.as_ref());
+        push_opt(&mut params, "order_by", self.order_by.as_ref());
+        params
+    }
+}
+
+/// Filters for `trades_history`, every field is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradesHistoryQuery {
+    pub market: Option<String>,
+    pub limit: Option<usize>,
+    pub from: Option<usize>, // trade id
+    pub to: Option<usize>, // trade id
+    pub page: Option<usize>,
+    pub order_by: Option<OrderBy>,
+}
+
+impl TradesHistoryQuery {
+    #[must_use]
+    pub fn new() -> TradesHistoryQuery {
+        TradesHistoryQuery::default()
+    }
+
+    #[must_use]
+    pub fn market(mut self, market: &str) -> TradesHistoryQuery {
+        self.market = Some(market.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> TradesHistoryQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn from(mut self, trade_id: usize) -> TradesHistoryQuery {
+        self.from = Some(trade_id);
+        self
+    }
+
+    #[must_use]
+    pub fn to(mut self, trade_id: usize) -> TradesHistoryQuery {
+        self.to = Some(trade_id);
+        self
+    }
+
+    #[must_use]
+    pub fn page(mut self, page: usize) -> TradesHistoryQuery {
+        self.page = Some(page);
+        self
+    }
+
+    #[must_use]
+    pub fn order_by(mut self, order_by: OrderBy) -> TradesHistoryQuery {
+        self.order_by = Some(order_by);
+        self
+    }
+}
+
+impl ToParams for TradesHistoryQuery {
+    fn to_params(&self) -> Params {
+        let mut params = Params::new();
+        push_opt(&mut params, "market", self.market.as_ref());
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "from", self.from.as_ref());
+        push_opt(&mut params, "to", self.to.as_ref());
+        push_opt(&mut params, "page", self.page.as_ref());
+        push_opt(&mut params, "order_by", self.order_by.as_ref());
+        params
+    }
+}
+
+/// Parameters for `kline`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlineQuery {
+    pub market: String,
+    pub period: Period, // graviex defaults to one minute
+    pub limit: Option<usize>, // default 20
+    pub timestamp: Option<usize>, // only bars starting after this unix time
+}
+
+impl KlineQuery {
+    #[must_use]
+    pub fn new(market: &str, period: Period) -> KlineQuery {
+        KlineQuery { market: market.to_string(), period, limit: None, timestamp: None }
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> KlineQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn timestamp(mut self, timestamp: usize) -> KlineQuery {
+        self.timestamp = Some(timestamp);
+        self
+    }
+}
+
+impl ToParams for KlineQuery {
+    fn to_params(&self) -> Params {
+        let mut params = vec![
+            ("market", self.market.clone()),
+            ("period", self.period.to_string()),
+        ];
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "timestamp", self.timestamp.as_ref());
+        params
+    }
+}
+
+/// Parameters for `kline_pending`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlinePendingQuery {
+    pub kline: KlineQuery,
+    pub trade_id: usize, // id of the first trade you received
+}
+
+impl KlinePendingQuery {
+    #[must_use]
+    pub fn new(kline: KlineQuery, trade_id: usize) -> KlinePendingQuery {
+        KlinePendingQuery { kline, trade_id }
+    }
+}
+
+impl ToParams for KlinePendingQuery {
+    fn to_params(&self) -> Params {
+        let mut params = self.kline.to_params();
+        params.push(("trade_id", self.trade_id.to_string()));
+        params
+    }
+}
+
+/// Filters for `history`, every field is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryQuery {
+    pub currency: Option<String>,
+    pub limit: Option<usize>, // default 100
+    pub kind: Option<HistoryKind>, // sent as type
+    pub from: Option<String>, // date/time
+    pub to: Option<String>, // date/time
+    pub page: Option<usize>,
+    pub order_by: Option<OrderBy>,
+}
+
+impl HistoryQuery {
+    #[must_use]
+    pub fn new() -> HistoryQuery {
+        HistoryQuery::default()
+    }
+
+    #[must_use]
+    pub fn currency(mut self, currency: &str) -> HistoryQuery {
+        self.currency = Some(currency.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> HistoryQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn kind(mut self, kind: HistoryKind) -> HistoryQuery {
+        self.kind = Some(kind);
+        self
+    }
+
+    #[must_use]
+    pub fn from(mut self, from: &str) -> HistoryQuery {
+        self.from = Some(from.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn to(mut self, to: &str) -> HistoryQuery {
+        self.to = Some(to.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn page(mut self, page: usize) -> HistoryQuery {
+        self.page = Some(page);
+        self
+    }
+
+    #[must_use]
+    pub fn order_by(mut self, order_by: OrderBy) -> HistoryQuery {
+        self.order_by = Some(order_by);
+        self
+    }
+}
+
+impl ToParams for HistoryQuery {
+    fn to_params(&self) -> Params {
+        let mut params = Params::new();
+        push_opt(&mut params, "currency", self.currency.as_ref());
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "type", self.kind.as_ref());
+        push_opt(&mut params, "from", self.from.as_ref());
+        push_opt(&mut params, "to", self.to.as_ref());
+        push_opt(&mut params, "page", self.page.as_ref());
+        push_opt(&mut params, "order_by", self.order_by.as_ref());
+        params
+    }
+}
+
+/// Filters for `deposits`, every field is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepositsQuery {
+    pub currencies: Vec<String>, // sent comma separated like gio,btc,doge
+    pub limit: Option<usize>, // default 100
+    pub state: Option<DepositState>,
+}
+
+impl DepositsQuery {
+    #[must_use]
+    pub fn new() -> DepositsQuery {
+        DepositsQuery::default()
+    }
+
+    #[must_use]
+    pub fn currency(mut self, currency: &str) -> DepositsQuery {
+        self.currencies.push(currency.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> DepositsQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn state(mut self, state: DepositState) -> DepositsQuery {
+        self.state = Some(state);
+        self
+    }
+}
+
+impl ToParams for DepositsQuery {
+    fn to_params(&self) -> Params {
+        let mut params = Params::new();
+        if !self.currencies.is_empty() {
+            params.push(("currency", self.currencies.join(",")));
+        }
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "state", self.state.as_ref());
+        params
+    }
+}
+
+/// Parameters for `withdraws`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawsQuery {
+    pub currency: String,
+    pub limit: Option<usize>,
+    pub state: Option<WithdrawState>,
+}
+
+impl WithdrawsQuery {
+    #[must_use]
+    pub fn new(currency: &str) -> WithdrawsQuery {
+        WithdrawsQuery { currency: currency.to_string(), limit: None, state: None }
+    }
+
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> WithdrawsQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    #[must_use]
+    pub fn state(mut self, state: WithdrawState) -> WithdrawsQuery {
+        self.state = Some(state);
+        self
+    }
+}
+
+impl ToParams for WithdrawsQuery {
+    fn to_params(&self) -> Params {
+        let mut params = vec![("currency", self.currency.clone())];
+        push_opt(&mut params, "limit", self.limit.as_ref());
+        push_opt(&mut params, "state", self.state.as_ref());
+        params
+    }
+}
+
+/// A withdrawal for `create_withdraw`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewWithdraw {
+    pub currency: String, // like btc
+    pub fund_uid: String, // address to withdraw to
+    pub sum: Decimal, // amount to withdraw
+    pub provider: Option<String>,
+    pub speed_up: Option<String>,
+}
+
+impl NewWithdraw {
+    #[must_use]
+    pub fn new(currency: &str, fund_uid: &str, sum: Decimal) -> NewWithdraw {
+        NewWithdraw {
+            currency: currency.to_string(),
+            fund_uid: fund_uid.to_string(),
+            sum,
+            provider: None,
+            speed_up: None,
+        }
+    }
+
+    #[must_use]
+    pub fn provider(mut self, provider: &str) -> NewWithdraw {
+        self.provider = Some(provider.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn speed_up(mut self, speed_up: &str) -> NewWithdraw {
+        self.speed_up = Some(speed_up.to_string());
+        self
+    }
+}
+
+impl ToParams for NewWithdraw {
+    fn to_params(&self) -> Params {
+        let mut params = vec![
+            ("currency", self.currency.clone()),
+            ("fund_uid", self.fund_uid.clone()),
+            ("sum", self.sum.to_string()),
+        ];
+        push_opt(&mut params, "provider", self.provider.as_ref());
+        push_opt(&mut params, "speed_up", self.speed_up.as_ref());
+        params
+    }
+}
+
+/// A withdraw address for `fund_source_create`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewFundSource {
+    pub currency: String, // like btc
+    pub uid: String, // address of the fund source
+    pub extra: String, // label for the address
+}
+
+impl NewFundSource {
+    #[must_use]
+    pub fn new(currency: &str, uid: &str, extra: &str) -> NewFundSource {
+        NewFundSource {
+            currency: currency.to_string(),
+            uid: uid.to_string(),
+            extra: extra.to_string(),
+        }
+    }
+}
+
+impl ToParams for NewFundSource {
+    fn to_params(&self) -> Params {
+        vec![
+            ("currency", self.currency.clone()),
+            ("uid", self.uid.clone()),
+            ("extra", self.extra.clone()),
+        ]
+    }
+}
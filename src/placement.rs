@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use crate::logging;
+use crate::time::{self, parse_timestamp};
+use crate::*;
+
+/// The definitive outcome of `GraviexClient::place_order_safely`.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // built once per order, boxing buys nothing
+pub enum Placement {
+    /// The order is on graviex, either straight from `orders_post` or
+    /// found on the exchange after an ambiguous failure.
+    Placed(Order),
+    /// Graviex definitely doesn't have the order: it refused it, or it
+    /// never got there. Holds why.
+    NotPlaced(GraviexError),
+    /// The order failed ambiguously and couldn't be found afterwards, or
+    /// couldn't be looked for, so it may still be on graviex, perhaps not
+    /// listed yet. Holds the failure of the order itself.
+    Unknown(GraviexError),
+}
+
+/// How `place_order_safely` settles ambiguous failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacementPolicy {
+    pub resubmits: u32, // attempts after the first, for orders that never reached graviex
+    pub settle: Duration, // wait before looking, so a slow order has time to land
+    pub skew: Duration, // how far graviex's created_at may lag our clock
+}
+
+impl Default for PlacementPolicy {
+    fn default() -> PlacementPolicy {
+        PlacementPolicy {
+            resubmits: 0,
+            settle: Duration::from_secs(1),
+            skew: Duration::from_secs(2),
+        }
+    }
+}
+
+impl PlacementPolicy {
+    #[must_use]
+    pub fn resubmits(mut self, resubmits: u32) -> PlacementPolicy {
+        self.resubmits = resubmits;
+        self
+    }
+
+    #[must_use]
+    pub fn settle(mut self, settle: Duration) -> PlacementPolicy {
+        self.settle = settle;
+        self
+    }
+
+    #[must_use]
+    pub fn skew(mut self, skew: Duration) -> PlacementPolicy {
+        self.skew = skew;
+        self
+    }
+}
+
+/// Whether `error` leaves us unsure if graviex acted on a POST: the
+/// connection died, the gateway failed, or the answer was garbled.
+/// Errors graviex explains itself mean the request was refused.
+#[must_use]
+pub fn is_ambiguous(error: &GraviexError) -> bool {
+    match error {
+        GraviexError::Transport(_) => !never_sent(error),
+        GraviexError::Decode { .. } => true,
+        GraviexError::Status { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+/// Whether `error` means the request never left us: graviex's address
+/// couldn't be resolved or nothing accepted the connection.
+#[must_use]
+pub fn never_sent(error: &GraviexError) -> bool {
+    match error {
+        GraviexError::Transport(minreq::Error::AddressNotFound) => true,
+        GraviexError::Transport(minreq::Error::IoError(e)) => {
+            matches!(e.kind(), io::ErrorKind::ConnectionRefused | io::ErrorKind::AddrNotAvailable)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `found` on the exchange could be `order` sent at `sent_at`,
+/// created no more than `skew` seconds before it by graviex's clock.
+#[must_use]
+pub fn matches_order(found: &Order, order: &NewOrder, sent_at: u64, skew: u64) -> bool {
+    let price_matches = match order.kind {
+        OrderKind::Limit { price } => found.price == price,
+        OrderKind::Market => true,
+    };
+    let created_at = parse_timestamp(&found.created_at);
+    found.market == order.market
+        && found.side == order.side.as_str()
+        && found.volume == order.volume
+        && price_matches
+        && created_at.is_some_and(|at| at + skew >= sent_at)
+}
+
+impl GraviexClient {
+    /// Place `order` without risking a double fill.
+    ///
+    /// When `orders_post` fails in a way that leaves the outcome unknown,
+    /// like a timeout, this waits for `PlacementPolicy::settle` and looks
+    /// for the order among your open and recent orders. An order is
+    /// recognised by market, side, price, volume and `created_at`, leaving
+    /// out orders already open before it was sent, so an identical order
+    /// sent by other means in the meantime can still be mistaken for it.
+    /// One that can't be found is `Placement::Unknown`, never sent again,
+    /// since it may only be slow to show up. Orders are only sent again,
+    /// up to `PlacementPolicy::resubmits` times, when they never reached
+    /// graviex at all.
+    pub fn place_order_safely(&self, order: &NewOrder) -> Placement {
+        let policy = self.placement;
+        let existing: HashSet<usize> = match self.open_orders(order) {
+            Ok(open) => open.iter().map(|open| open.id).collect(),
+            Err(e) => return Placement::NotPlaced(e),
+        };
+        let mut resubmits = 0;
+        loop {
+            let sent_at = self.server_now();
+            let error = match self.orders_post(order) {
+                Ok(placed) => return Placement::Placed(placed),
+                Err(e) if never_sent(&e) && resubmits < policy.resubmits => {
+                    resubmits += 1;
+                    tracing::warn!(error = %logging::summary(&e), resubmits, "order never reached graviex, sending it again");
+                    thread::sleep(policy.settle);
+                    continue;
+                }
+                Err(e) if is_ambiguous(&e) => e,
+                Err(e) => return Placement::NotPlaced(e),
+            };
+            tracing::warn!(error = %logging::summary(&error), "order outcome unknown, looking it up");
+            thread::sleep(policy.settle);
+            return match self.find_order(order, &existing, sent_at, policy.skew) {
+                Ok(Some(found)) => Placement::Placed(found),
+                Ok(None) => Placement::Unknown(error),
+                Err(e) => {
+                    tracing::warn!(error = %logging::summary(&e), "could not look the order up");
+                    Placement::Unknown(error)
+                }
+            };
+        }
+    }
+
+    // our clock moved onto graviex's, in seconds
+    fn server_now(&self) -> u64 {
+        let now = i64::try_from(time::now()).unwrap_or(i64::MAX);
+        u64::try_from(now + self.tonce.offset() / 1000).unwrap_or_default()
+    }
+
+    // open orders in the market of `order`, newest first
+    fn open_orders(&self, order: &NewOrder) -> Result<Vec<Order>, GraviexError> {
+        self.orders_get(&OrdersQuery::new()
+            .market(&order.market)
+            .order_by(OrderBy::Desc))
+    }
+
+    // newest open or recent order matching `order`, other than `existing`
+    fn find_order(
+        &self,
+        order: &NewOrder,
+        existing: &HashSet<usize>,
+        sent_at: u64,
+        skew: Duration)
+    -> Result<Option<Order>, GraviexError> {
+        let open = self.open_orders(order)?;
+        let recent = self.orders_history(&OrdersHistoryQuery::new()
+            .market(&order.market)
+            .order_by(OrderBy::Desc))?;
+
+        Ok(open
+            .into_iter()
+            .chain(recent)
+            .filter(|found| !existing.contains(&found.id))
+            .filter(|found| matches_order(found, order, sent_at, skew.as_secs()))
+            .max_by_key(|found| found.id))
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+/// Market data a `Poller` can follow.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Ticker(String), // one market's ticker, like Ticker("ethbtc")
+    Depth(String), // one market's order book
+    Trades(String), // one market's public trades
+    Kline(String, Period), // one market's k lines
+}
+
+impl Topic {
+    #[must_use]
+    pub fn market(&self) -> &str {
+        match self {
+            Topic::Ticker(market) | Topic::Depth(market) | Topic::Trades(market) | Topic::Kline(market, _) => market,
+        }
+    }
+
+    // the request serving this topic, shared with every topic it serves
+    fn request(&self) -> Request {
+        match self {
+            // one tickers call covers every market
+            Topic::Ticker(_) => Request::Tickers,
+            Topic::Depth(market) => Request::Depth(market.clone()),
+            Topic::Trades(market) => Request::Trades(market.clone()),
+            Topic::Kline(market, period) => Request::Kline(market.clone(), *period),
+        }
+    }
+}
+
+/// Something that changed since the subscriber last heard.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // passed by reference, boxing buys nothing
+pub enum MarketEvent {
+    /// The market's ticker moved.
+    Ticker { market: String, ticker: Ticker },
+    /// The book changed, `changes` lists the levels that did.
+    Depth { market: String, book: OrderBookState, changes: Vec<LevelChange> },
+    /// Trades not seen before, oldest first.
+    Trades { market: String, trades: Vec<Trade> },
+    /// Bars that are new or still filling, oldest first.
+    Kline { market: String, period: Period, candles: Vec<Candle> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Request {
+    Tickers,
+    Depth(String),
+    Trades(String),
+    Kline(String, Period),
+}
+
+// one response, shared by every subscription it was fetched for
+enum Response {
+    Tickers(HashMap<String, Ticker>),
+    Depth(Depth),
+    Trades(Vec<Trade>),
+    Kline(Vec<Candle>),
+}
+
+type Callback = Box<dyn FnMut(&MarketEvent) + Send>;
+type ErrorCallback = Box<dyn FnMut(&Topic, &GraviexError) + Send>;
+
+// what a subscriber has seen, to send only what changed
+enum Seen {
+    Nothing,
+    Ticker(Box<Ticker>),
+    Depth(OrderBookState),
+    Trades(usize), // highest trade id
+    Kline(BTreeMap<usize, Candle>), // recent bars by timestamp
+}
+
+struct Subscription {
+    topic: Topic,
+    interval: Duration,
+    due: Instant,
+    seen: Seen,
+    callback: Callback,
+}
+
+// consecutive failures of a request, and when it may be tried again
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    failures: u32,
+    until: Instant,
+}
+
+/// Polls tickers, depth, trades and k lines for the markets you
+/// subscribe to, passing on only what changed.
+///
+/// Subscriptions that come due together and need the same request share
+/// one call, so following ten tickers costs one `tickers` request. A
+/// request that fails is backed off exponentially, for every subscription
+/// needing it, until it succeeds again.
+pub struct Poller {
+    client: Arc<GraviexClient>,
+    subscriptions: Vec<Subscription>,
+    backoff: RetryPolicy, // only its delays are used
+    failing: HashMap<Request, Backoff>,
+    on_error: Option<ErrorCallback>,
+}
+
+impl Poller {
+    #[must_use]
+    pub fn new(client: Arc<GraviexClient>) -> Poller {
+        Poller {
+            client,
+            subscriptions: Vec::new(),
+            backoff: RetryPolicy::default()
+                .base_delay(Duration::from_secs(1))
+                .max_delay(Duration::from_secs(60)),
+            failing: HashMap::new(),
+            on_error: None,
+        }
+    }
+
+    /// Back off failing requests by `backoff`'s delays, default is one
+    /// second doubling up to a minute.
+    #[must_use]
+    pub fn backoff(mut self, backoff: RetryPolicy) -> Poller {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Call `callback` with every failed request and the topic it was for.
+    /// Errors are only logged otherwise.
+    #[must_use]
+    pub fn on_error<F: FnMut(&Topic, &GraviexError) + Send + 'static>(mut self, callback: F) -> Poller {
+        self.on_error = Some(Box::new(callback));
+        self
+    }
+
+    /// Poll `topic` every `interval`, calling `callback` with what changed.
+    pub fn subscribe<F: FnMut(&MarketEvent) + Send + 'static>(&mut self, topic: Topic, interval: Duration, callback: F) {
+        self.subscriptions.push(Subscription {
+            topic,
+            interval,
+            due: Instant::now(),
+            seen: Seen::Nothing,
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Poll `topic` every `interval`, sending what changed down a channel.
+    /// The subscription lives on after the receiver is dropped, but
+    /// sends nothing.
+    pub fn channel(&mut self, topic: Topic, interval: Duration) -> Receiver<MarketEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribe(topic, interval, move |event| {
+            let _ = sender.send(event.clone());
+        });
+        receiver
+    }
+
+    /// Poll every subscription that is due, returning when the next one
+    /// will be.
+    pub fn poll_once(&mut self) -> Option<Instant> {
+        let now = Instant::now();
+        let mut responses: HashMap<Request, Option<Response>> = HashMap::new();
+        for index in 0..self.subscriptions.len() {
+            if self.subscriptions[index].due > now {
+                continue;
+            }
+            let topic = self.subscriptions[index].topic.clone();
+            let request = topic.request();
+            if self.failing.get(&request).is_some_and(|backoff| backoff.until > now) {
+                continue;
+            }
+            if !responses.contains_key(&request) {
+                let response = self.fetch(&topic, &request);
+                responses.insert(request.clone(), response);
+            }
+            let subscription = &mut self.subscriptions[index];
+            subscription.due = now + subscription.interval;
+            if let Some(response) = &responses[&request] {
+                if let Some(event) = subscription.update(response) {
+                    (subscription.callback)(&event);
+                }
+            }
+        }
+        self.next_due()
+    }
+
+    // run the request, backing it off if it fails
+    fn fetch(&mut self, topic: &Topic, request: &Request) -> Option<Response> {
+        match self.send(request) {
+            Ok(response) => {
+                self.failing.remove(request);
+                Some(response)
+            }
+            Err(e) => {
+                let failures = self.failing.get(request).map_or(0, |backoff| backoff.failures);
+                let delay = self.backoff.backoff(failures);
+                tracing::warn!(error = %e, topic = ?topic, failures = failures + 1, ?delay, "poll failed, backing off");
+                self.failing.insert(request.clone(), Backoff { failures: failures + 1, until: Instant::now() + delay });
+                if let Some(on_error) = &mut self.on_error {
+                    on_error(topic, &e);
+                }
+                None
+            }
+        }
+    }
+
+    fn send(&self, request: &Request) -> Result<Response, GraviexError> {
+        let client = &self.client;
+        Ok(match request {
+            Request::Tickers => Response::Tickers(client.tickers()?),
+            Request::Depth(market) => Response::Depth(client.depth(&DepthQuery::new(market))?),
+            Request::Trades(market) => Response::Trades(client.trades(&TradesQuery::new(market))?),
+            Request::Kline(market, period) => Response::Kline(client.kline(&KlineQuery::new(market, *period))?),
+        })
+    }
+
+    // the soonest a subscription can be polled, counting backoffs
+    fn next_due(&self) -> Option<Instant> {
+        self.subscriptions
+            .iter()
+            .map(|subscription| {
+                let backoff = self.failing.get(&subscription.topic.request()).map(|backoff| backoff.until);
+                backoff.map_or(subscription.due, |until| until.max(subscription.due))
+            })
+            .min()
+    }
+
+    /// Poll on a thread of its own until the handle is stopped or dropped.
+    #[must_use]
+    pub fn start(mut self) -> PollerHandle {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || loop {
+            let wait = self
+                .poll_once()
+                .map_or(Duration::from_secs(1), |due| due.saturating_duration_since(Instant::now()));
+            match stopped.recv_timeout(wait) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+        });
+        PollerHandle { stop, thread: Some(thread) }
+    }
+}
+
+impl Subscription {
+    // fold a response into what we've seen, returning what changed
+    fn update(&mut self, response: &Response) -> Option<MarketEvent> {
+        let market = self.topic.market().to_string();
+        match (&self.topic, response) {
+            (Topic::Ticker(_), Response::Tickers(tickers)) => {
+                let ticker = tickers.get(&market)?;
+                // `at` moves on every call, the ticker only when it changed
+                if let Seen::Ticker(seen) = &self.seen {
+                    if (Ticker { at: ticker.at, ..(**seen).clone() }) == *ticker {
+                        return None;
+                    }
+                }
+                self.seen = Seen::Ticker(Box::new(ticker.clone()));
+                Some(MarketEvent::Ticker { market, ticker: ticker.clone() })
+            }
+            (Topic::Depth(_), Response::Depth(depth)) => {
+                if !matches!(self.seen, Seen::Depth(_)) {
+                    self.seen = Seen::Depth(OrderBookState::new(&market));
+                }
+                let Seen::Depth(book) = &mut self.seen else { return None };
+                let changes = book.update_from_depth(depth);
+                (!changes.is_empty()).then(|| MarketEvent::Depth { market, book: book.clone(), changes })
+            }
+            (Topic::Trades(_), Response::Trades(trades)) => {
+                let last = match self.seen {
+                    Seen::Trades(last) => last,
+                    _ => 0,
+                };
+                let mut fresh: Vec<Trade> = trades.iter().filter(|trade| trade.id > last).cloned().collect();
+                fresh.sort_by_key(|trade| trade.id);
+                self.seen = Seen::Trades(fresh.last().map_or(last, |trade| trade.id));
+                (!fresh.is_empty()).then_some(MarketEvent::Trades { market, trades: fresh })
+            }
+            (Topic::Kline(_, period), Response::Kline(candles)) => {
+                if !matches!(self.seen, Seen::Kline(_)) {
+                    self.seen = Seen::Kline(BTreeMap::new());
+                }
+                let Seen::Kline(seen) = &mut self.seen else { return None };
+                let changed: Vec<Candle> = candles
+                    .iter()
+                    .filter(|candle| seen.get(&candle.timestamp) != Some(candle))
+                    .cloned()
+                    .collect();
+                *seen = candles.iter().map(|candle| (candle.timestamp, candle.clone())).collect();
+                (!changed.is_empty()).then_some(MarketEvent::Kline { market, period: *period, candles: changed })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A `Poller` running on its own thread, stopped when dropped.
+#[derive(Debug)]
+pub struct PollerHandle {
+    stop: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PollerHandle {
+    /// Stop polling and wait for the thread, letting a poll in flight finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PollerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
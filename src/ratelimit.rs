@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::GraviexError;
+
+/// Which budget a request draws from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    Public, // unsigned, like tickers, depth and trades
+    Signed, // anything going through graviex_handler
+}
+
+/// What to do with a request that is over budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitPolicy {
+    #[default]
+    Block, // sleep until the request fits the budget
+    Error, // fail with `GraviexError::RateLimited` right away
+}
+
+/// `requests` per `period`, with bursts of up to `requests` at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Budget {
+    #[must_use]
+    pub fn new(requests: u32, period: Duration) -> Budget {
+        Budget { requests, period }
+    }
+
+    #[must_use]
+    pub fn per_second(requests: u32) -> Budget {
+        Budget::new(requests, Duration::from_secs(1))
+    }
+
+    #[must_use]
+    pub fn per_minute(requests: u32) -> Budget {
+        Budget::new(requests, Duration::from_secs(60))
+    }
+}
+
+// token bucket refilled continuously at requests / period
+#[derive(Debug)]
+struct Bucket {
+    budget: Budget,
+    state: Mutex<(f64, Instant)>, // tokens left, last refill
+}
+
+impl Bucket {
+    fn new(mut budget: Budget) -> Bucket {
+        // an empty bucket would never refill
+        budget.requests = budget.requests.max(1);
+        Bucket {
+            budget,
+            state: Mutex::new((f64::from(budget.requests), Instant::now())),
+        }
+    }
+
+    // tokens added per second
+    fn rate(&self) -> f64 {
+        f64::from(self.budget.requests) / self.budget.period.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Token bucket rate limiter for a `GraviexClient`.
+///
+/// Public and signed requests draw from separate budgets, and single
+/// endpoints like `/webapi/v3/orders.json` can get a budget of their own on
+/// top. Share one limiter between clients and threads with an `Arc` so
+/// they all pace against the same budgets.
+#[derive(Debug)]
+pub struct RateLimiter {
+    policy: LimitPolicy,
+    public: Bucket,
+    signed: Bucket,
+    endpoints: HashMap<String, Bucket>, // by api target
+}
+
+impl RateLimiter {
+    /// Limit public and signed requests to their own budgets, blocking
+    /// when over budget.
+    #[must_use]
+    pub fn new(public: Budget, signed: Budget) -> RateLimiter {
+        RateLimiter {
+            policy: LimitPolicy::default(),
+            public: Bucket::new(public),
+            signed: Bucket::new(signed),
+            endpoints: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn policy(mut self, policy: LimitPolicy) -> RateLimiter {
+        self.policy = policy;
+        self
+    }
+
+    /// Also limit requests to `api_target`, like `/webapi/v3/orders.json`,
+    /// to `budget`.
+    #[must_use]
+    pub fn endpoint(mut self, api_target: &str, budget: Budget) -> RateLimiter {
+        self.endpoints.insert(api_target.to_string(), Bucket::new(budget));
+        self
+    }
+
+    /// Take a token for a request to `api_target`, waiting or failing
+    /// according to the policy when there is none left.
+    ///
+    /// # Errors
+    /// returns `GraviexError::RateLimited` when over budget under
+    /// `LimitPolicy::Error`
+    pub fn acquire(&self, access: Access, api_target: &str) -> Result<(), GraviexError> {
+        loop {
+            match self.try_acquire(access, api_target) {
+                Ok(()) => return Ok(()),
+                Err(retry_after) if self.policy == LimitPolicy::Block => thread::sleep(retry_after),
+                Err(retry_after) => return Err(GraviexError::RateLimited { retry_after }),
+            }
+        }
+    }
+
+    /// Take a token if every budget the request falls under has one,
+    /// otherwise say how long until they all will.
+    ///
+    /// # Errors
+    /// returns the time to wait when over budget
+    pub fn try_acquire(&self, access: Access, api_target: &str) -> Result<(), Duration> {
+        let class = match access {
+            Access::Public => &self.public,
+            Access::Signed => &self.signed,
+        };
+        let buckets: Vec<&Bucket> = std::iter::once(class)
+            .chain(self.endpoints.get(api_target))
+            .collect();
+
+        // always locked class first, then endpoint, so threads can't deadlock
+        let mut states: Vec<_> = buckets
+            .iter()
+            .map(|bucket| bucket.state.lock().unwrap_or_else(|e| e.into_inner()))
+            .collect();
+        let now = Instant::now();
+        let mut wait = Duration::ZERO;
+        for (bucket, state) in buckets.iter().zip(states.iter_mut()) {
+            let (tokens, last) = &mut **state;
+            let refilled = now.duration_since(*last).as_secs_f64() * bucket.rate();
+            *tokens = (*tokens + refilled).min(f64::from(bucket.budget.requests));
+            *last = now;
+            if *tokens < 1.0 {
+                wait = wait.max(Duration::from_secs_f64((1.0 - *tokens) / bucket.rate()));
+            }
+        }
+        if wait > Duration::ZERO {
+            return Err(wait);
+        }
+        for state in &mut states {
+            state.0 -= 1.0;
+        }
+        Ok(())
+    }
+}
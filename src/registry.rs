@@ -0,0 +1,428 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::MutexGuard;
+
+use serde::{Deserialize, Serialize};
+
+use crate::placement::is_ambiguous;
+use crate::time;
+use crate::*;
+
+/// Where an order stands, as far as the registry has seen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackedState {
+    Pending, // sent, no answer from graviex yet
+    Wait, // on the book
+    Done, // fully executed
+    Cancel, // cancelled, maybe partially executed
+    Rejected, // graviex refused it, or it never arrived
+}
+
+impl TrackedState {
+    /// The state graviex reports as `wait`, `done` or `cancel`.
+    #[must_use]
+    pub fn from_exchange(state: &str) -> Option<TrackedState> {
+        match state {
+            "wait" => Some(TrackedState::Wait),
+            "done" => Some(TrackedState::Done),
+            "cancel" => Some(TrackedState::Cancel),
+            _ => None,
+        }
+    }
+
+    /// Whether the order can still change.
+    #[must_use]
+    pub fn is_open(self) -> bool {
+        matches!(self, TrackedState::Pending | TrackedState::Wait)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub state: TrackedState,
+    pub at: u64, // unix timestamp it was observed
+}
+
+/// One of our orders, known by the id we gave it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackedOrder {
+    pub client_id: String, // chosen by the caller, unique within the registry
+    pub exchange_id: Option<usize>, // graviex's id, once it answered
+    pub market: String, // like "ethbtc"
+    pub side: Side,
+    pub price: Option<Decimal>, // None for market orders
+    pub volume: Decimal,
+    pub executed_volume: Decimal,
+    pub state: TrackedState,
+    pub transitions: Vec<Transition>, // every state seen, oldest first
+}
+
+impl TrackedOrder {
+    fn new(client_id: &str, order: &NewOrder) -> TrackedOrder {
+        TrackedOrder {
+            client_id: client_id.to_string(),
+            exchange_id: None,
+            market: order.market.clone(),
+            side: order.side,
+            price: order.kind.price(),
+            volume: order.volume,
+            executed_volume: Decimal::ZERO,
+            state: TrackedState::Pending,
+            transitions: vec![Transition { state: TrackedState::Pending, at: time::now() }],
+        }
+    }
+
+    // move to `state`, recording it if it is new
+    fn transition(&mut self, state: TrackedState) -> bool {
+        if self.state == state {
+            return false;
+        }
+        self.state = state;
+        self.transitions.push(Transition { state, at: time::now() });
+        true
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    /// The client order id is already taken.
+    DuplicateId(String),
+    /// A tracked call was made on a client without a registry.
+    NoRegistry,
+    /// `orders_multi_tracked` got a different number of ids than orders.
+    IdCount { ids: usize, orders: usize },
+    /// The registry file could not be read or written.
+    Io(io::Error),
+    /// The registry file is not a registry.
+    Corrupt(serde_json::Error),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::DuplicateId(id) => write!(f, "client order id {:?} is already registered", id),
+            RegistryError::NoRegistry => write!(f, "no order registry, set one with with_registry"),
+            RegistryError::IdCount { ids, orders } => write!(f, "{} client order ids for {} orders", ids, orders),
+            RegistryError::Io(e) => write!(f, "order registry io error: {}", e),
+            RegistryError::Corrupt(e) => write!(f, "order registry file is corrupt: {}", e),
+        }
+    }
+}
+
+impl Error for RegistryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RegistryError::Io(e) => Some(e),
+            RegistryError::Corrupt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RegistryError {
+    fn from(e: io::Error) -> RegistryError {
+        RegistryError::Io(e)
+    }
+}
+
+/// Our orders by client order id, mapped to graviex ids once known.
+///
+/// Opened with a path, the registry is saved after every change and
+/// loaded again on the next `open`, so it survives restarts.
+#[derive(Debug, Default)]
+pub struct OrderRegistry {
+    path: Option<PathBuf>,
+    orders: BTreeMap<String, TrackedOrder>, // by client id
+    by_exchange: HashMap<usize, String>, // exchange id to client id
+}
+
+impl OrderRegistry {
+    /// A registry kept in memory only.
+    #[must_use]
+    pub fn new() -> OrderRegistry {
+        OrderRegistry::default()
+    }
+
+    /// Load the registry saved at `path`, or start an empty one there.
+    ///
+    /// # Errors
+    /// returns `RegistryError` if the file exists but can't be read
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<OrderRegistry, RegistryError> {
+        let path = path.as_ref().to_path_buf();
+        let orders: Vec<TrackedOrder> = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).map_err(RegistryError::Corrupt)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut registry = OrderRegistry { path: Some(path), ..OrderRegistry::default() };
+        for order in orders {
+            if let Some(id) = order.exchange_id {
+                registry.by_exchange.insert(id, order.client_id.clone());
+            }
+            registry.orders.insert(order.client_id.clone(), order);
+        }
+        Ok(registry)
+    }
+
+    /// Write the registry to its file, if it has one.
+    ///
+    /// # Errors
+    /// returns `RegistryError::Io` if the file can't be written
+    pub fn save(&self) -> Result<(), RegistryError> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let orders: Vec<&TrackedOrder> = self.orders.values().collect();
+        let json = serde_json::to_string_pretty(&orders).map_err(RegistryError::Corrupt)?;
+        // write then rename, so a crash never leaves half a file behind
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Start tracking `order` under `client_id`, before it is sent. If the
+    /// registry can't be saved `client_id` is left free to try again.
+    ///
+    /// # Errors
+    /// returns `RegistryError::DuplicateId` if `client_id` is taken, or
+    /// `RegistryError::Io` if the registry can't be saved
+    pub fn register(&mut self, client_id: &str, order: &NewOrder) -> Result<(), RegistryError> {
+        if self.orders.contains_key(client_id) {
+            return Err(RegistryError::DuplicateId(client_id.to_string()));
+        }
+        self.orders.insert(client_id.to_string(), TrackedOrder::new(client_id, order));
+        let saved = self.save();
+        if saved.is_err() {
+            self.orders.remove(client_id);
+        }
+        saved
+    }
+
+    /// `register` for several orders at once, `client_ids[i]` for
+    /// `orders[i]`: either all of them are tracked or, if an id is taken
+    /// or the registry can't be saved, none are.
+    ///
+    /// # Errors
+    /// returns `RegistryError::IdCount` if there isn't one id per order,
+    /// `RegistryError::DuplicateId` if an id is taken or given twice, or
+    /// `RegistryError::Io` if the registry can't be saved
+    pub fn register_all(&mut self, client_ids: &[&str], orders: &[NewOrder]) -> Result<(), RegistryError> {
+        if client_ids.len() != orders.len() {
+            return Err(RegistryError::IdCount { ids: client_ids.len(), orders: orders.len() });
+        }
+        let mut seen = HashSet::new();
+        if let Some(taken) = client_ids.iter().find(|id| self.orders.contains_key(**id) || !seen.insert(**id)) {
+            return Err(RegistryError::DuplicateId((*taken).to_string()));
+        }
+        for (client_id, order) in client_ids.iter().zip(orders) {
+            self.orders.insert((*client_id).to_string(), TrackedOrder::new(client_id, order));
+        }
+        let saved = self.save();
+        if saved.is_err() {
+            for client_id in client_ids {
+                self.orders.remove(*client_id);
+            }
+        }
+        saved
+    }
+
+    /// Tie `client_id` to the order graviex answered with.
+    ///
+    /// # Errors
+    /// returns `RegistryError::Io` if the registry can't be saved
+    pub fn bind(&mut self, client_id: &str, order: &Order) -> Result<(), RegistryError> {
+        if let Some(tracked) = self.orders.get_mut(client_id) {
+            tracked.exchange_id = Some(order.id);
+            self.by_exchange.insert(order.id, client_id.to_string());
+        }
+        self.apply(order);
+        self.save()
+    }
+
+    /// Mark `client_id` as never having reached the book.
+    ///
+    /// # Errors
+    /// returns `RegistryError::Io` if the registry can't be saved
+    pub fn reject(&mut self, client_id: &str) -> Result<(), RegistryError> {
+        if let Some(tracked) = self.orders.get_mut(client_id) {
+            tracked.transition(TrackedState::Rejected);
+        }
+        self.save()
+    }
+
+    /// Record what graviex says about one of our orders. Orders the
+    /// registry doesn't know are ignored. Returns whether anything changed.
+    ///
+    /// # Errors
+    /// returns `RegistryError::Io` if the registry can't be saved
+    pub fn observe(&mut self, order: &Order) -> Result<bool, RegistryError> {
+        let changed = self.apply(order);
+        if changed {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    /// `observe` for a batch, like the result of `orders_get`, saving once.
+    ///
+    /// # Errors
+    /// returns `RegistryError::Io` if the registry can't be saved
+    pub fn observe_all(&mut self, orders: &[Order]) -> Result<usize, RegistryError> {
+        let changed = orders.iter().filter(|order| self.apply(order)).count();
+        if changed > 0 {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    fn apply(&mut self, order: &Order) -> bool {
+        let Some(tracked) = self.by_exchange.get(&order.id).and_then(|id| self.orders.get_mut(id)) else {
+            return false;
+        };
+        let mut changed = tracked.executed_volume != order.executed_volume;
+        tracked.executed_volume = order.executed_volume;
+        if let Some(state) = TrackedState::from_exchange(&order.state) {
+            changed |= tracked.transition(state);
+        }
+        changed
+    }
+
+    #[must_use]
+    pub fn get(&self, client_id: &str) -> Option<&TrackedOrder> {
+        self.orders.get(client_id)
+    }
+
+    #[must_use]
+    pub fn by_exchange_id(&self, exchange_id: usize) -> Option<&TrackedOrder> {
+        self.by_exchange.get(&exchange_id).and_then(|id| self.orders.get(id))
+    }
+
+    /// Every tracked order, by client id.
+    pub fn iter(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.values()
+    }
+
+    /// Orders that are pending or on the book.
+    pub fn open_orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.iter().filter(|order| order.state.is_open())
+    }
+
+    /// Stop tracking orders that can no longer change.
+    ///
+    /// # Errors
+    /// returns `RegistryError::Io` if the registry can't be saved
+    pub fn prune(&mut self) -> Result<(), RegistryError> {
+        self.orders.retain(|_, order| order.state.is_open());
+        let orders = &self.orders;
+        self.by_exchange.retain(|_, client_id| orders.contains_key(client_id));
+        self.save()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+// after graviex refused a POST its orders are rejected; those whose fate
+// is unknown stay pending
+fn settle(registry: &mut OrderRegistry, client_ids: &[&str], error: &GraviexError) -> Result<(), RegistryError> {
+    if is_ambiguous(error) {
+        return Ok(());
+    }
+    for client_id in client_ids {
+        registry.reject(client_id)?;
+    }
+    Ok(())
+}
+
+impl GraviexClient {
+    // the registry, locked, or an error if there is none
+    fn registry(&self) -> Result<MutexGuard<'_, OrderRegistry>, RegistryError> {
+        let registry = self.registry.as_ref().ok_or(RegistryError::NoRegistry)?;
+        Ok(registry.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    // keep the registry, if any, up to date with orders graviex told us
+    // about; a registry that can't be saved shouldn't lose the caller the
+    // response, the next save catches up
+    pub(crate) fn observe(&self, orders: &[Order]) {
+        let Some(registry) = &self.registry else { return };
+        let mut registry = registry.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = registry.observe_all(orders) {
+            tracing::warn!(error = %e, "could not update the order registry");
+        }
+    }
+
+    /// `place_order_safely`, with the order tracked in the registry under
+    /// `client_id`. A `Placement::NotPlaced` order is
+    /// `TrackedState::Rejected`, a `Placement::Unknown` one stays
+    /// `TrackedState::Pending`.
+    ///
+    /// # Errors
+    /// returns `GraviexError::Registry` if there is no registry or
+    /// `client_id` is taken, in which case nothing is sent
+    pub fn orders_post_tracked(&self, client_id: &str, order: &NewOrder) -> Result<Placement, GraviexError> {
+        self.registry()?.register(client_id, order)?;
+        let placement = self.place_order_safely(order);
+        let mut registry = self.registry()?;
+        // the order is sent, a registry that can't be saved mustn't hide
+        // what happened to it
+        let saved = match &placement {
+            Placement::Placed(placed) => registry.bind(client_id, placed),
+            Placement::NotPlaced(_) => registry.reject(client_id),
+            Placement::Unknown(_) => Ok(()),
+        };
+        if let Err(e) = saved {
+            tracing::warn!(error = %e, client_id, "could not update the order registry");
+        }
+        Ok(placement)
+    }
+
+    /// `orders_multi`, with each order tracked under the client id at the
+    /// same position in `client_ids`. If graviex answers with a different
+    /// number of orders than were sent, which is which can't be told, so
+    /// they all stay `TrackedState::Pending`.
+    ///
+    /// # Errors
+    /// returns `GraviexError::Registry` if there is no registry, a client
+    /// id is taken or given twice, there isn't one per order or the
+    /// registry can't be saved, none of them tracked then, otherwise the
+    /// same as `orders_multi`
+    pub fn orders_multi_tracked(&self, client_ids: &[&str], orders: &MultiOrder) -> Result<Vec<Order>, GraviexError> {
+        self.registry()?.register_all(client_ids, &orders.new_orders())?;
+        let result = self.orders_multi(orders);
+        let mut registry = self.registry()?;
+        let saved = match &result {
+            Ok(placed) if placed.len() != client_ids.len() => {
+                tracing::warn!(
+                    sent = client_ids.len(),
+                    placed = placed.len(),
+                    "graviex answered with a different number of orders, leaving them all pending"
+                );
+                Ok(())
+            }
+            // bind every one of them, even past a failed save
+            Ok(placed) => client_ids
+                .iter()
+                .zip(placed)
+                .map(|(client_id, order)| registry.bind(client_id, order))
+                .fold(Ok(()), Result::and),
+            Err(e) => settle(&mut registry, client_ids, e),
+        };
+        if let Err(e) = saved {
+            tracing::warn!(error = %e, "could not update the order registry");
+        }
+        result
+    }
+}
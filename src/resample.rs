@@ -0,0 +1,307 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::time::parse_timestamp;
+use crate::*;
+
+// Monday 1970-01-05, so weekly bars start on Mondays
+const FIRST_MONDAY: i64 = 4 * 86_400;
+
+/// A bar size, including ones graviex doesn't serve, like three minutes
+/// or a week starting Monday in UTC+8.
+///
+/// Bars are counted from Monday 1970-01-05 at midnight in the bar's time
+/// zone, so days start at local midnight and weeks on Mondays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeframe {
+    pub seconds: usize, // length of a bar
+    pub utc_offset: i32, // seconds east of utc bars line up in, like 28800 for UTC+8
+}
+
+impl Timeframe {
+    /// Bars of `seconds`, at least one.
+    #[must_use]
+    pub fn seconds(seconds: usize) -> Timeframe {
+        Timeframe { seconds: seconds.max(1), utc_offset: 0 }
+    }
+
+    #[must_use]
+    pub fn minutes(minutes: usize) -> Timeframe {
+        Timeframe::seconds(minutes * 60)
+    }
+
+    #[must_use]
+    pub fn hours(hours: usize) -> Timeframe {
+        Timeframe::seconds(hours * 3600)
+    }
+
+    #[must_use]
+    pub fn days(days: usize) -> Timeframe {
+        Timeframe::seconds(days * 86_400)
+    }
+
+    #[must_use]
+    pub fn weeks(weeks: usize) -> Timeframe {
+        Timeframe::seconds(weeks * 7 * 86_400)
+    }
+
+    /// Line bars up with local time `utc_offset` seconds east of utc.
+    #[must_use]
+    pub fn utc_offset(mut self, utc_offset: i32) -> Timeframe {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    /// Start of the bar `timestamp` falls in.
+    #[must_use]
+    pub fn start_of(&self, timestamp: usize) -> usize {
+        let seconds = i64::try_from(self.seconds).unwrap_or(i64::MAX);
+        let local = i64::try_from(timestamp).unwrap_or(i64::MAX) + i64::from(self.utc_offset);
+        let start = local - (local - FIRST_MONDAY).rem_euclid(seconds) - i64::from(self.utc_offset);
+        usize::try_from(start).unwrap_or(0)
+    }
+}
+
+impl From<Period> for Timeframe {
+    fn from(period: Period) -> Timeframe {
+        Timeframe::seconds(period.seconds())
+    }
+}
+
+/// An OHLCV bar with volume counted in both currencies, like `volume`
+/// and `volume2` on a `Ticker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bar {
+    pub timestamp: usize, // unix timestamp of the start of the bar
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal, // in base units
+    pub quote_volume: Decimal, // in quote units
+}
+
+impl Bar {
+    fn new(timestamp: usize, price: Decimal) -> Bar {
+        Bar {
+            timestamp,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Decimal::ZERO,
+            quote_volume: Decimal::ZERO,
+        }
+    }
+}
+
+impl From<Bar> for Candle {
+    fn from(bar: Bar) -> Candle {
+        Candle {
+            timestamp: bar.timestamp,
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+        }
+    }
+}
+
+/// A trade as far as bars are concerned, so `Trade` and `SimpleTrade`
+/// both make them.
+pub trait Tick {
+    /// Graviex's id for it, which only goes up.
+    fn id(&self) -> usize;
+    /// When it happened, as a unix timestamp.
+    fn at(&self) -> Option<usize>;
+    fn price(&self) -> Decimal;
+    /// In base units.
+    fn volume(&self) -> Decimal;
+    /// In quote units.
+    fn funds(&self) -> Decimal {
+        self.price() * self.volume()
+    }
+}
+
+impl Tick for Trade {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn at(&self) -> Option<usize> {
+        usize::try_from(parse_timestamp(&self.created_at)?).ok()
+    }
+
+    fn price(&self) -> Decimal {
+        self.price
+    }
+
+    fn volume(&self) -> Decimal {
+        self.volume
+    }
+
+    fn funds(&self) -> Decimal {
+        self.funds.unwrap_or(self.price * self.volume)
+    }
+}
+
+impl Tick for SimpleTrade {
+    fn id(&self) -> usize {
+        self.tid
+    }
+
+    fn at(&self) -> Option<usize> {
+        Some(self.date)
+    }
+
+    fn price(&self) -> Decimal {
+        self.price
+    }
+
+    fn volume(&self) -> Decimal {
+        self.amount
+    }
+}
+
+/// A bar that doesn't fit in the timeframe it was resampled into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResampleError {
+    pub timestamp: usize, // start of the bar
+    pub period: Period, // its length
+    pub timeframe: Timeframe,
+}
+
+impl fmt::Display for ResampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the {} minute bar at {} crosses into the next {} second bar",
+            self.period, self.timestamp, self.timeframe.seconds
+        )
+    }
+}
+
+impl Error for ResampleError {}
+
+/// Builds bars of a `Timeframe` as trades or shorter bars come in.
+///
+/// Bars nothing traded in are filled in flat at the previous close with no
+/// volume, the way graviex does. Anything older than the bar being built
+/// is too late and skipped.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    timeframe: Timeframe,
+    current: Option<Bar>,
+}
+
+impl Resampler {
+    #[must_use]
+    pub fn new(timeframe: Timeframe) -> Resampler {
+        Resampler { timeframe, current: None }
+    }
+
+    /// The bar being built, which may still change.
+    #[must_use]
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    /// Fold in a trade, returning the bars it finished.
+    pub fn push_trade<T: Tick>(&mut self, trade: &T) -> Vec<Bar> {
+        let Some(at) = trade.at() else {
+            tracing::debug!("skipping a trade without a time");
+            return Vec::new();
+        };
+        let (price, volume, funds) = (trade.price(), trade.volume(), trade.funds());
+        self.fold(at, price, |bar| {
+            bar.high = bar.high.max(price);
+            bar.low = bar.low.min(price);
+            bar.close = price;
+            bar.volume += volume;
+            bar.quote_volume += funds;
+        })
+    }
+
+    /// Fold in a `period` bar, returning the bars it finished. K lines
+    /// carry no quote volume, so the bar's volume is priced at its
+    /// typical price, the mean of its high, low and close.
+    ///
+    /// # Errors
+    /// returns `ResampleError` if the bar is longer than the timeframe or
+    /// crosses one of its bars
+    pub fn push_candle(&mut self, candle: &Candle, period: Period) -> Result<Vec<Bar>, ResampleError> {
+        let start = self.timeframe.start_of(candle.timestamp);
+        if self.timeframe.start_of(candle.timestamp + period.seconds() - 1) != start {
+            return Err(ResampleError { timestamp: candle.timestamp, period, timeframe: self.timeframe });
+        }
+        let typical = (candle.high + candle.low + candle.close) / Decimal::from(3);
+        Ok(self.fold(candle.timestamp, candle.open, |bar| {
+            bar.high = bar.high.max(candle.high);
+            bar.low = bar.low.min(candle.low);
+            bar.close = candle.close;
+            bar.volume += candle.volume;
+            bar.quote_volume += candle.volume * typical;
+        }))
+    }
+
+    // open bars up to the one `at` falls in, starting new ones at `open`,
+    // then update that one
+    fn fold(&mut self, at: usize, open: Decimal, update: impl FnOnce(&mut Bar)) -> Vec<Bar> {
+        let start = self.timeframe.start_of(at);
+        let mut finished = Vec::new();
+        match &self.current {
+            Some(bar) if start < bar.timestamp => {
+                tracing::debug!(at, bar = bar.timestamp, "skipping data older than the bar being built");
+                return finished;
+            }
+            Some(bar) if start > bar.timestamp => {
+                let close = bar.close;
+                let mut timestamp = bar.timestamp + self.timeframe.seconds;
+                finished.extend(self.current.take());
+                while timestamp < start {
+                    finished.push(Bar::new(timestamp, close));
+                    timestamp += self.timeframe.seconds;
+                }
+            }
+            _ => {}
+        }
+        update(self.current.get_or_insert_with(|| Bar::new(start, open)));
+        finished
+    }
+
+    /// Hand over the bar being built, as if its time was up.
+    pub fn finish(&mut self) -> Option<Bar> {
+        self.current.take()
+    }
+}
+
+/// Bars of `timeframe` made from `trades`, in any order, the last one
+/// possibly still open.
+pub fn bars_from_trades<T: Tick>(trades: &[T], timeframe: Timeframe) -> Vec<Bar> {
+    let mut trades: Vec<&T> = trades.iter().collect();
+    // graviex lists trades newest first
+    trades.sort_by_key(|trade| (trade.at(), trade.id()));
+    let mut resampler = Resampler::new(timeframe);
+    let mut bars: Vec<Bar> = trades.into_iter().flat_map(|trade| resampler.push_trade(trade)).collect();
+    bars.extend(resampler.finish());
+    bars
+}
+
+/// `period` bars, like those from `kline`, resampled into `timeframe`,
+/// the last one possibly incomplete.
+///
+/// # Errors
+/// returns `ResampleError` if a bar doesn't fit in the timeframe, like
+/// hour bars into 90 minute ones
+pub fn resample(candles: &[Candle], period: Period, timeframe: Timeframe) -> Result<Vec<Bar>, ResampleError> {
+    let mut candles: Vec<&Candle> = candles.iter().collect();
+    candles.sort_by_key(|candle| candle.timestamp);
+    let mut resampler = Resampler::new(timeframe);
+    let mut bars = Vec::new();
+    for candle in candles {
+        bars.extend(resampler.push_candle(candle, period)?);
+    }
+    bars.extend(resampler.finish());
+    Ok(bars)
+}
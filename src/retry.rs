@@ -0,0 +1,104 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::{logging, GraviexError};
+
+/// How a `GraviexClient` retries GET requests that failed in transit or
+/// with a 5xx. POSTs are never retried, since graviex may have acted on
+/// them before the failure reached us, and neither is
+/// `gen_deposit_address`, a GET that makes graviex create an address.
+///
+/// The delay before retry `n` is a random duration up to
+/// `base_delay * 2^n`, capped at `max_delay` ("full jitter").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32, // retries after the first attempt
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retry up to `max_retries` times with the default delays.
+    #[must_use]
+    pub fn new(max_retries: u32) -> RetryPolicy {
+        RetryPolicy { max_retries, ..RetryPolicy::default() }
+    }
+
+    /// Never retry.
+    #[must_use]
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(0)
+    }
+
+    #[must_use]
+    pub fn base_delay(mut self, base_delay: Duration) -> RetryPolicy {
+        self.base_delay = base_delay;
+        self
+    }
+
+    #[must_use]
+    pub fn max_delay(mut self, max_delay: Duration) -> RetryPolicy {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Whether `error` is worth another try: the request never completed,
+    /// or graviex answered with a 5xx.
+    #[must_use]
+    pub fn is_retryable(&self, error: &GraviexError) -> bool {
+        match error {
+            GraviexError::Transport(_) => true,
+            GraviexError::Status { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Longest delay before retry number `retry`, counting from 0.
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Random delay before retry number `retry`, between zero and `backoff`.
+    #[must_use]
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        let millis = u64::try_from(backoff.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(fastrand::u64(0..=millis))
+    }
+
+    /// Call `attempt` until it succeeds, fails with an error that isn't
+    /// retryable, or runs out of retries. Each call should build a fresh
+    /// request, so signed requests get a new tonce.
+    ///
+    /// # Errors
+    /// returns the last error `attempt` failed with
+    pub fn run<T, F>(&self, mut attempt: F) -> Result<T, GraviexError>
+    where
+        F: FnMut() -> Result<T, GraviexError>,
+    {
+        let mut retry = 0;
+        loop {
+            match attempt() {
+                Err(e) if retry < self.max_retries && self.is_retryable(&e) => {
+                    let delay = self.delay(retry);
+                    tracing::info!(error = %logging::summary(&e), retry = retry + 1, delay_ms = delay.as_millis() as u64, "retrying request");
+                    thread::sleep(delay);
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
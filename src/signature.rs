@@ -0,0 +1,66 @@
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+/// Sort params the way graviex expects before signing: by key, keeping
+/// the relative order of repeated keys like `orders[][side]`.
+pub fn sort_params<K: AsRef<str>>(params: &mut [(K, String)]) {
+    params.sort_by(|a, b| top_level(a.0.as_ref()).cmp(top_level(b.0.as_ref())));
+}
+
+/// `key=value` pairs joined with `&`, without any escaping.
+#[must_use]
+pub fn query_string<K: AsRef<str>>(params: &[(K, String)]) -> String {
+    let mut query_string: String = String::new();
+    for (key, value) in params {
+        if !query_string.is_empty() {
+            query_string.push('&');
+        }
+        query_string.push_str(key.as_ref());
+        query_string.push('=');
+        query_string.push_str(value);
+    }
+    query_string
+}
+
+/// The message graviex signs, `METHOD|path|sorted_query`.
+#[must_use]
+pub fn payload(req_method: &str, api_target: &str, query: &str) -> String {
+    // appease the API overlords with their message syntax
+    let mut message: String = String::new();
+    message.push_str(req_method);
+    message.push('|');
+    message.push_str(api_target);
+    message.push('|');
+    message.push_str(query);
+    message
+}
+
+/// Hex encoded HMAC-SHA256 of `payload` keyed with `secret`.
+#[must_use]
+pub fn hmac_hex(secret: &str, payload: &str) -> String {
+    type HmacSha256 = Hmac<Sha256>;
+
+    // give that bad boy some hmac signature action
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Sort `params` in place and return their signature for a request to
+/// `api_target`. `params` must already hold the tonce and access key.
+pub fn sign<K: AsRef<str>>(
+    secret: &str,
+    req_method: &str,
+    api_target: &str,
+    params: &mut [(K, String)])
+-> String {
+    sort_params(params);
+    let query = query_string(params);
+    hmac_hex(secret, &payload(req_method, api_target, &query))
+}
+
+// "orders[][side]" sorts as "orders"
+fn top_level(key: &str) -> &str {
+    key.split('[').next().unwrap_or(key)
+}
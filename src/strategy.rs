@@ -0,0 +1,85 @@
+//! The interface a trading bot implements, shared by the live `Engine`
+//! and the `Backtest`, so the same bot runs in both.
+//!
+//! `Strategy` here is the trait, not graviex's published strategies at
+//! the crate root.
+
+use crate::*;
+
+/// What a strategy can see and do from inside its hooks.
+///
+/// Live, orders go straight to graviex and the state is whatever the
+/// engine last polled. In a `Backtest`, orders fill against the history
+/// replayed and the state is as it stood at the replayed time.
+pub trait Context {
+    /// Current unix time in seconds, as far as the strategy is concerned.
+    fn now(&self) -> u64;
+
+    /// Place an order, returning it as graviex accepted it.
+    ///
+    /// # Errors
+    /// returns `GraviexError` if the order was refused or never arrived
+    fn place(&mut self, order: &NewOrder) -> Result<Order, GraviexError>;
+
+    /// Cancel one of your orders, returning it as graviex last saw it. It
+    /// leaves `open_orders` right away, and `on_order_update` reports it
+    /// canceled once the cancel goes through.
+    ///
+    /// # Errors
+    /// returns `GraviexError` if the order couldn't be canceled
+    fn cancel(&mut self, id: usize) -> Result<Order, GraviexError>;
+
+    /// Your orders known to be open, oldest first.
+    fn open_orders(&self) -> Vec<&Order>;
+
+    /// Your account in `currency`, like "btc", once the account was read.
+    fn account(&self, currency: &str) -> Option<&Account>;
+
+    /// The latest ticker of `market`, if it was followed.
+    fn ticker(&self, market: &str) -> Option<&Ticker>;
+
+    /// The latest order book of `market`, if its depth was followed.
+    fn book(&self, market: &str) -> Option<&OrderBookState>;
+
+    /// Stop once the current hook returns.
+    fn stop(&mut self);
+}
+
+/// A trading bot, as a set of hooks called as things happen.
+///
+/// Every hook does nothing unless implemented, so a bot only writes the
+/// ones it needs.
+#[allow(unused_variables)]
+pub trait Strategy {
+    /// Before anything else.
+    fn on_start(&mut self, ctx: &mut dyn Context) {}
+
+    /// The ticker of `market` changed.
+    fn on_ticker(&mut self, ctx: &mut dyn Context, market: &str, ticker: &Ticker) {}
+
+    /// The book of a market changed, `changes` lists the levels that did.
+    fn on_depth(&mut self, ctx: &mut dyn Context, book: &OrderBookState, changes: &[LevelChange]) {}
+
+    /// A public trade went through on `market`.
+    fn on_trade(&mut self, ctx: &mut dyn Context, market: &str, trade: &Trade) {}
+
+    /// A `period` bar of `market` is new or still filling.
+    fn on_candle(&mut self, ctx: &mut dyn Context, market: &str, period: Period, candle: &Candle) {}
+
+    /// One of your orders filled some more, was canceled, or showed up.
+    fn on_order_update(&mut self, ctx: &mut dyn Context, order: &Order) {}
+
+    /// Your accounts were read again.
+    fn on_account(&mut self, ctx: &mut dyn Context, member: &Member) {}
+
+    /// The timer went off.
+    fn on_timer(&mut self, ctx: &mut dyn Context) {}
+
+    /// A request made for the strategy failed. Only logged by default.
+    fn on_error(&mut self, ctx: &mut dyn Context, error: &GraviexError) {
+        tracing::warn!(error = %error, "strategy request failed");
+    }
+
+    /// After everything else, once stopped.
+    fn on_stop(&mut self, ctx: &mut dyn Context) {}
+}
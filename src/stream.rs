@@ -0,0 +1,492 @@
+//! Live market and account events over graviex's websocket feed, enabled
+//! with the `stream` feature.
+//!
+//! Graviex doesn't document its push feed, so this speaks the protocol of
+//! the Peatio websocket api graviex is built on. Every frame is json text:
+//!
+//! - the server opens with `{"challenge":"..."}`
+//! - a client with credentials answers `{"auth":{"access_key":"...","answer":"..."}}`,
+//!   the answer being the hex HMAC-SHA256 of the access key followed by the
+//!   challenge, keyed with the secret, and hears `{"success":{...}}` or an
+//!   error like `{"error":{"code":2005,"message":"..."}}` back
+//! - `{"event":"subscribe","streams":["ethbtc.trades","ethbtc.update","global.tickers"]}`
+//!   starts the public streams, `order` and `trade` stream your own orders
+//!   and trades once authenticated
+//! - updates arrive as `{"<stream>": payload}`, like
+//!   `{"ethbtc.update":{"asks":[["0.0614","0.5"]],"bids":[]}}`
+//!
+//! `mock::MockStreamServer` speaks the same protocol on localhost.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{HandshakeError, Message, WebSocket};
+
+use crate::signature::hmac_hex;
+use crate::*;
+
+// how often a running stream looks up from the socket to check for stop
+const STOP_CHECK: Duration = Duration::from_millis(100);
+
+/// A feed to subscribe to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Stream {
+    Tickers, // every market's ticker
+    Trades(String), // public trades on a market, like Trades("ethbtc")
+    OrderBook(String), // a market's order book
+    Orders, // your own orders, needs credentials
+    MyTrades, // your own trades, needs credentials
+}
+
+impl Stream {
+    /// The name graviex knows the stream by, like `ethbtc.trades`.
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            Stream::Tickers => "global.tickers".to_string(),
+            Stream::Trades(market) => format!("{}.trades", market),
+            Stream::OrderBook(market) => format!("{}.update", market),
+            Stream::Orders => "order".to_string(),
+            Stream::MyTrades => "trade".to_string(),
+        }
+    }
+
+    /// Whether the stream is about your own account.
+    #[must_use]
+    pub fn is_private(&self) -> bool {
+        matches!(self, Stream::Orders | Stream::MyTrades)
+    }
+}
+
+/// Something graviex pushed.
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// Authenticated and subscribed, after connecting or reconnecting.
+    /// Anything pushed while disconnected was missed, so resync books and
+    /// orders over the api.
+    Connected,
+    /// Every market's ticker.
+    Tickers(HashMap<String, Ticker>),
+    /// New public trades on `market`.
+    Trades { market: String, trades: Vec<SimpleTrade> },
+    /// The order book of `market`.
+    OrderBook { market: String, asks: Vec<TinyOrder>, bids: Vec<TinyOrder> },
+    /// One of your orders changed.
+    Order(Order),
+    /// One of your orders traded.
+    Trade(Trade),
+}
+
+#[derive(Deserialize)]
+struct TradesPayload {
+    trades: Vec<SimpleTrade>,
+}
+
+#[derive(Deserialize)]
+struct BookPayload {
+    asks: Vec<TinyOrder>,
+    bids: Vec<TinyOrder>,
+}
+
+/// Everything that can go wrong on a stream.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Connecting, the handshake, or the connection itself failed.
+    Socket(Box<tungstenite::Error>),
+    /// Graviex refused our credentials or subscription.
+    Rejected(GraviexError),
+    /// Graviex sent something we couldn't make sense of.
+    Protocol(String),
+}
+
+impl StreamError {
+    // the socket timed out without anything to read
+    fn is_timeout(&self) -> bool {
+        match self {
+            StreamError::Socket(e) => matches!(
+                &**e,
+                tungstenite::Error::Io(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Socket(e) => write!(f, "websocket error: {}", e),
+            StreamError::Rejected(e) => write!(f, "stream rejected: {}", e),
+            StreamError::Protocol(message) => write!(f, "unexpected frame: {}", message),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Socket(e) => Some(&**e),
+            StreamError::Rejected(e) => Some(e),
+            StreamError::Protocol(_) => None,
+        }
+    }
+}
+
+impl From<tungstenite::Error> for StreamError {
+    fn from(e: tungstenite::Error) -> StreamError {
+        StreamError::Socket(Box::new(e))
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> StreamError {
+        tungstenite::Error::Io(e).into()
+    }
+}
+
+type ErrorCallback = Box<dyn FnMut(&StreamError) + Send>;
+
+/// Connects to the websocket feed and subscribes to streams.
+///
+/// `connect` gives a single connection to read from. `start` runs the
+/// stream on a thread of its own, reconnecting and resubscribing with
+/// backoff whenever the connection drops.
+pub struct StreamClient {
+    url: String, // like "wss://graviex.net:8080"
+    credentials: Option<(String, String)>, // key and secret, for private streams
+    streams: Vec<Stream>,
+    timeout: Duration, // for connecting and each step of the handshake
+    reconnect: RetryPolicy, // only its delays are used
+    on_error: Option<ErrorCallback>,
+}
+
+impl fmt::Debug for StreamClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamClient")
+            .field("url", &self.url)
+            .field("credentials", &self.credentials.as_ref().map(|_| logging::REDACTED))
+            .field("streams", &self.streams)
+            .field("timeout", &self.timeout)
+            .field("reconnect", &self.reconnect)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StreamClient {
+    /// A stream from `url` without credentials, for public streams only.
+    #[must_use]
+    pub fn new(url: &str) -> StreamClient {
+        StreamClient {
+            url: url.to_string(),
+            credentials: None,
+            streams: Vec::new(),
+            timeout: Duration::from_secs(10),
+            reconnect: RetryPolicy::default()
+                .base_delay(Duration::from_secs(1))
+                .max_delay(Duration::from_secs(60)),
+            on_error: None,
+        }
+    }
+
+    /// Authenticate as the account owning `key` and `secret`.
+    #[must_use]
+    pub fn with_credentials(mut self, key: &str, secret: &str) -> StreamClient {
+        self.credentials = Some((key.to_string(), secret.to_string()));
+        self
+    }
+
+    #[must_use]
+    pub fn subscribe(mut self, stream: Stream) -> StreamClient {
+        self.streams.push(stream);
+        self
+    }
+
+    /// Give up on connecting, and on each step of the handshake, after
+    /// `timeout`, default is 10 seconds.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> StreamClient {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Wait between reconnects by `reconnect`'s delays, default is one
+    /// second doubling up to a minute. `start` never stops reconnecting.
+    #[must_use]
+    pub fn with_reconnect(mut self, reconnect: RetryPolicy) -> StreamClient {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Call `callback` with every error that drops or prevents a connection
+    /// under `start`. Errors are only logged otherwise.
+    #[must_use]
+    pub fn on_error<F: FnMut(&StreamError) + Send + 'static>(mut self, callback: F) -> StreamClient {
+        self.on_error = Some(Box::new(callback));
+        self
+    }
+
+    /// Connect, authenticate if there are credentials, and subscribe.
+    ///
+    /// # Errors
+    /// returns `StreamError` if any of that fails
+    pub fn connect(&self) -> Result<Connection, StreamError> {
+        let mut connection = self.open()?;
+        connection.handshake(self.credentials.as_ref(), &self.streams)?;
+        connection.tcp.set_read_timeout(None)?;
+        Ok(connection)
+    }
+
+    fn open(&self) -> Result<Connection, StreamError> {
+        let request = self.url.as_str().into_client_request()?;
+        let uri = request.uri();
+        let host = uri.host().unwrap_or_default().to_string();
+        let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("wss") { 443 } else { 80 });
+        let addr = (host.as_str(), port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", host)))?;
+
+        let tcp = TcpStream::connect_timeout(&addr, self.timeout)?;
+        tcp.set_read_timeout(Some(self.timeout))?;
+        tcp.set_write_timeout(Some(self.timeout))?;
+        let (socket, _) = tungstenite::client_tls(request, tcp.try_clone()?).map_err(|e| match e {
+            HandshakeError::Failure(e) => StreamError::from(e),
+            HandshakeError::Interrupted(_) => StreamError::from(io::Error::from(io::ErrorKind::TimedOut)),
+        })?;
+        Ok(Connection { socket, tcp })
+    }
+
+    /// Stream on a thread of its own until the handle is stopped or
+    /// dropped, reconnecting whenever the connection drops.
+    #[must_use]
+    pub fn start(mut self) -> StreamHandle {
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || self.run(&sender, &stop))
+        };
+        StreamHandle { events, stop, thread: Some(thread) }
+    }
+
+    fn run(&mut self, sender: &Sender<StreamEvent>, stop: &AtomicBool) {
+        let mut failures = 0;
+        while !stop.load(Ordering::SeqCst) {
+            let error = match self.connect() {
+                Ok(mut connection) => {
+                    failures = 0;
+                    if sender.send(StreamEvent::Connected).is_err() {
+                        return;
+                    }
+                    match connection.forward(sender, stop) {
+                        Ok(()) => return,
+                        Err(e) => e,
+                    }
+                }
+                Err(e) => e,
+            };
+            let delay = self.reconnect.backoff(failures);
+            failures += 1;
+            tracing::warn!(error = %error, failures, ?delay, "stream disconnected, reconnecting");
+            if let Some(on_error) = &mut self.on_error {
+                on_error(&error);
+            }
+            let until = Instant::now() + delay;
+            while Instant::now() < until && !stop.load(Ordering::SeqCst) {
+                thread::sleep(STOP_CHECK.min(until.saturating_duration_since(Instant::now())));
+            }
+        }
+    }
+}
+
+impl GraviexClient {
+    /// A stream from `url` authenticating with this client's key and
+    /// secret, like `client.stream("wss://graviex.net:8080").subscribe(Stream::Orders)`.
+    #[must_use]
+    pub fn stream(&self, url: &str) -> StreamClient {
+        StreamClient::new(url).with_credentials(&self.key, &self.secret)
+    }
+}
+
+/// One open, subscribed connection.
+#[derive(Debug)]
+pub struct Connection {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    tcp: TcpStream, // the socket underneath, to change its timeouts
+}
+
+impl Connection {
+    fn handshake(&mut self, credentials: Option<&(String, String)>, streams: &[Stream]) -> Result<(), StreamError> {
+        let challenge = self.next_frame()?;
+        let challenge = challenge
+            .get("challenge")
+            .and_then(Value::as_str)
+            .ok_or_else(|| StreamError::Protocol(challenge.to_string()))?;
+
+        if let Some((key, secret)) = credentials {
+            let answer = hmac_hex(secret, &format!("{}{}", key, challenge));
+            self.send(&json!({"auth": {"access_key": key, "answer": answer}}))?;
+            self.expect_success()?;
+        }
+        if !streams.is_empty() {
+            let names: Vec<String> = streams.iter().map(Stream::name).collect();
+            self.send(&json!({"event": "subscribe", "streams": names}))?;
+            self.expect_success()?;
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, frame: &Value) -> Result<(), StreamError> {
+        self.socket.send(Message::Text(frame.to_string()))?;
+        Ok(())
+    }
+
+    // the next json frame, answering pings along the way
+    fn next_frame(&mut self) -> Result<Value, StreamError> {
+        loop {
+            match self.socket.read()? {
+                Message::Text(text) => {
+                    return serde_json::from_str(&text).map_err(|_| StreamError::Protocol(text));
+                }
+                Message::Close(_) => return Err(tungstenite::Error::ConnectionClosed.into()),
+                // tungstenite answers pings itself
+                _ => {}
+            }
+        }
+    }
+
+    fn expect_success(&mut self) -> Result<(), StreamError> {
+        let frame = self.next_frame()?;
+        if frame.get("success").is_some() {
+            return Ok(());
+        }
+        Err(match GraviexError::from_response(200, &frame.to_string()) {
+            Some(e) => StreamError::Rejected(e),
+            None => StreamError::Protocol(frame.to_string()),
+        })
+    }
+
+    /// Wait for the next event.
+    ///
+    /// # Errors
+    /// returns `StreamError` if the connection drops or graviex sends
+    /// something unexpected
+    pub fn next_event(&mut self) -> Result<StreamEvent, StreamError> {
+        loop {
+            if let Some(event) = parse(self.next_frame()?)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Close the connection politely.
+    pub fn close(mut self) {
+        let _ = self.socket.close(None);
+        let _ = self.socket.flush();
+    }
+
+    // pass events on until told to stop, or the receiver hangs up
+    fn forward(&mut self, sender: &Sender<StreamEvent>, stop: &AtomicBool) -> Result<(), StreamError> {
+        self.tcp.set_read_timeout(Some(STOP_CHECK))?;
+        loop {
+            if stop.load(Ordering::SeqCst) {
+                let _ = self.socket.close(None);
+                return Ok(());
+            }
+            match self.next_event() {
+                Ok(event) => {
+                    if sender.send(event).is_err() {
+                        return Ok(());
+                    }
+                }
+                Err(e) if e.is_timeout() => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+// turn a pushed frame into an event, skipping the ones we don't follow
+fn parse(frame: Value) -> Result<Option<StreamEvent>, StreamError> {
+    let text = frame.to_string();
+    if let Some(e) = GraviexError::from_response(200, &text) {
+        return Err(StreamError::Rejected(e));
+    }
+    let Value::Object(frame) = frame else {
+        return Err(StreamError::Protocol(text));
+    };
+    let Some((stream, payload)) = frame.into_iter().next() else {
+        return Ok(None);
+    };
+    let payload = payload.to_string();
+    let event = match stream.as_str() {
+        "success" => return Ok(None),
+        "global.tickers" => StreamEvent::Tickers(payload_of(&payload)?),
+        "order" => StreamEvent::Order(payload_of(&payload)?),
+        "trade" => StreamEvent::Trade(payload_of(&payload)?),
+        _ => {
+            if let Some(market) = stream.strip_suffix(".trades") {
+                let trades: TradesPayload = payload_of(&payload)?;
+                StreamEvent::Trades { market: market.to_string(), trades: trades.trades }
+            } else if let Some(market) = stream.strip_suffix(".update") {
+                let book: BookPayload = payload_of(&payload)?;
+                StreamEvent::OrderBook { market: market.to_string(), asks: book.asks, bids: book.bids }
+            } else {
+                tracing::debug!(stream, "ignoring frame from unknown stream");
+                return Ok(None);
+            }
+        }
+    };
+    Ok(Some(event))
+}
+
+// a payload we couldn't decode is a frame we didn't expect
+fn payload_of<T: serde::de::DeserializeOwned>(payload: &str) -> Result<T, StreamError> {
+    serde_json::from_str(payload).map_err(|e| StreamError::Protocol(format!("{}: {}", e, payload)))
+}
+
+/// A `StreamClient` running on its own thread, stopped when dropped.
+#[derive(Debug)]
+pub struct StreamHandle {
+    events: Receiver<StreamEvent>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StreamHandle {
+    /// Events as they arrive, with `StreamEvent::Connected` after every
+    /// (re)connect.
+    #[must_use]
+    pub fn events(&self) -> &Receiver<StreamEvent> {
+        &self.events
+    }
+
+    /// Close the connection and wait for the thread.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
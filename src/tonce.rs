@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Graviex error code for a tonce that was already used by the access key.
+pub const TONCE_USED: i64 = 2006;
+/// Graviex error code for a tonce too far away from the server's clock.
+pub const TONCE_INVALID: i64 = 2007;
+
+// one generator per access key, shared by every client using that key
+static GENERATORS: OnceLock<Mutex<HashMap<String, Arc<TonceGenerator>>>> = OnceLock::new();
+
+/// Hands out millisecond tonces that are strictly increasing.
+///
+/// The generator is safe to share across threads. Consecutive calls to
+/// `next` never return the same value, even when many requests go out in
+/// the same millisecond or the wall clock steps backwards.
+#[derive(Debug, Default)]
+pub struct TonceGenerator {
+    last: AtomicU64, // last tonce handed out
+    offset: AtomicI64, // server clock minus local clock, in milliseconds
+}
+
+impl TonceGenerator {
+    #[must_use]
+    pub fn new() -> TonceGenerator {
+        TonceGenerator::default()
+    }
+
+    /// The generator shared by every client that signs with `key`.
+    ///
+    /// # Panics
+    /// panics if another thread panicked while holding the registry lock
+    #[must_use]
+    pub fn for_key(key: &str) -> Arc<TonceGenerator> {
+        let generators = GENERATORS.get_or_init(|| Mutex::new(HashMap::new()));
+        let mut generators = generators.lock().unwrap();
+        generators
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(TonceGenerator::new()))
+            .clone()
+    }
+
+    /// Next tonce, in milliseconds since the unix epoch on the server's clock.
+    pub fn next(&self) -> u64 {
+        let now = local_millis()
+            .saturating_add_signed(self.offset.load(Ordering::SeqCst));
+        let mut last = self.last.load(Ordering::SeqCst);
+        loop {
+            let next = now.max(last + 1);
+            match self.last.compare_exchange(last, next, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => return next,
+                Err(actual) => last = actual,
+            }
+        }
+    }
+
+    /// Line our clock up with `server_time`, a unix timestamp in seconds
+    /// like the one returned by `GraviexClient::timestamp`.
+    pub fn resync(&self, server_time: u64) {
+        let server_millis = i64::try_from(server_time.saturating_mul(1000)).unwrap_or(i64::MAX);
+        let local = i64::try_from(local_millis()).unwrap_or(i64::MAX);
+        self.offset.store(server_millis - local, Ordering::SeqCst);
+    }
+
+    /// Current difference between the server's clock and ours, in milliseconds.
+    pub fn offset(&self) -> i64 {
+        self.offset.load(Ordering::SeqCst)
+    }
+}
+
+fn local_millis() -> u64 {
+    let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    u64::try_from(current_time.as_millis()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::{GraviexError, Params};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A request ready to go over the wire, already signed if it needed to be.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String, // like "https://graviex.net/webapi/v3/orders.json"
+    pub params: Params, // sent as the query string
+    pub timeout: u64, // seconds
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Sends requests for a `GraviexClient`.
+///
+/// The default is `MinreqTransport`. Swap it out with
+/// `GraviexClient::with_transport` to record, replay or reroute traffic.
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// # Errors
+    /// returns `GraviexError::Transport` if the request never completed
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError>;
+}
+
+/// Plain http(s) over minreq.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinreqTransport;
+
+impl Transport for MinreqTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        let mut req = match request.method {
+            Method::Get => minreq::get(&request.url),
+            Method::Post => minreq::post(&request.url),
+        }
+        .with_timeout(request.timeout);
+        for (key, value) in &request.params {
+            req = req.with_param(*key, value);
+        }
+        let response = req.send()?;
+        Ok(HttpResponse {
+            status: response.status_code,
+            body: response.as_str()?.to_string(),
+        })
+    }
+}
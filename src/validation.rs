@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::decimal::decimal_places;
+use crate::*;
+
+/// What graviex accepts on one market, from its `Ticker` and `Market`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketRules {
+    pub market: String, // like "ethbtc"
+    pub base_unit: String, // like "eth"
+    pub quote_unit: String, // like "btc"
+    pub api: bool, // whether orders are accepted over the api
+    pub wstatus: String, // "off" when trading is suspended
+    pub base_min: Decimal, // smallest volume
+    pub quote_min: Decimal, // smallest price * volume
+    pub volume_fixed: usize, // decimals allowed in the volume
+    pub price_fixed: usize, // decimals allowed in the price
+    pub volume_lot: Option<Decimal>, // volume must be a multiple of this
+    pub price_lot: Option<Decimal>, // price must be a multiple of this
+}
+
+impl MarketRules {
+    /// Combine a market's ticker and attributes, taking the stricter of the
+    /// two wherever they overlap. The ask side is the base currency, so it
+    /// governs volume; the bid side is the quote currency, and governs price.
+    #[must_use]
+    pub fn new(ticker: &Ticker, market: &Market) -> MarketRules {
+        let attributes = &market.attributes;
+        MarketRules {
+            market: attributes.id.clone(),
+            base_unit: ticker.base_unit.clone(),
+            quote_unit: ticker.quote_unit.clone(),
+            api: ticker.api,
+            wstatus: ticker.wstatus.clone(),
+            base_min: ticker.base_min,
+            quote_min: ticker.quote_min,
+            volume_fixed: ticker.base_fixed.min(attributes.ask.fixed),
+            price_fixed: ticker.quote_fixed.min(attributes.bid.fixed),
+            volume_lot: lot([ticker.base_lot, Some(attributes.ask.lot)]),
+            price_lot: lot([ticker.quote_lot, Some(attributes.bid.lot)]),
+        }
+    }
+
+    /// The rules a ticker alone gives for `market`, like "ethbtc", for
+    /// when its attributes aren't at hand.
+    #[must_use]
+    pub fn from_ticker(market: &str, ticker: &Ticker) -> MarketRules {
+        MarketRules {
+            market: market.to_string(),
+            base_unit: ticker.base_unit.clone(),
+            quote_unit: ticker.quote_unit.clone(),
+            api: ticker.api,
+            wstatus: ticker.wstatus.clone(),
+            base_min: ticker.base_min,
+            quote_min: ticker.quote_min,
+            volume_fixed: ticker.base_fixed,
+            price_fixed: ticker.quote_fixed,
+            volume_lot: lot([ticker.base_lot, None]),
+            price_lot: lot([ticker.quote_lot, None]),
+        }
+    }
+
+    /// Check `order` against the rules, without sending anything.
+    ///
+    /// # Errors
+    /// returns the first `ValidationError` the order runs into
+    pub fn check(&self, order: &NewOrder) -> Result<(), ValidationError> {
+        let market = &self.market;
+        if order.market != *market {
+            return Err(ValidationError::UnknownMarket { market: order.market.clone() });
+        }
+        if !self.api {
+            return Err(ValidationError::ApiDisabled { market: market.clone() });
+        }
+        if self.wstatus == "off" {
+            return Err(ValidationError::Suspended { market: market.clone() });
+        }
+        self.check_volume(order.volume)?;
+        match order.kind {
+            OrderKind::Limit { price } => self.check_price(price, order.volume),
+            OrderKind::Market => Ok(()),
+        }
+    }
+
+    fn check_volume(&self, volume: Decimal) -> Result<(), ValidationError> {
+        self.check_amount("volume", volume, self.volume_fixed, self.volume_lot)?;
+        if volume < self.base_min {
+            return Err(self.too_small("volume", volume, self.base_min, &self.base_unit));
+        }
+        Ok(())
+    }
+
+    fn check_price(&self, price: Decimal, volume: Decimal) -> Result<(), ValidationError> {
+        self.check_amount("price", price, self.price_fixed, self.price_lot)?;
+        let total = price * volume;
+        if total < self.quote_min {
+            return Err(self.too_small("total", total, self.quote_min, &self.quote_unit));
+        }
+        Ok(())
+    }
+
+    // positive, precise enough and a whole number of lots
+    fn check_amount(
+        &self,
+        field: &'static str,
+        value: Decimal,
+        fixed: usize,
+        lot: Option<Decimal>)
+    -> Result<(), ValidationError> {
+        let market = self.market.clone();
+        if value <= Decimal::ZERO {
+            return Err(ValidationError::NotPositive { market, field, value });
+        }
+        let decimals = decimal_places(value);
+        if decimals > fixed {
+            return Err(ValidationError::TooPrecise { market, field, value, decimals, allowed: fixed });
+        }
+        match lot {
+            Some(lot) if !(value % lot).is_zero() => Err(ValidationError::NotALot { market, field, value, lot }),
+            _ => Ok(()),
+        }
+    }
+
+    fn too_small(&self, field: &'static str, value: Decimal, min: Decimal, unit: &str) -> ValidationError {
+        ValidationError::TooSmall {
+            market: self.market.clone(),
+            field,
+            value,
+            min,
+            unit: unit.to_string(),
+        }
+    }
+}
+
+// the strictest lot of those given. a lot of zero, like graviex sends for
+// most markets, means none
+fn lot(lots: [Option<Decimal>; 2]) -> Option<Decimal> {
+    lots.into_iter().flatten().filter(|lot| *lot > Decimal::ZERO).max()
+}
+
+/// Why an order was refused before it was sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// Graviex doesn't list the market.
+    UnknownMarket { market: String },
+    /// The market can't be traded over the api.
+    ApiDisabled { market: String },
+    /// The market's `wstatus` is off.
+    Suspended { market: String },
+    /// `field` is zero or negative.
+    NotPositive { market: String, field: &'static str, value: Decimal },
+    /// `field`, or the total for `"total"`, is under the market minimum.
+    TooSmall { market: String, field: &'static str, value: Decimal, min: Decimal, unit: String },
+    /// `field` has more decimals than the market allows.
+    TooPrecise { market: String, field: &'static str, value: Decimal, decimals: usize, allowed: usize },
+    /// `field` isn't a multiple of the market's lot size.
+    NotALot { market: String, field: &'static str, value: Decimal, lot: Decimal },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::UnknownMarket { market } => write!(f, "unknown market {:?}", market),
+            ValidationError::ApiDisabled { market } => write!(f, "{}: trading over the api is disabled", market),
+            ValidationError::Suspended { market } => write!(f, "{}: trading is suspended", market),
+            ValidationError::NotPositive { market, field, value } => {
+                write!(f, "{}: {} {} must be positive", market, field, value)
+            }
+            ValidationError::TooSmall { market, field, value, min, unit } => {
+                write!(f, "{}: {} {} is below the minimum of {} {}", market, field, value, min, unit)
+            }
+            ValidationError::TooPrecise { market, field, value, decimals, allowed } => write!(
+                f,
+                "{}: {} {} has {} decimals, at most {} are allowed",
+                market, field, value, decimals, allowed
+            ),
+            ValidationError::NotALot { market, field, value, lot } => {
+                write!(f, "{}: {} {} is not a multiple of the lot size {}", market, field, value, lot)
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Checks orders before a `GraviexClient` sends them, fetching each
+/// market's rules the first time it is traded and again once they are
+/// older than `max_age`. Share one with an `Arc` between clients.
+#[derive(Debug)]
+pub struct Validator {
+    max_age: Duration,
+    rules: Mutex<HashMap<String, (MarketRules, Instant)>>, // by market, with when they were fetched
+}
+
+impl Default for Validator {
+    fn default() -> Validator {
+        Validator::new()
+    }
+}
+
+impl Validator {
+    /// Refetch rules every hour.
+    #[must_use]
+    pub fn new() -> Validator {
+        Validator {
+            max_age: Duration::from_secs(3600),
+            rules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Validator {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Use `rules` for their market until they are `max_age` old.
+    pub fn insert(&self, rules: MarketRules) {
+        let mut cache = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        cache.insert(rules.market.clone(), (rules, Instant::now()));
+    }
+
+    /// The rules for `market`, unless there are none or they are stale.
+    #[must_use]
+    pub fn get(&self, market: &str) -> Option<MarketRules> {
+        let cache = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .get(market)
+            .filter(|(_, fetched)| fetched.elapsed() < self.max_age)
+            .map(|(rules, _)| rules.clone())
+    }
+
+    /// Forget every market's rules, so they are fetched again.
+    pub fn clear(&self) {
+        self.rules.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl GraviexClient {
+    /// The rules for `market`, from the validator's cache when it has
+    /// them, otherwise from graviex.
+    ///
+    /// # Errors
+    /// returns `GraviexError::Validation` if graviex doesn't list the
+    /// market, or the error fetching its ticker or attributes
+    pub fn market_rules(&self, market: &str) -> Result<MarketRules, GraviexError> {
+        if let Some(rules) = self.validator.as_ref().and_then(|validator| validator.get(market)) {
+            return Ok(rules);
+        }
+        // tickers lists every market, so an unknown one is told apart
+        // from a failed request
+        let ticker = self
+            .tickers()?
+            .remove(market)
+            .ok_or_else(|| ValidationError::UnknownMarket { market: market.to_string() })?;
+        let rules = MarketRules::new(&ticker, &self.market(market)?);
+        if let Some(validator) = &self.validator {
+            validator.insert(rules.clone());
+        }
+        Ok(rules)
+    }
+
+    /// Check `order` against its market's rules without sending it.
+    ///
+    /// # Errors
+    /// returns `GraviexError::Validation` if graviex would refuse the
+    /// order, or the error fetching the market's rules
+    pub fn validate(&self, order: &NewOrder) -> Result<(), GraviexError> {
+        self.market_rules(&order.market)?.check(order)?;
+        Ok(())
+    }
+
+    // validate before sending, when the client has a validator
+    pub(crate) fn prevalidate(&self, orders: &[NewOrder]) -> Result<(), GraviexError> {
+        if self.validator.is_none() {
+            return Ok(());
+        }
+        orders.iter().try_for_each(|order| self.validate(order))
+    }
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "async")]
+
+use graviex_api_client::mock::MockServer;
+use graviex_api_client::*;
+
+mod common;
+use common::{dec, server, trade};
+
+fn client(server: &MockServer, key: &str, secret: &str) -> AsyncGraviexClient {
+    AsyncGraviexClient::new(server.client(key, secret).with_retry(RetryPolicy::none()))
+}
+
+#[tokio::test]
+async fn public_calls() {
+    let server = server();
+    trade(&server, "0.03");
+    let alice = client(&server, "alice", "secret");
+
+    assert_eq!(alice.ticker("ethbtc").await.unwrap().last, dec("0.03"));
+    assert_eq!(alice.markets().await.unwrap().len(), 2);
+    assert!(alice.ticker("nope").await.is_err());
+}
+
+#[tokio::test]
+async fn signed_calls() {
+    let server = server();
+    let alice = client(&server, "alice", "secret");
+
+    let placed = alice.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"))).await.unwrap();
+    let open = alice.orders_get(&OrdersQuery::new().market("ethbtc")).await.unwrap();
+    assert_eq!(open.iter().map(|order| order.id).collect::<Vec<_>>(), [placed.id]);
+    let eth = alice.me().await.unwrap().accounts_filtered.into_iter().find(|a| a.currency == "eth").unwrap();
+    assert_eq!((eth.balance, eth.locked), (dec("9"), dec("1")));
+
+    // signed with the wrong secret
+    assert!(client(&server, "alice", "wrong").me().await.is_err());
+}
+
+#[tokio::test]
+async fn fans_out_in_order() {
+    let server = server();
+    trade(&server, "0.03");
+    let alice = client(&server, "alice", "secret");
+
+    let tickers = alice.ticker_each(&["giobtc", "ethbtc", "nope"]).await;
+    assert_eq!(tickers.len(), 3);
+    assert_eq!(tickers[0].as_ref().unwrap().last, Decimal::ZERO);
+    assert_eq!(tickers[1].as_ref().unwrap().last, dec("0.03"));
+    assert!(tickers[2].is_err());
+
+    let books = alice
+        .order_book_each(&[OrderBookQuery::new("ethbtc"), OrderBookQuery::new("giobtc")])
+        .await;
+    assert!(books.iter().all(Result::is_ok));
+}
+
+// a transport with a bug in it
+#[derive(Debug, Clone)]
+struct Broken;
+
+impl Transport for Broken {
+    fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        panic!("transport bug")
+    }
+}
+
+#[tokio::test]
+#[should_panic(expected = "transport bug")]
+async fn passes_panics_on() {
+    let client = AsyncGraviexClient::new(GraviexClient::new("key", "secret").with_transport(Broken));
+    let _ = client.markets().await;
+}
@@ -0,0 +1,335 @@
+use graviex_api_client::error::decode;
+use graviex_api_client::strategy::Strategy;
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+const START: usize = 1_626_048_000; // midnight
+
+// eth/btc at 0.2% a side, 8 decimals and a 0.001 eth minimum
+fn ticker() -> Ticker {
+    decode(include_str!("fixtures/ticker.json")).unwrap()
+}
+
+// hourly bars from midnight, as (open, high, low, close)
+fn hourly(bars: &[(&str, &str, &str, &str)]) -> Vec<Candle> {
+    bars.iter()
+        .enumerate()
+        .map(|(i, (open, high, low, close))| Candle {
+            timestamp: START + i * 3600,
+            open: dec(open),
+            high: dec(high),
+            low: dec(low),
+            close: dec(close),
+            volume: dec("10"),
+        })
+        .collect()
+}
+
+fn trade(id: usize, at: usize, price: &str, volume: &str) -> Trade {
+    Trade {
+        id,
+        price: dec(price),
+        volume: dec(volume),
+        funds: None,
+        market: "ethbtc".to_string(),
+        created_at: time::format_timestamp(at as u64),
+        side: None,
+        order_id: None,
+    }
+}
+
+// buys 1 eth at 0.05 once started, and sells it all at 0.06 once bought
+#[derive(Default)]
+struct Swing {
+    states: Vec<String>,
+}
+
+impl Strategy for Swing {
+    fn on_candle(&mut self, ctx: &mut dyn Context, _market: &str, _period: Period, _candle: &Candle) {
+        if self.states.is_empty() && ctx.open_orders().is_empty() {
+            ctx.place(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec("0.05"))).unwrap();
+        }
+    }
+
+    fn on_order_update(&mut self, ctx: &mut dyn Context, order: &Order) {
+        self.states.push(format!("{} {}", order.side, order.state));
+        if order.side == "buy" && order.state == "done" {
+            let eth = ctx.account("eth").unwrap().balance;
+            ctx.place(&NewOrder::limit("ethbtc", Side::Sell, eth, dec("0.06"))).unwrap();
+        }
+    }
+}
+
+#[test]
+fn fills_orders_from_candles_with_fees() {
+    let data = Dataset::new().ticker("ethbtc", ticker()).candles(
+        "ethbtc",
+        Period::Hour1,
+        hourly(&[
+            ("0.055", "0.056", "0.054", "0.055"),
+            ("0.055", "0.056", "0.0495", "0.051"), // through the bid
+            ("0.051", "0.061", "0.051", "0.06"), // through the ask
+            ("0.06", "0.06", "0.058", "0.058"),
+        ]),
+    );
+    let mut swing = Swing::default();
+    let report = Backtest::new(data).balance("btc", dec("1")).run(&mut swing);
+
+    assert_eq!(swing.states, ["buy done", "sell done"]);
+    let fills: Vec<(Side, Decimal, Decimal, bool, usize)> =
+        report.fills.iter().map(|fill| (fill.side, fill.price, fill.volume, fill.maker, fill.at)).collect();
+    assert_eq!(
+        fills,
+        [
+            (Side::Buy, dec("0.05"), dec("1"), true, START + 2 * 3600),
+            (Side::Sell, dec("0.06"), dec("0.998"), true, START + 3 * 3600),
+        ]
+    );
+    assert_eq!(report.fees()["eth"], dec("0.002"));
+    assert_eq!(report.fees()["btc"], dec("0.00011976"));
+
+    // 0.998 * 0.06 less the fee, for 0.05
+    assert_eq!(report.round_trips.len(), 1);
+    assert_eq!(report.round_trips[0].pnl(), dec("0.00976024"));
+    assert_eq!(report.win_rate, Some(Decimal::ONE));
+    assert_eq!((report.start, report.end, report.pnl), (dec("1"), dec("1.00976024"), dec("0.00976024")));
+    assert_eq!(report.currency, "btc");
+}
+
+// tries everything graviex would refuse
+#[derive(Default)]
+struct Probe {
+    errors: Vec<String>,
+    locked: Vec<Decimal>,
+}
+
+impl Strategy for Probe {
+    fn on_start(&mut self, ctx: &mut dyn Context) {
+        let orders = [
+            NewOrder::limit("ethbtc", Side::Buy, dec("0.123456789"), dec("0.05")),
+            NewOrder::limit("ethbtc", Side::Buy, dec("0.0001"), dec("0.05")),
+            NewOrder::limit("ethbtc", Side::Buy, dec("100"), dec("0.05")),
+            NewOrder::limit("giobtc", Side::Buy, dec("1"), dec("0.05")),
+            NewOrder::market("ethbtc", Side::Sell, dec("1")),
+        ];
+        for order in &orders {
+            self.errors.push(ctx.place(order).unwrap_err().to_string());
+        }
+        self.errors.push(ctx.cancel(42).unwrap_err().to_string());
+
+        let order = ctx.place(&NewOrder::limit("ethbtc", Side::Buy, dec("2"), dec("0.05"))).unwrap();
+        self.locked.push(ctx.account("btc").unwrap().locked);
+        assert_eq!(ctx.cancel(order.id).unwrap().state, "cancel");
+        self.locked.push(ctx.account("btc").unwrap().locked);
+        assert!(ctx.open_orders().is_empty());
+    }
+}
+
+#[test]
+fn refuses_what_graviex_would() {
+    let data = Dataset::new().ticker("ethbtc", ticker());
+    let mut probe = Probe::default();
+    let report = Backtest::new(data).balance("btc", dec("1")).run(&mut probe);
+
+    assert_eq!(
+        probe.errors,
+        [
+            "invalid order: ethbtc: volume 0.123456789 has 9 decimals, at most 8 are allowed",
+            "invalid order: ethbtc: volume 0.0001 is below the minimum of 0.001 eth",
+            "graviex error 2002: Failed to create order. Insufficient balance.",
+            "invalid order: unknown market \"giobtc\"",
+            "graviex error 2002: Failed to create order. Nothing to fill a market order against.",
+            "graviex error 2004: Order not found.",
+        ]
+    );
+    assert_eq!(probe.locked, [dec("0.1"), Decimal::ZERO]);
+    assert!(report.fills.is_empty());
+    assert_eq!(report.accounts[0].balance, dec("1"));
+}
+
+// takes from the book when it arrives, then offers what it got a little higher
+#[derive(Default)]
+struct Taker {
+    taken: Option<Order>,
+    updates: Vec<(String, Decimal)>,
+}
+
+impl Strategy for Taker {
+    fn on_depth(&mut self, ctx: &mut dyn Context, _book: &OrderBookState, _changes: &[LevelChange]) {
+        if self.taken.is_none() {
+            let order = NewOrder::market("ethbtc", Side::Buy, dec("0.6"));
+            self.taken = Some(ctx.place(&order).unwrap());
+            ctx.place(&NewOrder::limit("ethbtc", Side::Sell, dec("0.5"), dec("0.062"))).unwrap();
+        }
+    }
+
+    fn on_order_update(&mut self, _ctx: &mut dyn Context, order: &Order) {
+        if order.side == "sell" {
+            self.updates.push((order.state.clone(), order.executed_volume));
+        }
+    }
+}
+
+#[test]
+fn fills_orders_from_depth_and_trades() {
+    let depth: Depth = decode(include_str!("fixtures/depth.json")).unwrap();
+    let at = depth.timestamp;
+    let data = Dataset::new().ticker("ethbtc", ticker()).depth("ethbtc", depth).trades(vec![
+        // newest first, like graviex sends them
+        trade(3, at + 20, "0.0622", "1"),
+        trade(2, at + 10, "0.0621", "0.2"),
+        trade(1, at + 5, "0.0619", "5"), // short of the offer
+    ]);
+    let mut taker = Taker::default();
+    let report = Backtest::new(data).balance("btc", dec("1")).run(&mut taker);
+
+    // the two cheapest asks, 0.25 at 0.06139 and the rest at 0.0614
+    let taken = taker.taken.unwrap();
+    assert_eq!((taken.state.as_str(), taken.executed_volume), ("done", dec("0.6")));
+    assert_eq!(taken.avg_price, (dec("0.25") * dec("0.06139") + dec("0.35") * dec("0.0614")) / dec("0.6"));
+    assert!(!report.fills[0].maker && !report.fills[1].maker);
+
+    // the offer fills as far as each trade through it goes
+    assert_eq!(taker.updates, [("wait".to_string(), dec("0.2")), ("done".to_string(), dec("0.5"))]);
+    let sells: Vec<(Decimal, bool, usize)> =
+        report.fills[2..].iter().map(|fill| (fill.volume, fill.maker, fill.at)).collect();
+    assert_eq!(sells, [(dec("0.2"), true, at + 10), (dec("0.3"), true, at + 20)]);
+    // 0.6 less the 0.2% fee, less the 0.5 sold
+    let eth = report.accounts.iter().find(|account| account.currency == "eth").unwrap();
+    assert_eq!((eth.balance, eth.locked), (dec("0.0988"), Decimal::ZERO));
+}
+
+// buys 10 eth at market on the first bar and holds
+#[derive(Default)]
+struct Hold {
+    bought: bool,
+}
+
+impl Strategy for Hold {
+    fn on_candle(&mut self, ctx: &mut dyn Context, _market: &str, _period: Period, _candle: &Candle) {
+        if !self.bought {
+            self.bought = true;
+            ctx.place(&NewOrder::market("ethbtc", Side::Buy, dec("10"))).unwrap();
+        }
+    }
+}
+
+#[test]
+fn measures_drawdown_and_sharpe() {
+    let data = Dataset::new().ticker("ethbtc", ticker()).candles(
+        "ethbtc",
+        Period::Hour1,
+        hourly(&[
+            ("0.05", "0.05", "0.05", "0.05"),
+            ("0.05", "0.06", "0.05", "0.06"),
+            ("0.06", "0.06", "0.045", "0.045"),
+            ("0.045", "0.055", "0.045", "0.055"),
+        ]),
+    );
+    let report = Backtest::new(data)
+        .balance("btc", dec("1"))
+        .sample(Timeframe::hours(1))
+        .run(&mut Hold::default());
+
+    // 0.5 btc and 9.98 eth after the fee, at each close
+    let values: Vec<Decimal> = report.equity.iter().map(|(_, value)| *value).collect();
+    assert_eq!(values, [dec("0.999"), dec("1.0988"), dec("0.9491"), dec("1.0489")]);
+    assert_eq!(report.equity[0].0, START + 3600);
+    assert_eq!(report.pnl, dec("0.0489"));
+    assert_eq!(report.max_drawdown.round_dp(8), dec("0.13623953"));
+    assert_eq!(report.sharpe.unwrap().round_dp(6), dec("14.015788"));
+    // nothing sold, so nothing won or lost
+    assert_eq!(report.win_rate, None);
+}
+
+// bids under the market, stops on the next bar and cancels what's open
+#[derive(Default)]
+struct Tidy {
+    candles: usize,
+    states: Vec<String>,
+}
+
+impl Strategy for Tidy {
+    fn on_candle(&mut self, ctx: &mut dyn Context, _market: &str, _period: Period, _candle: &Candle) {
+        self.candles += 1;
+        if self.candles == 1 {
+            ctx.place(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec("0.01"))).unwrap();
+        } else {
+            ctx.stop();
+        }
+    }
+
+    fn on_order_update(&mut self, _ctx: &mut dyn Context, order: &Order) {
+        self.states.push(format!("{} {}", order.side, order.state));
+    }
+
+    fn on_stop(&mut self, ctx: &mut dyn Context) {
+        let open: Vec<usize> = ctx.open_orders().iter().map(|order| order.id).collect();
+        for id in open {
+            ctx.cancel(id).unwrap();
+        }
+    }
+}
+
+#[test]
+fn cancels_on_stop_reach_the_strategy() {
+    let data = Dataset::new().ticker("ethbtc", ticker()).candles(
+        "ethbtc",
+        Period::Hour1,
+        hourly(&[("0.05", "0.05", "0.05", "0.05"), ("0.05", "0.05", "0.05", "0.05")]),
+    );
+    let mut tidy = Tidy::default();
+    let report = Backtest::new(data).balance("btc", dec("1")).run(&mut tidy);
+
+    // stopped before on_stop canceled, and told all the same
+    assert_eq!(tidy.states, ["buy cancel"]);
+    let btc = report.accounts.iter().find(|account| account.currency == "btc").unwrap();
+    assert_eq!((btc.balance, btc.locked), (dec("1"), Decimal::ZERO));
+}
+
+// stops on its third timer
+#[derive(Default)]
+struct Clock {
+    ticks: Vec<u64>,
+    candles: usize,
+    stopped: bool,
+}
+
+impl Strategy for Clock {
+    fn on_candle(&mut self, _ctx: &mut dyn Context, _market: &str, _period: Period, _candle: &Candle) {
+        self.candles += 1;
+    }
+
+    fn on_timer(&mut self, ctx: &mut dyn Context) {
+        self.ticks.push(ctx.now());
+        if self.ticks.len() == 3 {
+            ctx.stop();
+        }
+    }
+
+    fn on_stop(&mut self, _ctx: &mut dyn Context) {
+        self.stopped = true;
+    }
+}
+
+#[test]
+fn replays_saved_data_on_replayed_time() {
+    let bars = [("0.05", "0.05", "0.05", "0.05"); 6];
+    let path = std::env::temp_dir().join(format!("graviex-backtest-{}.json", std::process::id()));
+    Dataset::new().ticker("ethbtc", ticker()).candles("ethbtc", Period::Hour1, hourly(&bars)).save(&path).unwrap();
+    let data = Dataset::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(data.candles[0].candles.len(), 6);
+
+    let mut clock = Clock::default();
+    Backtest::new(data).timer(std::time::Duration::from_secs(1800)).run(&mut clock);
+    let first = (START + 3600) as u64;
+    assert_eq!(clock.ticks, [first, first + 1800, first + 3600]);
+    // stopped before the bar closing with the third timer
+    assert_eq!(clock.candles, 1);
+    assert!(clock.stopped);
+
+    assert!(matches!(Dataset::load("no/such/dataset.json"), Err(DatasetError::Io(_))));
+}
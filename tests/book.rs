@@ -0,0 +1,96 @@
+use graviex_api_client::error::decode;
+use graviex_api_client::mock::{MockExchange, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+fn depth_book() -> OrderBookState {
+    let depth: Depth = decode(include_str!("fixtures/depth.json")).unwrap();
+    OrderBookState::from_depth("ethbtc", &depth)
+}
+
+fn prices(levels: impl Iterator<Item = Level>) -> Vec<Decimal> {
+    levels.map(|level| level.price).collect()
+}
+
+#[test]
+fn sorts_levels_best_first() {
+    let book = depth_book();
+    assert_eq!(book.timestamp, Some(1_626_125_887));
+    assert_eq!(prices(book.bids()), [dec("0.06101"), dec("0.061"), dec("0.0595")]);
+    assert_eq!(prices(book.asks()), [dec("0.06139"), dec("0.0614"), dec("0.0625")]);
+    assert_eq!(book.best_bid(), Some(Level { price: dec("0.06101"), volume: dec("0.3") }));
+    assert_eq!(book.spread(), Some(dec("0.00038")));
+    assert_eq!(book.mid(), Some(dec("0.0612")));
+
+    let order_book: OrderBook = decode(include_str!("fixtures/order_book.json")).unwrap();
+    let book = OrderBookState::from_order_book("ethbtc", &order_book);
+    // remaining volume, not the original
+    assert_eq!(book.best_ask(), Some(Level { price: dec("0.0614"), volume: dec("0.5") }));
+    assert_eq!(book.best_bid().unwrap().price, dec("0.059"));
+}
+
+#[test]
+fn depth_and_fills() {
+    let book = depth_book();
+    let volumes: Vec<Decimal> = book.cumulative(Side::Sell).iter().map(|level| level.volume).collect();
+    assert_eq!(volumes, [dec("0.25"), dec("0.75"), dec("1.95")]);
+    assert_eq!(book.volume_to(Side::Buy, dec("0.061")), dec("2.3"));
+
+    // 0.25 at 0.06139 and 0.25 at 0.0614
+    assert_eq!(book.vwap(Side::Buy, dec("0.5")), Some(dec("0.061395")));
+    assert_eq!(book.vwap(Side::Sell, dec("0.3")), Some(dec("0.06101")));
+    assert_eq!(book.vwap(Side::Buy, dec("2")), None);
+}
+
+#[test]
+fn diffs_consecutive_snapshots() {
+    let mut book = depth_book();
+    let next: Depth = serde_json::from_str(
+        r#"{"timestamp":1626125900,
+            "asks":[["0.0625","1.2"],["0.0614","0.4"]],
+            "bids":[["0.0611","1.0"],["0.06101","0.3"],["0.061","2.0"],["0.0595","10.0"]]}"#,
+    )
+    .unwrap();
+    let changes = book.update_from_depth(&next);
+    assert_eq!(
+        changes,
+        [
+            LevelChange { side: Side::Buy, price: dec("0.0611"), before: dec("0"), after: dec("1.0") },
+            LevelChange { side: Side::Sell, price: dec("0.06139"), before: dec("0.25"), after: dec("0") },
+            LevelChange { side: Side::Sell, price: dec("0.0614"), before: dec("0.5"), after: dec("0.4") },
+        ]
+    );
+    assert_eq!(changes[2].delta(), dec("-0.1"));
+    assert!(book.update_from_depth(&next).is_empty());
+}
+
+#[test]
+fn polls_either_endpoint() {
+    let mut exchange = MockExchange::new();
+    exchange.add_member("alice", "secret");
+    exchange.credit("alice", "btc", dec("1"));
+    exchange.credit("alice", "eth", dec("10"));
+    let server = MockServer::start(exchange).unwrap();
+    let alice = server.client("alice", "secret");
+    for (side, price) in [(Side::Buy, "0.04"), (Side::Buy, "0.04"), (Side::Sell, "0.06")] {
+        alice.orders_post(&NewOrder::limit("ethbtc", side, dec("1"), dec(price))).unwrap();
+    }
+
+    let mut from_depth = OrderBookState::new("ethbtc");
+    let changes = alice.refresh_depth(&mut from_depth, &DepthQuery::new("ethbtc")).unwrap();
+    assert_eq!(changes.len(), 2);
+    let mut from_order_book = OrderBookState::new("ethbtc");
+    alice.refresh_order_book(&mut from_order_book, &OrderBookQuery::new("ethbtc")).unwrap();
+
+    for book in [&from_depth, &from_order_book] {
+        assert_eq!(book.best_bid(), Some(Level { price: dec("0.04"), volume: dec("2") }));
+        assert_eq!(book.mid(), Some(dec("0.05")));
+    }
+
+    alice.orders_clear(Side::Buy).unwrap();
+    let changes = alice.refresh_depth(&mut from_depth, &DepthQuery::new("ethbtc")).unwrap();
+    assert_eq!(changes[0].delta(), dec("-2"));
+    assert_eq!(from_depth.best_bid(), None);
+}
@@ -0,0 +1,155 @@
+use std::sync::{Arc, Mutex};
+
+use graviex_api_client::mock::{MockExchange, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+const START: usize = 1_699_999_200; // on the hour
+const MINUTE: usize = 60;
+
+// trades `minutes` after START at `price`, then the clock stops at 150
+fn server(trades: &[(usize, &str)]) -> MockServer {
+    let mut exchange = MockExchange::new();
+    for key in ["alice", "bob"] {
+        exchange.add_member(key, "secret");
+        exchange.credit(key, "btc", dec("10"));
+        exchange.credit(key, "eth", dec("100"));
+    }
+    for (minutes, price) in trades {
+        exchange.set_time((START + minutes * MINUTE) as u64);
+        exchange.place_order("alice", "ethbtc", Side::Sell, dec("1"), dec(price)).unwrap();
+        exchange.place_order("bob", "ethbtc", Side::Buy, dec("1"), dec(price)).unwrap();
+    }
+    exchange.set_time((START + 150 * MINUTE) as u64);
+    MockServer::start(exchange).unwrap()
+}
+
+fn trading_server() -> MockServer {
+    server(&[(0, "0.05"), (1, "0.051"), (2, "0.049"), (5, "0.05"), (17, "0.052"), (40, "0.06"), (41, "0.058"), (90, "0.055"), (130, "0.054")])
+}
+
+// every bar from START in one request
+fn everything(client: &GraviexClient) -> Vec<Candle> {
+    client.kline(&KlineQuery::new("ethbtc", Period::Minute1).timestamp(START).limit(1000)).unwrap()
+}
+
+#[test]
+fn pages_forward_without_gaps_or_duplicates() {
+    let server = trading_server();
+    let client = server.client("alice", "secret");
+    let all = everything(&client);
+    assert_eq!(all.len(), 151);
+
+    for page_size in [1, 7, 150, 151, 500] {
+        let range = KlineRange::new("ethbtc", Period::Minute1, START).page_size(page_size);
+        let paged: Vec<Candle> = client.kline_range(&range).collect::<Result<_, _>>().unwrap();
+        assert_eq!(paged, all, "pages of {}", page_size);
+    }
+
+    // between two times that aren't on a bar
+    let range = KlineRange::new("ethbtc", Period::Minute1, START + 10 * MINUTE + 30).to(START + 100 * MINUTE + 30).page_size(8);
+    let paged: Vec<Candle> = client.kline_range(&range).collect::<Result<_, _>>().unwrap();
+    assert_eq!(paged, all[11..=100]);
+}
+
+#[test]
+fn pages_backward_from_the_newest_bar() {
+    let server = trading_server();
+    let client = server.client("alice", "secret");
+    let all = everything(&client);
+
+    let range = KlineRange::new("ethbtc", Period::Minute1, START).page_size(9).backward();
+    let paged: Vec<Candle> = client.kline_range(&range).collect::<Result<_, _>>().unwrap();
+    assert_eq!(paged, all.iter().rev().cloned().collect::<Vec<_>>());
+
+    let range = range.to(START + 40 * MINUTE);
+    let paged: Vec<Candle> = client.kline_range(&range).take(3).collect::<Result<_, _>>().unwrap();
+    assert_eq!(paged, [all[40].clone(), all[39].clone(), all[38].clone()]);
+
+    // hourly bars too
+    let hourly: Vec<Candle> = client
+        .kline_range(&KlineRange::new("ethbtc", Period::Hour1, START).page_size(1).backward())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let timestamps: Vec<usize> = hourly.iter().map(|candle| candle.timestamp).collect();
+    assert_eq!(timestamps, [START + 120 * MINUTE, START + 60 * MINUTE, START]);
+    assert_eq!(hourly[2].high, dec("0.06"));
+}
+
+// graviex as it would look if it skipped bars without trades
+#[derive(Debug, Clone, Default)]
+struct Sparse {
+    requests: Arc<Mutex<usize>>,
+}
+
+impl Transport for Sparse {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        *self.requests.lock().unwrap() += 1;
+        let mut response = MinreqTransport.send(request)?;
+        if request.url.ends_with("/k.json") {
+            let candles: Vec<Candle> = serde_json::from_str(&response.body).unwrap();
+            let traded: Vec<Candle> = candles.into_iter().filter(|candle| !candle.volume.is_zero()).collect();
+            response.body = serde_json::to_string(&traded).unwrap();
+        }
+        Ok(response)
+    }
+}
+
+#[test]
+fn fills_bars_graviex_left_out() {
+    let server = trading_server();
+    let all = everything(&server.client("alice", "secret"));
+    let transport = Sparse::default();
+    let client = server.client("alice", "secret").with_transport(transport.clone());
+
+    // quiet bars after the last trade are nowhere to be seen
+    let range = KlineRange::new("ethbtc", Period::Minute1, START).to(START + 130 * MINUTE).page_size(20);
+    let paged: Vec<Candle> = client.kline_range(&range).collect::<Result<_, _>>().unwrap();
+    assert_eq!(paged, all[..=130]);
+    assert_eq!(*transport.requests.lock().unwrap(), 8);
+
+    let backward: Vec<Candle> = client.kline_range(&range.backward()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(backward, all[..=130].iter().rev().cloned().collect::<Vec<_>>());
+}
+
+#[test]
+fn merges_pending_trades_into_the_open_bar() {
+    let pending: KlineWithPending = serde_json::from_str(include_str!("fixtures/k_with_pending_trades.json")).unwrap();
+    let mut candles = pending.k.clone();
+
+    // the last bar already counts the pending trade
+    merge_pending(&mut candles, &pending.trades, Period::Minute1);
+    assert_eq!(candles, pending.k);
+
+    let mut later = pending.trades[0].clone();
+    later.id += 1;
+    later.price = dec("0.0625");
+    later.volume = dec("0.3");
+    later.created_at = "2021-07-12T21:33:10Z".to_string();
+    merge_pending(&mut candles, &[later], Period::Minute1);
+    assert_eq!(candles.len(), 4);
+    assert_eq!(candles[2], Candle {
+        timestamp: 1_626_125_520,
+        open: dec("0.0612"),
+        high: dec("0.0612"),
+        low: dec("0.0612"),
+        close: dec("0.0612"),
+        volume: Decimal::ZERO,
+    });
+    assert_eq!((candles[3].timestamp, candles[3].open, candles[3].volume), (1_626_125_580, dec("0.0625"), dec("0.3")));
+
+    // through the api, and as the end of a range
+    let server = server(&[(0, "0.05"), (149, "0.051"), (150, "0.052")]);
+    let client = server.client("alice", "secret");
+    let query = KlineQuery::new("ethbtc", Period::Minute1).limit(3);
+    let merged = client.kline_with_pending(&KlinePendingQuery::new(query.clone(), 0)).unwrap();
+    assert_eq!(merged, client.kline(&query).unwrap());
+    assert_eq!(merged[2].volume, dec("1"));
+
+    let range = KlineRange::new("ethbtc", Period::Minute1, START + 140 * MINUTE).pending(0);
+    let paged: Vec<Candle> = client.kline_range(&range).collect::<Result<_, _>>().unwrap();
+    assert_eq!(paged.len(), 11);
+    assert_eq!(paged.last(), merged.last());
+}
@@ -0,0 +1,27 @@
+// fixtures shared by the tests, each test binary uses its own share of them
+#![allow(dead_code)]
+
+use graviex_api_client::mock::{MockExchange, MockServer};
+use graviex_api_client::*;
+
+pub fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+// alice and bob each start with 1 btc and 10 eth, signing with "secret"
+pub fn server() -> MockServer {
+    let mut exchange = MockExchange::new();
+    for key in ["alice", "bob"] {
+        exchange.add_member(key, "secret");
+        exchange.credit(key, "btc", dec("1"));
+        exchange.credit(key, "eth", dec("10"));
+    }
+    MockServer::start(exchange).unwrap()
+}
+
+// bob trades 1 eth with himself at `price`
+pub fn trade(server: &MockServer, price: &str) {
+    let bob = server.client("bob", "secret");
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec(price))).unwrap();
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec(price))).unwrap();
+}
@@ -0,0 +1,73 @@
+use graviex_api_client::error::decode;
+use graviex_api_client::mock::{MockExchange, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+fn ethbtc() -> FeeSchedule {
+    let ticker: Ticker = decode(include_str!("fixtures/ticker.json")).unwrap();
+    FeeSchedule::from_ticker("ethbtc", &ticker)
+}
+
+#[test]
+fn buys_pay_the_fee_in_base() {
+    let cost = ethbtc().cost(Side::Buy, dec("0.05"), dec("2"));
+    assert_eq!(cost.spent.to_string(), "0.10 btc");
+    assert_eq!(cost.gross.to_string(), "2 eth");
+    assert_eq!(cost.fee.value, dec("0.004"));
+    assert_eq!(cost.fee.currency, "eth");
+    assert_eq!(cost.net.value, dec("1.996"));
+}
+
+#[test]
+fn sells_pay_the_fee_in_quote() {
+    let cost = ethbtc().cost(Side::Sell, dec("0.05"), dec("2"));
+    assert_eq!(cost.spent.to_string(), "2 eth");
+    assert_eq!(cost.gross.value, dec("0.1"));
+    assert_eq!(cost.fee.to_string(), "0.00020 btc");
+    assert_eq!(cost.net.value, dec("0.0998"));
+}
+
+#[test]
+fn break_even_covers_both_fees() {
+    let fees = ethbtc();
+    // 0.05 / 0.998^2 = 0.0502006...
+    assert_eq!(fees.break_even(Side::Buy, dec("0.05")), dec("0.05020061"));
+    // 0.05 * 0.998^2 = 0.0498002
+    assert_eq!(fees.break_even(Side::Sell, dec("0.05")), dec("0.0498002"));
+
+    // a round trip at the break even price leaves at least what we spent
+    let bought = fees.cost(Side::Buy, dec("0.05"), dec("2"));
+    let sold = fees.cost(Side::Sell, bought.break_even, bought.net.value);
+    assert!(sold.net.value >= bought.spent.value);
+
+    let free = FeeSchedule { buy_fee: Decimal::ZERO, sell_fee: Decimal::ZERO, ..fees };
+    assert_eq!(free.break_even(Side::Buy, dec("0.05")), dec("0.05"));
+}
+
+#[test]
+fn matches_what_the_exchange_charges() {
+    let mut exchange = MockExchange::new();
+    exchange.add_member("alice", "secret");
+    exchange.add_member("bob", "secret");
+    exchange.credit("alice", "eth", dec("10"));
+    exchange.credit("bob", "btc", dec("1"));
+    let server = MockServer::start(exchange).unwrap();
+    let alice = server.client("alice", "secret");
+    let bob = server.client("bob", "secret");
+
+    let fees = alice.fee_schedule("ethbtc").unwrap();
+    assert_eq!(fees, FeeSchedule::from_market(&alice.market("ethbtc").unwrap()));
+
+    alice.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("3"), dec("0.07"))).unwrap();
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("3"), dec("0.07"))).unwrap();
+
+    let sold = fees.cost(Side::Sell, dec("0.07"), dec("3"));
+    let bought = fees.cost(Side::Buy, dec("0.07"), dec("3"));
+    let exchange = server.exchange();
+    assert_eq!(exchange.balance("alice", "btc").balance, sold.net.value);
+    assert_eq!(exchange.balance("alice", "eth").balance, dec("10") - sold.spent.value);
+    assert_eq!(exchange.balance("bob", "eth").balance, bought.net.value);
+    assert_eq!(exchange.balance("bob", "btc").balance, dec("1") - bought.spent.value);
+}
@@ -0,0 +1,119 @@
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+const START: usize = 1_626_048_000; // midnight
+
+// the closes of the classic 14 day rsi example, hourly from midnight, with
+// highs, lows and volumes made up around them
+const CLOSES: [&str; 40] = [
+    "44.34", "44.09", "44.15", "43.61", "44.33", "44.83", "45.10", "45.42", "45.84", "46.08",
+    "45.89", "46.03", "45.61", "46.28", "46.28", "46.00", "46.03", "46.41", "46.22", "45.64",
+    "46.21", "46.25", "45.71", "46.45", "45.78", "45.35", "44.03", "44.18", "44.22", "44.57",
+    "43.42", "42.66", "43.13", "43.50", "43.97", "44.20", "43.85", "44.61", "45.02", "44.88",
+];
+
+fn candles() -> Vec<Candle> {
+    CLOSES
+        .iter()
+        .enumerate()
+        .map(|(i, close)| {
+            let close = dec(close);
+            Candle {
+                timestamp: START + i * 3600,
+                open: close,
+                high: close + dec("0.5") + Decimal::new((i % 3) as i64, 1),
+                low: close - dec("0.4") - Decimal::new((i % 4) as i64, 1),
+                close,
+                volume: Decimal::from(100 + (i * 37) % 50),
+            }
+        })
+        .collect()
+}
+
+// to compare with the reference values
+fn round(values: &[Option<Decimal>]) -> Vec<Option<Decimal>> {
+    values.iter().map(|value| value.map(|value| value.round_dp(8))).collect()
+}
+
+fn some(values: &[&str]) -> Vec<Option<Decimal>> {
+    values.iter().map(|value| Some(dec(value))).collect()
+}
+
+#[test]
+fn moving_averages() {
+    let sma = Sma::new(5).batch(&candles());
+    assert!(sma[..4].iter().all(Option::is_none));
+    assert_eq!(round(&sma[37..]), some(&["44.026", "44.33", "44.512"]));
+
+    let ema = Ema::new(10).batch(&candles());
+    assert_eq!(ema[8], None);
+    assert_eq!(round(&ema[9..12]), some(&["44.779", "44.981", "45.17172727"]));
+    assert_eq!(round(&ema[39..]), some(&["44.38734818"]));
+}
+
+#[test]
+fn rsi() {
+    let rsi = Rsi::new(14).batch(&candles());
+    assert_eq!(rsi[13], None);
+    assert_eq!(round(&rsi[14..17]), some(&["70.46413502", "66.24961855", "66.48094183"]));
+    assert_eq!(round(&rsi[39..]), some(&["53.04039174"]));
+
+    let mut rising = Rsi::new(2);
+    for close in ["1", "2", "3", "4"] {
+        rising.update(dec(close));
+    }
+    assert_eq!(rising.update(dec("5")), Some(Decimal::ONE_HUNDRED));
+}
+
+#[test]
+fn macd_and_bollinger() {
+    let macd = Macd::new(12, 26, 9).batch(&candles());
+    assert_eq!(macd[32], None);
+    let first = macd[33].unwrap();
+    assert_eq!(
+        (first.macd.round_dp(8), first.signal.round_dp(8), first.histogram.round_dp(8)),
+        (dec("-0.502083"), dec("-0.14844056"), dec("-0.35364244"))
+    );
+    let last = macd[39].unwrap();
+    assert_eq!((last.macd.round_dp(8), last.signal.round_dp(8)), (dec("-0.19872864"), dec("-0.278233")));
+
+    let bands = Bollinger::new(20, Decimal::TWO).batch(&candles())[39].unwrap();
+    assert_eq!(
+        (bands.upper.round_dp(8), bands.middle, bands.lower.round_dp(8)),
+        (dec("46.71949505"), dec("44.5995"), dec("42.47950495"))
+    );
+}
+
+#[test]
+fn atr_vwap_and_obv() {
+    let atr = Atr::new(14).batch(&candles());
+    assert_eq!(atr[12], None);
+    assert_eq!(round(&atr[13..14]), some(&["1.16785714"]));
+    assert_eq!(round(&atr[39..]), some(&["1.23431757"]));
+
+    let vwap = Vwap::new().batch(&candles());
+    assert_eq!(round(&vwap[39..]), some(&["45.00619561"]));
+    // the second day starts over
+    let daily = Vwap::session(Timeframe::days(1)).batch(&candles());
+    assert_eq!(daily[24], Some((candles()[24].high + candles()[24].low + candles()[24].close) / Decimal::from(3)));
+    assert_eq!(round(&daily[39..]), some(&["44.21791178"]));
+
+    let obv = Obv::new().batch(&candles());
+    assert_eq!(obv[0], Some(Decimal::ZERO));
+    assert_eq!(obv[39], Some(Decimal::from(1078)));
+}
+
+#[test]
+fn streams_like_it_batches() {
+    let candles = candles();
+    let mut streaming = Macd::new(12, 26, 9);
+    let (history, live) = candles.split_at(30);
+    streaming.batch(history);
+    let live: Vec<Option<MacdValue>> = live.iter().map(|candle| streaming.next(candle)).collect();
+    assert_eq!(live, Macd::new(12, 26, 9).batch(&candles)[30..]);
+
+    streaming.reset();
+    assert_eq!(streaming.next(&candles[39]), None);
+}
@@ -0,0 +1,130 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use graviex_api_client::logging::{redact, REDACTED};
+use graviex_api_client::*;
+
+const ADDRESS: &str = "1BoatSLRHtKNngkdXEeobR76b53LETtpyT";
+
+// every field of every span and event, as "name=value"
+#[derive(Default)]
+struct Capture {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: AtomicU64,
+}
+
+struct Lines<'a>(&'a Mutex<Vec<String>>);
+
+impl Visit for Lines<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.lock().unwrap().push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        span.record(&mut Lines(&self.lines));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &Id, values: &Record<'_>) {
+        values.record(&mut Lines(&self.lines));
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        event.record(&mut Lines(&self.lines));
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+// a gateway failing with the request echoed back in its error page
+#[derive(Debug, Clone)]
+struct Echo;
+
+impl Transport for Echo {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        let params: Vec<String> = request.params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        Ok(HttpResponse { status: 502, body: format!("<html>bad gateway for {}</html>", params.join("&")) })
+    }
+}
+
+#[test]
+fn redacts_credentials_and_addresses() {
+    let params = vec![
+        ("access_key", "key".to_string()),
+        ("currency", "btc".to_string()),
+        ("fund_uid", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string()),
+        ("sum", "0.25".to_string()),
+        ("signature", "9a583ffe".to_string()),
+    ];
+    assert_eq!(
+        redact(&params),
+        format!("access_key={r}&currency=btc&fund_uid={r}&sum=0.25&signature={r}", r = REDACTED)
+    );
+}
+
+#[test]
+fn client_debug_hides_credentials() {
+    let client = GraviexClient::new("my-access-key", "my-secret-key");
+    let debug = format!("{:?}", client);
+    assert!(!debug.contains("my-access-key"));
+    assert!(!debug.contains("my-secret-key"));
+    assert!(debug.contains("https://graviex.net"));
+}
+
+#[test]
+fn failing_responses_keep_addresses_out() {
+    let capture = Capture::default();
+    let lines = Arc::clone(&capture.lines);
+    let client = GraviexClient::new("key", "secret").with_transport(Echo).with_retry(RetryPolicy::none());
+
+    let withdraw = NewWithdraw::new("btc", ADDRESS, "0.1".parse().unwrap());
+    let result = tracing::subscriber::with_default(capture, || client.create_withdraw(&withdraw));
+    // the caller still gets the whole body
+    assert!(matches!(result, Err(GraviexError::Status { ref body, .. }) if body.contains(ADDRESS)));
+
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|line| line.contains("http status 502")));
+    assert!(lines.iter().all(|line| !line.contains(ADDRESS)), "{:?}", lines);
+}
+
+// a gateway failing with a page that quotes the member's withdrawals
+#[derive(Debug, Clone)]
+struct Leaky;
+
+impl Transport for Leaky {
+    fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        Ok(HttpResponse { status: 503, body: format!("<html>withdraw to {} pending</html>", ADDRESS) })
+    }
+}
+
+#[test]
+fn retries_keep_addresses_out() {
+    let capture = Capture::default();
+    let lines = Arc::clone(&capture.lines);
+    let client = GraviexClient::new("key", "secret")
+        .with_transport(Leaky)
+        .with_retry(RetryPolicy::new(1).base_delay(Duration::from_millis(1)));
+
+    let result = tracing::subscriber::with_default(capture, || client.withdraws(&WithdrawsQuery::new("btc")));
+    assert!(matches!(result, Err(GraviexError::Status { status: 503, .. })));
+
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|line| line.contains("retrying request")));
+    assert!(lines.iter().all(|line| !line.contains(ADDRESS)), "{:?}", lines);
+}
@@ -0,0 +1,194 @@
+use graviex_api_client::mock::{MockFailure, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::{dec, server};
+
+fn client(server: &MockServer, key: &str) -> GraviexClient {
+    server.client(key, "secret")
+}
+
+#[test]
+fn public_endpoints() {
+    let server = server();
+    let client = client(&server, "alice");
+
+    let markets = client.markets().unwrap();
+    assert!(markets.iter().any(|m| m.id == "ethbtc"));
+    let market = client.market("ethbtc").unwrap();
+    assert_eq!(market.attributes.base_unit, "eth");
+    assert_eq!(market.attributes.bid.currency, "btc");
+    assert!(client.tickers().unwrap().contains_key("giobtc"));
+    assert!(client.timestamp().unwrap() > 0);
+    assert!(client.depth(&DepthQuery::new("ethbtc")).unwrap().asks.is_empty());
+}
+
+#[test]
+fn rejects_bad_signatures() {
+    let server = server();
+    let alice = client(&server, "alice");
+    let wrong = GraviexClient::new("alice", "not-the-secret").with_base_url(&server.url());
+    let unknown = GraviexClient::new("nobody", "secret").with_base_url(&server.url());
+
+    assert!(alice.me().is_ok());
+    match wrong.me() {
+        Err(GraviexError::Auth { code, .. }) => assert_eq!(code, 2005),
+        other => panic!("expected a signature error, got {:?}", other),
+    }
+    match unknown.me() {
+        Err(GraviexError::Auth { code, .. }) => assert_eq!(code, 2008),
+        other => panic!("expected an unknown key error, got {:?}", other),
+    }
+
+    // secrets longer than a sha256 block are hashed first, on both ends
+    let long = "s".repeat(100);
+    server.exchange().add_member("carol", &long);
+    assert!(server.client("carol", &long).me().is_ok());
+}
+
+#[test]
+fn rejects_reused_tonces() {
+    let server = server();
+    let secret = "secret";
+    let target = "/webapi/v3/members/me.json";
+    let tonce = (time::now() * 1000).to_string();
+    let mut params = vec![("access_key", "alice".to_string()), ("tonce", tonce)];
+    let signature = signature::sign(secret, "GET", target, &mut params);
+    params.push(("signature", signature));
+    let request = HttpRequest {
+        method: Method::Get,
+        url: format!("{}{}", server.url(), target),
+        params,
+        timeout: 2,
+    };
+
+    assert_eq!(MinreqTransport.send(&request).unwrap().status, 200);
+    let replayed = MinreqTransport.send(&request).unwrap();
+    assert_eq!(replayed.status, 401);
+    match GraviexError::from_response(replayed.status, &replayed.body) {
+        Some(e) => assert_eq!(e.code(), Some(tonce::TONCE_USED)),
+        None => panic!("replayed tonce was accepted"),
+    }
+}
+
+#[test]
+fn matches_orders_and_settles_balances() {
+    let server = server();
+    let alice = client(&server, "alice");
+    let bob = client(&server, "bob");
+
+    let ask = alice.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("2"), dec("0.05"))).unwrap();
+    assert_eq!(ask.state, "wait");
+    assert_eq!(server.exchange().balance("alice", "eth").locked, dec("2"));
+
+    let depth = bob.depth(&DepthQuery::new("ethbtc")).unwrap();
+    assert_eq!(depth.asks[0].price, dec("0.05"));
+    assert_eq!(depth.asks[0].vol, dec("2"));
+
+    // bob crosses the spread and fills at alice's price
+    let bid = bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("1.5"), dec("0.06"))).unwrap();
+    assert_eq!(bid.state, "done");
+    assert_eq!(bid.avg_price, dec("0.05"));
+    assert_eq!(bid.executed_volume, dec("1.5"));
+
+    let exchange = server.exchange();
+    let fee = dec("0.998");
+    assert_eq!(exchange.balance("bob", "btc").balance, dec("1") - dec("0.075"));
+    assert_eq!(exchange.balance("bob", "btc").locked, dec("0"));
+    assert_eq!(exchange.balance("bob", "eth").balance, dec("10") + dec("1.5") * fee);
+    assert_eq!(exchange.balance("alice", "btc").balance, dec("1") + dec("0.075") * fee);
+    assert_eq!(exchange.balance("alice", "eth").locked, dec("0.5"));
+    drop(exchange);
+
+    let order = alice.order(ask.id).unwrap();
+    assert_eq!(order.remaining_volume, dec("0.5"));
+    assert_eq!(order.trades.unwrap().len(), 1);
+
+    let mine = bob.trades_my(&TradesQuery::new("ethbtc")).unwrap();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0].side.as_deref(), Some("buy"));
+    assert_eq!(mine[0].order_id, Some(bid.id));
+
+    let ticker = alice.ticker("ethbtc").unwrap();
+    assert_eq!(ticker.last, dec("0.05"));
+    assert_eq!(ticker.volume, dec("1.5"));
+}
+
+#[test]
+fn cancels_and_refunds() {
+    let server = server();
+    let alice = client(&server, "alice");
+
+    let order = alice.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("10"), dec("0.01"))).unwrap();
+    assert_eq!(server.exchange().balance("alice", "btc").locked, dec("0.1"));
+    assert_eq!(alice.orders_get(&OrdersQuery::new().market("ethbtc")).unwrap().len(), 1);
+
+    let cancelled = alice.order_delete(order.id).unwrap();
+    assert_eq!(cancelled.state, "cancel");
+    assert_eq!(server.exchange().balance("alice", "btc").balance, dec("1"));
+    assert!(alice.orders_get(&OrdersQuery::new().market("ethbtc")).unwrap().is_empty());
+}
+
+#[test]
+fn refuses_orders_beyond_balance() {
+    let server = server();
+    let alice = client(&server, "alice");
+
+    match alice.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("100"), dec("0.05"))) {
+        Err(GraviexError::Api { code, .. }) => assert_eq!(code, 2002),
+        other => panic!("expected order creation to fail, got {:?}", other),
+    }
+    assert_eq!(server.exchange().balance("alice", "btc").locked, dec("0"));
+}
+
+#[test]
+fn multi_orders_and_clear() {
+    let server = server();
+    let alice = client(&server, "alice");
+
+    let multi = MultiOrder::new("ethbtc")
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.07")))
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.08")));
+    let orders = alice.orders_multi(&multi).unwrap();
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[1].price, dec("0.08"));
+
+    let book = alice.order_book(&OrderBookQuery::new("ethbtc")).unwrap();
+    assert_eq!(book.asks.len(), 2);
+
+    let cleared = alice.orders_clear(Side::Sell).unwrap();
+    assert_eq!(cleared.len(), 2);
+    assert_eq!(server.exchange().balance("alice", "eth").balance, dec("10"));
+}
+
+#[test]
+fn funding_history() {
+    let server = server();
+    let alice = client(&server, "alice");
+
+    let withdraw = alice.create_withdraw(&NewWithdraw::new("btc", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT", dec("0.25"))).unwrap();
+    assert_eq!(withdraw.amount, dec("0.25"));
+    assert_eq!(server.exchange().balance("alice", "btc").balance, dec("0.75"));
+    assert_eq!(alice.withdraws(&WithdrawsQuery::new("btc")).unwrap().len(), 1);
+
+    let history = alice.history(&HistoryQuery::new()).unwrap();
+    assert_eq!(history.iter().filter(|e| e.kind == "deposit").count(), 2);
+    assert_eq!(history.iter().filter(|e| e.kind == "withdrawal").count(), 1);
+    assert_eq!(alice.deposits(&DepositsQuery::new()).unwrap().len(), 2);
+}
+
+#[test]
+fn injected_failures() {
+    let server = server();
+    let alice = client(&server, "alice").with_retry(RetryPolicy::none());
+    let order = NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"));
+
+    server.exchange().fail_next("POST", "/webapi/v3/orders.json", MockFailure::LostResponse);
+    // minreq reports the hang up as a 503 without a body
+    assert!(alice.orders_post(&order).is_err_and(|e| placement::is_ambiguous(&e)));
+    server.exchange().fail_next("POST", "/webapi/v3/orders.json", MockFailure::Unavailable);
+    assert!(matches!(alice.orders_post(&order), Err(GraviexError::Status { status: 503, .. })));
+
+    // only the lost one went through
+    assert_eq!(server.exchange().balance("alice", "eth").locked, dec("1"));
+}
@@ -0,0 +1,242 @@
+// every model against a response captured from graviex, trimmed down
+use std::collections::HashMap;
+
+use graviex_api_client::error::decode;
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+macro_rules! fixture {
+    ($name:literal) => {
+        include_str!(concat!("fixtures/", $name))
+    };
+}
+
+#[test]
+fn markets() {
+    let markets: Vec<MarketList> = decode(fixture!("markets.json")).unwrap();
+    assert_eq!(markets.len(), 3);
+    assert_eq!(markets[2].id, "vrscbtc");
+    assert_eq!(markets[2].name, "VRSC/BTC");
+}
+
+#[test]
+fn market() {
+    let market: Market = decode(fixture!("market.json")).unwrap();
+    let attributes = market.attributes;
+    assert_eq!(attributes.id, "ethbtc");
+    assert_eq!(attributes.base_unit, "eth");
+    assert_eq!(attributes.bid.currency, "btc");
+    assert_eq!(attributes.ask.fee, dec("0.002"));
+    assert_eq!(attributes.ask.fixed, 8);
+}
+
+#[test]
+fn ticker() {
+    let ticker: Ticker = decode(fixture!("ticker.json")).unwrap();
+    assert_eq!(ticker.name, "ETH/BTC");
+    assert!(ticker.api);
+    assert_eq!(ticker.base_lot, None);
+    assert_eq!(ticker.base_min, dec("0.001"));
+    assert_eq!(ticker.last, dec("0.0612"));
+    assert_eq!(ticker.sell, dec("0.06139"));
+    assert_eq!(ticker.at, 1_626_125_887);
+}
+
+#[test]
+fn tickers() {
+    let tickers: HashMap<String, Ticker> = decode(fixture!("tickers.json")).unwrap();
+    assert_eq!(tickers.len(), 2);
+    let gio = &tickers["giobtc"];
+    assert_eq!(gio.base_lot, Some(dec("1")));
+    assert_eq!(gio.block_time, "");
+    assert_eq!(gio.buy, dec("0.0000009"));
+}
+
+#[test]
+fn depth() {
+    let depth: Depth = decode(fixture!("depth.json")).unwrap();
+    assert_eq!(depth.timestamp, 1_626_125_887);
+    assert_eq!(depth.asks.len(), 3);
+    // graviex sends asks high to low, so the best ask is last
+    assert_eq!(depth.asks[2].price, dec("0.06139"));
+    assert_eq!(depth.bids[0].price, dec("0.06101"));
+    assert_eq!(depth.bids[0].vol, dec("0.3"));
+}
+
+#[test]
+fn member() {
+    let me: Member = decode(fixture!("members_me.json")).unwrap();
+    assert_eq!(me.sn, "GRX4YUDFMKAT7NR");
+    assert_eq!(me.name, None);
+    assert!(me.activated);
+    assert_eq!(me.accounts_filtered[0].currency, "btc");
+    assert_eq!(me.accounts_filtered[0].balance, dec("0.51234"));
+    assert_eq!(me.accounts_filtered[0].locked, dec("0.01"));
+}
+
+#[test]
+fn orders() {
+    let orders: Vec<Order> = decode(fixture!("orders.json")).unwrap();
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[0].id, 81723);
+    assert_eq!(orders[0].volume, orders[0].remaining_volume + orders[0].executed_volume);
+    assert!(orders[0].trades.is_none());
+    assert_eq!(orders[1].avg_price, Decimal::ZERO);
+}
+
+#[test]
+fn order_with_trades() {
+    let order: Order = decode(fixture!("order.json")).unwrap();
+    let trades = order.trades.unwrap();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].order_id, Some(order.id));
+    assert_eq!(trades[0].funds, Some(dec("0.01535")));
+}
+
+#[test]
+fn order_book() {
+    let book: OrderBook = decode(fixture!("order_book.json")).unwrap();
+    assert_eq!(book.asks[0].side, "sell");
+    assert_eq!(book.bids[0].side, "buy");
+}
+
+#[test]
+fn trades() {
+    let trades: Vec<Trade> = decode(fixture!("trades.json")).unwrap();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].side, None);
+    assert_eq!(trades[0].order_id, None);
+    assert_eq!(trades[1].created_at, "2021-07-12T21:30:59Z");
+
+    let mine: Vec<Trade> = decode(fixture!("trades_my.json")).unwrap();
+    assert_eq!(mine[0].side.as_deref(), Some("sell"));
+    assert_eq!(mine[0].order_id, Some(81723));
+}
+
+#[test]
+fn trades_simple() {
+    let trades: Vec<SimpleTrade> = decode(fixture!("trades_simple.json")).unwrap();
+    assert_eq!(trades[0].tid, 554_013);
+    assert_eq!(trades[0].side, "buy");
+    assert_eq!(trades[1].amount, dec("0.25"));
+}
+
+#[test]
+fn kline() {
+    let candles: Vec<Candle> = decode(fixture!("k.json")).unwrap();
+    assert_eq!(candles[0], Candle {
+        timestamp: 1_626_125_400,
+        open: dec("0.0601"),
+        high: dec("0.0614"),
+        low: dec("0.06"),
+        close: dec("0.0612"),
+        volume: dec("0.35"),
+    });
+
+    let pending: KlineWithPending = decode(fixture!("k_with_pending_trades.json")).unwrap();
+    assert_eq!(pending.k, candles);
+    assert_eq!(pending.trades[0].id, 554_013);
+}
+
+#[test]
+fn deposits() {
+    let deposits: Vec<Deposit> = decode(fixture!("deposits.json")).unwrap();
+    assert_eq!(deposits[0].confirmations, Some(6));
+    assert_eq!(deposits[1].txid, None);
+    assert_eq!(deposits[1].done_at, None);
+
+    let deposit: Deposit = decode(fixture!("deposit.json")).unwrap();
+    assert_eq!(deposit.id, deposits[0].id);
+    assert_eq!(deposit.amount, dec("0.5"));
+}
+
+#[test]
+fn withdraws() {
+    let withdraws: Vec<Withdraw> = decode(fixture!("withdraws.json")).unwrap();
+    assert_eq!(withdraws[0].fee, Some(dec("0.0005")));
+    assert_eq!(withdraws[0].fund_extra.as_deref(), Some("cold wallet"));
+    assert_eq!(withdraws[0].txid, None);
+}
+
+#[test]
+fn history() {
+    let history: Vec<HistoryEntry> = decode(fixture!("history.json")).unwrap();
+    assert_eq!(history[0].kind, "deposit");
+    assert_eq!(history[1].kind, "withdrawal");
+    assert_eq!(history[1].amount, dec("0.1"));
+}
+
+#[test]
+fn deposit_address() {
+    let address: DepositAddress = decode(fixture!("deposit_address.json")).unwrap();
+    assert_eq!(address.currency, "btc");
+    assert_eq!(address.address.as_deref(), Some("1BoatSLRHtKNngkdXEeobR76b53LETtpyT"));
+}
+
+#[test]
+fn fund_sources() {
+    let sources: Vec<FundSource> = decode(fixture!("fund_sources.json")).unwrap();
+    assert_eq!(sources[0].id, 77);
+    assert_eq!(sources[0].extra, "cold wallet");
+}
+
+#[test]
+fn currency_info() {
+    let info: CurrencyInfo = decode(fixture!("currency_info.json")).unwrap();
+    assert_eq!(info.code.as_deref(), Some("btc"));
+    assert_eq!(info.withdraw_fee.as_deref(), Some("0.0005"));
+    assert_eq!(info.extra["visible"], serde_json::Value::Bool(true));
+}
+
+#[test]
+fn strategies() {
+    let strategies: Vec<Strategy> = decode(fixture!("strategies.json")).unwrap();
+    assert_eq!(strategies[0].name, "Grid");
+    assert!(strategies[0].extra.contains_key("markets"));
+}
+
+#[test]
+fn settings() {
+    let settings: Settings = decode(fixture!("settings.json")).unwrap();
+    assert_eq!(settings.darkmode, Some(true));
+    assert_eq!(settings.sound, None);
+    assert_eq!(settings.markets_filter.as_deref(), Some("btc"));
+    assert_eq!(settings.extra["fav-vrscbtc"], serde_json::Value::Bool(true));
+
+    // unset fields stay out of what we store
+    let stored = serde_json::to_value(&settings).unwrap();
+    assert!(stored.get("sound").is_none());
+    assert_eq!(stored["fav-vrscbtc"], serde_json::Value::Bool(true));
+}
+
+#[test]
+fn timestamp() {
+    let timestamp: usize = decode(fixture!("timestamp.json")).unwrap();
+    assert_eq!(timestamp, 1_626_125_887);
+}
+
+#[test]
+fn errors() {
+    match GraviexError::from_response(401, fixture!("error_signature.json")) {
+        Some(GraviexError::Auth { code, .. }) => assert_eq!(code, 2005),
+        other => panic!("expected an auth error, got {:?}", other),
+    }
+    match GraviexError::from_response(400, fixture!("error_order.json")) {
+        Some(GraviexError::Api { code, message }) => {
+            assert_eq!(code, 2002);
+            assert!(message.contains("insufficient balance"));
+        }
+        other => panic!("expected an api error, got {:?}", other),
+    }
+    assert!(GraviexError::from_response(200, fixture!("ticker.json")).is_none());
+}
+
+#[test]
+fn decode_errors_keep_the_body() {
+    match decode::<Ticker>(fixture!("markets.json")) {
+        Err(GraviexError::Decode { body, .. }) => assert!(body.contains("ethbtc")),
+        other => panic!("expected a decode error, got {:?}", other),
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+
+use graviex_api_client::mock::{parse_query, MockExchange, MockServer};
+use graviex_api_client::signature::{query_string, sign};
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+fn query<P: ToParams>(params: &P) -> String {
+    query_string(&params.to_params())
+}
+
+// the query string, decoded again, gives back the same params
+fn round_trip<P: ToParams>(params: &P) {
+    let sent = params.to_params();
+    let received = parse_query(&query_string(&sent));
+    let sent: Vec<(String, String)> = sent.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    assert_eq!(sent, received);
+}
+
+#[test]
+fn order_params() {
+    let order = NewOrder::limit("ethbtc", Side::Buy, dec("1.5"), dec("0.061"));
+    assert_eq!(query(&order), "market=ethbtc&side=buy&volume=1.5&price=0.061");
+    let market = NewOrder::market("giobtc", Side::Sell, dec("2500"));
+    assert_eq!(query(&market), "market=giobtc&side=sell&volume=2500&ord_type=market");
+
+    let multi = MultiOrder::new("ethbtc")
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.07")))
+        .push(OrderLeg::limit(Side::Buy, dec("2"), dec("0.01")));
+    assert_eq!(
+        query(&multi),
+        "market=ethbtc&orders[][side]=sell&orders[][volume]=1&orders[][price]=0.07\
+         &orders[][side]=buy&orders[][volume]=2&orders[][price]=0.01"
+    );
+    assert_eq!(multi.new_orders()[1], NewOrder::limit("ethbtc", Side::Buy, dec("2"), dec("0.01")));
+    let sweep = MultiOrder::new("ethbtc").push(OrderLeg::market(Side::Buy, dec("3")));
+    assert_eq!(query(&sweep), "market=ethbtc&orders[][side]=buy&orders[][volume]=3&orders[][ord_type]=market");
+    assert_eq!(sweep.new_orders()[0], NewOrder::market("ethbtc", Side::Buy, dec("3")));
+    assert_eq!((order.kind.price(), order.kind.ord_type()), (Some(dec("0.061")), OrderType::Limit));
+    assert_eq!((market.kind.price(), market.kind.ord_type()), (None, OrderType::Market));
+
+    round_trip(&order);
+    round_trip(&multi);
+}
+
+#[test]
+fn query_params() {
+    assert_eq!(query(&OrdersQuery::new()), "");
+    assert_eq!(
+        query(&OrdersQuery::new().market("ethbtc").state(OrderState::Done).limit(10).order_by(OrderBy::Desc)),
+        "market=ethbtc&state=done&limit=10&order_by=desc"
+    );
+    assert_eq!(
+        query(&TradesQuery::new("ethbtc").limit(50).from(100).to(200)),
+        "market=ethbtc&limit=50&from=100&to=200"
+    );
+    assert_eq!(query(&DepthQuery::new("giobtc").limit(5)), "market=giobtc&limit=5");
+
+    let kline = KlineQuery::new("ethbtc", Period::Hour1).limit(24).timestamp(1_626_125_400);
+    assert_eq!(query(&kline), "market=ethbtc&period=60&limit=24&timestamp=1626125400");
+    assert_eq!(
+        query(&KlinePendingQuery::new(kline.clone(), 554_012)),
+        "market=ethbtc&period=60&limit=24&timestamp=1626125400&trade_id=554012"
+    );
+
+    assert_eq!(query(&HistoryQuery::new().currency("btc").kind(HistoryKind::Deposit)), "currency=btc&type=deposit");
+    assert_eq!(
+        query(&DepositsQuery::new().currency("gio").currency("btc").state(DepositState::Cancelled)),
+        "currency=gio,btc&state=cancelled"
+    );
+    assert_eq!(
+        query(&WithdrawsQuery::new("eth").state(WithdrawState::AlmostDone)),
+        "currency=eth&state=almost_done"
+    );
+
+    round_trip(&kline);
+    round_trip(&TradesHistoryQuery::new().market("ethbtc").page(2));
+}
+
+#[test]
+fn funding_params() {
+    let withdraw = NewWithdraw::new("btc", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT", dec("0.25"));
+    assert_eq!(query(&withdraw), "currency=btc&fund_uid=1BoatSLRHtKNngkdXEeobR76b53LETtpyT&sum=0.25");
+
+    let source = NewFundSource::new("btc", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT", "cold");
+    assert_eq!(query(&source), "currency=btc&uid=1BoatSLRHtKNngkdXEeobR76b53LETtpyT&extra=cold");
+    round_trip(&withdraw);
+    round_trip(&source);
+}
+
+#[test]
+fn periods() {
+    for period in Period::ALL {
+        assert_eq!(Period::from_minutes(period.minutes()), Some(period));
+    }
+    assert_eq!(Period::from_minutes(7), None);
+}
+
+// hands back a canned body and keeps every request it was given
+#[derive(Debug, Default, Clone)]
+struct Recorder {
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl Transport for Recorder {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        self.requests.lock().unwrap().push(request.clone());
+        Ok(HttpResponse { status: 200, body: include_str!("fixtures/orders.json").to_string() })
+    }
+}
+
+#[test]
+fn client_signs_what_it_sends() {
+    let recorder = Recorder::default();
+    let client = GraviexClient::new("key", "secret")
+        .with_base_url("http://127.0.0.1:1/")
+        .with_transport(recorder.clone());
+    client.orders_get(&OrdersQuery::new().market("ethbtc")).unwrap();
+
+    let request = recorder.requests.lock().unwrap().remove(0);
+    assert_eq!(request.method, Method::Get);
+    assert_eq!(request.url, "http://127.0.0.1:1/webapi/v3/orders.json");
+
+    let keys: Vec<&str> = request.params.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, ["access_key", "market", "tonce", "signature"]);
+
+    let mut signed = request.params.clone();
+    let (_, signature) = signed.pop().unwrap();
+    assert_eq!(sign("secret", "GET", "/webapi/v3/orders.json", &mut signed), signature);
+}
+
+#[test]
+fn encoded_values_verify_on_the_server() {
+    let mut exchange = MockExchange::new();
+    exchange.add_member("key", "secret");
+    let server = MockServer::start(exchange).unwrap();
+    let client = server.client("key", "secret");
+
+    // json full of characters that have to be percent encoded on the wire,
+    // while the signature covers them unencoded
+    let mut settings = Settings {
+        markets_filter: Some("btc & eth=50%".to_string()),
+        ..Settings::default()
+    };
+    settings.extra.insert("fav-vrscbtc".to_string(), serde_json::Value::Bool(true));
+    let stored = client.settings_store(&settings).unwrap();
+    assert_eq!(stored.markets_filter.as_deref(), Some("btc & eth=50%"));
+    assert_eq!(client.settings_get().unwrap().extra["fav-vrscbtc"], serde_json::Value::Bool(true));
+}
@@ -0,0 +1,177 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use graviex_api_client::mock::{MockFailure, MockServer};
+use graviex_api_client::time::{format_timestamp, parse_timestamp};
+use graviex_api_client::*;
+
+mod common;
+use common::{dec, server};
+
+const ORDERS: &str = "/webapi/v3/orders.json";
+const HISTORY: &str = "/webapi/v3/orders/history.json";
+
+// minreq, except the first `refusals` order POSTs find nothing listening
+#[derive(Debug, Clone)]
+struct Unreachable {
+    refusals: Arc<Mutex<usize>>,
+}
+
+impl Transport for Unreachable {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        let mut refusals = self.refusals.lock().unwrap();
+        if request.method == Method::Post && request.url.ends_with(ORDERS) && *refusals > 0 {
+            *refusals -= 1;
+            return Err(minreq::Error::IoError(io::ErrorKind::ConnectionRefused.into()).into());
+        }
+        MinreqTransport.send(request)
+    }
+}
+
+fn client(server: &MockServer, key: &str, resubmits: u32) -> GraviexClient {
+    server
+        .client(key, "secret")
+        .with_retry(RetryPolicy::none())
+        .with_placement_policy(PlacementPolicy::default().resubmits(resubmits).settle(Duration::ZERO))
+}
+
+fn order() -> NewOrder {
+    NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"))
+}
+
+fn all_orders(client: &GraviexClient) -> Vec<Order> {
+    client.orders_history(&OrdersHistoryQuery::new().market("ethbtc")).unwrap()
+}
+
+#[test]
+fn places_normally() {
+    let server = server();
+    let alice = client(&server, "alice", 0);
+    match alice.place_order_safely(&order()) {
+        Placement::Placed(placed) => assert_eq!(placed.state, "wait"),
+        other => panic!("order was not placed: {:?}", other),
+    }
+    assert_eq!(all_orders(&alice).len(), 1);
+}
+
+#[test]
+fn finds_an_order_whose_response_was_lost() {
+    let server = server();
+    server.exchange().fail_next("POST", ORDERS, MockFailure::LostResponse);
+    let alice = client(&server, "alice", 0);
+
+    match alice.place_order_safely(&order()) {
+        Placement::Placed(placed) => assert_eq!(placed.price, dec("0.05")),
+        other => panic!("lost order was not found: {:?}", other),
+    }
+    // found, not sent twice
+    assert_eq!(all_orders(&alice).len(), 1);
+}
+
+#[test]
+fn finds_a_lost_order_that_already_filled() {
+    let server = server();
+    let bob = client(&server, "bob", 0);
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec("0.05"))).unwrap();
+
+    server.exchange().fail_next("POST", ORDERS, MockFailure::LostResponse);
+    let alice = client(&server, "alice", 0);
+    match alice.place_order_safely(&order()) {
+        Placement::Placed(placed) => assert_eq!(placed.state, "done"),
+        other => panic!("filled order was not found: {:?}", other),
+    }
+    assert_eq!(all_orders(&alice).len(), 1);
+}
+
+#[test]
+fn never_resends_what_may_have_landed() {
+    let server = server();
+    server.exchange().fail_next("POST", ORDERS, MockFailure::Unavailable);
+    let alice = client(&server, "alice", 3);
+
+    // nothing landed, but nothing says so either
+    assert!(matches!(alice.place_order_safely(&order()), Placement::Unknown(_)));
+    assert!(all_orders(&alice).is_empty());
+}
+
+#[test]
+fn resends_what_never_left() {
+    let server = server();
+    let unreachable = |refusals| Unreachable { refusals: Arc::new(Mutex::new(refusals)) };
+
+    let alice = client(&server, "alice", 1).with_transport(unreachable(1));
+    assert!(matches!(alice.place_order_safely(&order()), Placement::Placed(_)));
+    assert_eq!(all_orders(&alice).len(), 1);
+
+    let alice = client(&server, "alice", 2).with_transport(unreachable(3));
+    match alice.place_order_safely(&order()) {
+        Placement::NotPlaced(e) => assert!(matches!(e, GraviexError::Transport(_))),
+        other => panic!("expected the order not to be placed, got {:?}", other),
+    }
+    assert_eq!(all_orders(&alice).len(), 1);
+}
+
+#[test]
+fn unknown_when_the_lookup_fails() {
+    let server = server();
+    server.exchange().fail_next("POST", ORDERS, MockFailure::LostResponse);
+    server.exchange().fail_next("GET", HISTORY, MockFailure::Unavailable);
+    let alice = client(&server, "alice", 0);
+
+    // holding the order's own failure, not the lookup's
+    match alice.place_order_safely(&order()) {
+        Placement::Unknown(GraviexError::Status { status, body }) => assert_eq!((status, body.as_str()), (503, "")),
+        other => panic!("expected an unknown outcome, got {:?}", other),
+    }
+    // it did land
+    assert_eq!(all_orders(&alice).len(), 1);
+}
+
+#[test]
+fn ignores_older_lookalikes() {
+    let server = server();
+    let alice = client(&server, "alice", 0);
+    server.exchange().set_time(time::now() - 600);
+    alice.orders_post(&order()).unwrap();
+    server.exchange().set_time(time::now());
+
+    server.exchange().fail_next("POST", ORDERS, MockFailure::Unavailable);
+    assert!(matches!(alice.place_order_safely(&order()), Placement::Unknown(_)));
+}
+
+#[test]
+fn ignores_lookalikes_placed_just_before() {
+    let server = server();
+    let alice = client(&server, "alice", 0);
+    let earlier = alice.orders_post(&order()).unwrap();
+
+    server.exchange().fail_next("POST", ORDERS, MockFailure::Unavailable);
+    match alice.place_order_safely(&order()) {
+        Placement::Unknown(e) => assert!(matches!(e, GraviexError::Status { status: 503, .. })),
+        other => panic!("expected an unknown outcome, got {:?}", other),
+    }
+    assert_eq!(all_orders(&alice).iter().map(|order| order.id).collect::<Vec<_>>(), [earlier.id]);
+}
+
+#[test]
+fn refusals_are_not_placed() {
+    let server = server();
+    let alice = client(&server, "alice", 3);
+    let too_big = NewOrder::limit("ethbtc", Side::Sell, dec("100"), dec("0.05"));
+    match alice.place_order_safely(&too_big) {
+        Placement::NotPlaced(GraviexError::Api { code, .. }) => assert_eq!(code, 2002),
+        other => panic!("expected the order to be refused, got {:?}", other),
+    }
+}
+
+#[test]
+fn parses_created_at() {
+    assert_eq!(parse_timestamp("2014-04-18T02:02:33Z"), Some(1_397_786_553));
+    assert_eq!(parse_timestamp("2014-04-18T10:02:33+08:00"), Some(1_397_786_553));
+    assert_eq!(parse_timestamp("2021-07-12 21:31:08"), Some(1_626_125_468));
+    assert_eq!(parse_timestamp("yesterday"), None);
+    for timestamp in [0, 951_782_400, 1_626_125_887, 4_102_444_800] {
+        assert_eq!(parse_timestamp(&format_timestamp(timestamp)), Some(timestamp));
+    }
+}
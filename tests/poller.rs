@@ -0,0 +1,162 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use graviex_api_client::mock::{MockFailure, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::{dec, server};
+
+// minreq, counting the requests that go out by url
+#[derive(Debug, Clone, Default)]
+struct Counting {
+    urls: Arc<Mutex<Vec<String>>>,
+}
+
+impl Counting {
+    fn count(&self, endpoint: &str) -> usize {
+        self.urls.lock().unwrap().iter().filter(|url| url.ends_with(endpoint)).count()
+    }
+}
+
+impl Transport for Counting {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        self.urls.lock().unwrap().push(request.url.clone());
+        MinreqTransport.send(request)
+    }
+}
+
+fn poller(server: &MockServer, transport: &Counting) -> Poller {
+    let client = server.client("alice", "secret").with_transport(transport.clone()).with_retry(RetryPolicy::none());
+    Poller::new(Arc::new(client))
+}
+
+fn trade(server: &MockServer, price: &str) {
+    let alice = server.client("alice", "secret");
+    let bob = server.client("bob", "secret");
+    alice.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec(price))).unwrap();
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec(price))).unwrap();
+}
+
+#[test]
+fn shares_requests_and_sends_only_changes() {
+    let server = server();
+    let transport = Counting::default();
+    let mut poller = poller(&server, &transport);
+    let eth = poller.channel(Topic::Ticker("ethbtc".to_string()), Duration::ZERO);
+    let gio = poller.channel(Topic::Ticker("giobtc".to_string()), Duration::ZERO);
+
+    poller.poll_once();
+    assert_eq!(transport.count("/tickers.json"), 1);
+    assert!(eth.try_recv().is_ok());
+    assert!(gio.try_recv().is_ok());
+
+    poller.poll_once();
+    assert_eq!(transport.count("/tickers.json"), 2);
+    assert!(eth.try_recv().is_err());
+
+    trade(&server, "0.05");
+    poller.poll_once();
+    match eth.try_recv().unwrap() {
+        MarketEvent::Ticker { market, ticker } => {
+            assert_eq!(market, "ethbtc");
+            assert_eq!(ticker.last, dec("0.05"));
+        }
+        other => panic!("expected a ticker, got {:?}", other),
+    }
+    assert!(gio.try_recv().is_err());
+}
+
+#[test]
+fn follows_trades_depth_and_klines() {
+    let server = server();
+    let transport = Counting::default();
+    let mut poller = poller(&server, &transport);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    for topic in [
+        Topic::Trades("ethbtc".to_string()),
+        Topic::Depth("ethbtc".to_string()),
+        Topic::Kline("ethbtc".to_string(), Period::Minute1),
+    ] {
+        let events = Arc::clone(&events);
+        poller.subscribe(topic, Duration::ZERO, move |event| events.lock().unwrap().push(event.clone()));
+    }
+
+    trade(&server, "0.05");
+    trade(&server, "0.06");
+    server.client("alice", "secret").orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("2"), dec("0.07"))).unwrap();
+    poller.poll_once();
+    let seen = std::mem::take(&mut *events.lock().unwrap());
+    assert_eq!(seen.len(), 3);
+    for event in &seen {
+        match event {
+            MarketEvent::Trades { trades, .. } => {
+                let prices: Vec<Decimal> = trades.iter().map(|trade| trade.price).collect();
+                assert_eq!(prices, [dec("0.05"), dec("0.06")]);
+            }
+            MarketEvent::Depth { book, .. } => assert_eq!(book.best_ask().unwrap().price, dec("0.07")),
+            MarketEvent::Kline { candles, .. } => assert_eq!(candles.last().unwrap().close, dec("0.06")),
+            MarketEvent::Ticker { .. } => panic!("no ticker was subscribed to"),
+        }
+    }
+
+    // only the new trade, and the bar it changed
+    trade(&server, "0.065");
+    poller.poll_once();
+    let seen = std::mem::take(&mut *events.lock().unwrap());
+    assert_eq!(seen.len(), 2);
+    match &seen[0] {
+        MarketEvent::Trades { trades, .. } => assert_eq!(trades.len(), 1),
+        other => panic!("expected trades, got {:?}", other),
+    }
+    match &seen[1] {
+        MarketEvent::Kline { candles, .. } => assert_eq!(candles.len(), 1),
+        other => panic!("expected k lines, got {:?}", other),
+    }
+}
+
+#[test]
+fn backs_off_failing_requests() {
+    let server = server();
+    let transport = Counting::default();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let errors_seen = Arc::clone(&errors);
+    let mut poller = poller(&server, &transport)
+        .backoff(RetryPolicy::default().base_delay(Duration::from_millis(100)))
+        .on_error(move |topic, _| errors_seen.lock().unwrap().push(topic.clone()));
+    let depth = poller.channel(Topic::Depth("ethbtc".to_string()), Duration::ZERO);
+    let ticker = poller.channel(Topic::Ticker("ethbtc".to_string()), Duration::ZERO);
+
+    server.exchange().fail_next("GET", "/webapi/v3/depth.json", MockFailure::Unavailable);
+    poller.poll_once();
+    assert_eq!(*errors.lock().unwrap(), [Topic::Depth("ethbtc".to_string())]);
+    assert!(ticker.try_recv().is_ok());
+
+    // backed off, while the ticker carries on
+    poller.poll_once();
+    assert_eq!(transport.count("/depth.json"), 1);
+    assert_eq!(transport.count("/tickers.json"), 2);
+
+    thread::sleep(Duration::from_millis(120));
+    server.client("alice", "secret").orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.07"))).unwrap();
+    poller.poll_once();
+    assert_eq!(transport.count("/depth.json"), 2);
+    assert!(depth.try_recv().is_ok());
+}
+
+#[test]
+fn polls_on_its_own_thread() {
+    let server = server();
+    let mut poller = poller(&server, &Counting::default());
+    let trades = poller.channel(Topic::Trades("ethbtc".to_string()), Duration::from_millis(10));
+    let handle = poller.start();
+
+    trade(&server, "0.05");
+    match trades.recv_timeout(Duration::from_secs(5)).unwrap() {
+        MarketEvent::Trades { trades, .. } => assert_eq!(trades[0].price, dec("0.05")),
+        other => panic!("expected trades, got {:?}", other),
+    }
+    handle.stop();
+    assert!(trades.recv().is_err());
+}
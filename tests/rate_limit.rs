@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use graviex_api_client::mock::{MockExchange, MockServer};
+use graviex_api_client::*;
+
+fn server() -> MockServer {
+    let mut exchange = MockExchange::new();
+    exchange.add_member("key", "secret");
+    MockServer::start(exchange).unwrap()
+}
+
+#[test]
+fn errors_when_over_budget() {
+    let server = server();
+    let limiter = RateLimiter::new(Budget::per_minute(2), Budget::per_minute(1)).policy(LimitPolicy::Error);
+    let client = server.client("key", "secret").with_rate_limiter(Arc::new(limiter));
+
+    assert!(client.markets().is_ok());
+    assert!(client.tickers().is_ok());
+    match client.timestamp() {
+        Err(GraviexError::RateLimited { retry_after }) => assert!(retry_after > Duration::from_secs(20)),
+        other => panic!("expected to be rate limited, got {:?}", other),
+    }
+
+    // signed requests have a budget of their own
+    assert!(client.me().is_ok());
+    assert!(matches!(client.me(), Err(GraviexError::RateLimited { .. })));
+}
+
+#[test]
+fn endpoint_budgets_stack_on_class_budgets() {
+    let limiter = RateLimiter::new(Budget::per_second(100), Budget::per_second(100))
+        .endpoint("/webapi/v3/orders.json", Budget::per_minute(1));
+
+    assert!(limiter.try_acquire(Access::Signed, "/webapi/v3/orders.json").is_ok());
+    assert!(limiter.try_acquire(Access::Signed, "/webapi/v3/orders.json").is_err());
+    assert!(limiter.try_acquire(Access::Signed, "/webapi/v3/members/me.json").is_ok());
+}
+
+#[test]
+fn refused_requests_spend_nothing() {
+    let limiter = RateLimiter::new(Budget::per_minute(1), Budget::per_minute(10))
+        .endpoint("/webapi/v3/orders.json", Budget::per_minute(1));
+
+    assert!(limiter.try_acquire(Access::Public, "/webapi/v3/orders.json").is_ok());
+    // the endpoint budget is empty, so the signed budget keeps its token
+    for _ in 0..5 {
+        assert!(limiter.try_acquire(Access::Signed, "/webapi/v3/orders.json").is_err());
+    }
+    for _ in 0..10 {
+        assert!(limiter.try_acquire(Access::Signed, "/webapi/v3/order.json").is_ok());
+    }
+}
+
+#[test]
+fn blocks_across_threads() {
+    // 5 at once, then one every 20ms
+    let limiter = Arc::new(RateLimiter::new(Budget::new(5, Duration::from_millis(100)), Budget::per_second(1)));
+    let started = Instant::now();
+    let threads: Vec<_> = (0..3)
+        .map(|_| {
+            let limiter = Arc::clone(&limiter);
+            thread::spawn(move || {
+                for _ in 0..5 {
+                    limiter.acquire(Access::Public, "/webapi/v3/tickers.json").unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    // 15 requests, 5 free and 10 paced at 20ms
+    assert!(started.elapsed() >= Duration::from_millis(190));
+}
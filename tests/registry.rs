@@ -0,0 +1,239 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use graviex_api_client::mock::{MockFailure, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::{dec, server};
+
+fn client(server: &MockServer, key: &str, registry: &Arc<Mutex<OrderRegistry>>) -> GraviexClient {
+    server
+        .client(key, "secret")
+        .with_retry(RetryPolicy::none())
+        .with_placement_policy(PlacementPolicy::default().resubmits(0).settle(Duration::ZERO))
+        .with_registry(Arc::clone(registry))
+}
+
+fn shared(registry: OrderRegistry) -> Arc<Mutex<OrderRegistry>> {
+    Arc::new(Mutex::new(registry))
+}
+
+// a registry file of our own, removed when done
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> TempFile {
+        TempFile(env::temp_dir().join(format!("graviex-{}-{}.json", name, process::id())))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn sell(volume: &str) -> NewOrder {
+    NewOrder::limit("ethbtc", Side::Sell, dec(volume), dec("0.05"))
+}
+
+#[test]
+fn tracks_orders_from_placement_to_fill() {
+    let server = server();
+    let registry = shared(OrderRegistry::new());
+    let alice = client(&server, "alice", &registry);
+
+    let placed = match alice.orders_post_tracked("grid-1", &sell("2")).unwrap() {
+        Placement::Placed(placed) => placed,
+        other => panic!("order was not placed: {:?}", other),
+    };
+    {
+        let registry = registry.lock().unwrap();
+        let tracked = registry.get("grid-1").unwrap();
+        assert_eq!(tracked.exchange_id, Some(placed.id));
+        assert_eq!(tracked.state, TrackedState::Wait);
+        assert_eq!(registry.by_exchange_id(placed.id).unwrap().client_id, "grid-1");
+    }
+
+    // bob fills half, which alice only learns by asking
+    let bob = server.client("bob", "secret");
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec("0.05"))).unwrap();
+    assert_eq!(registry.lock().unwrap().get("grid-1").unwrap().executed_volume, Decimal::ZERO);
+    alice.orders_get(&OrdersQuery::new().market("ethbtc")).unwrap();
+    assert_eq!(registry.lock().unwrap().get("grid-1").unwrap().executed_volume, dec("1"));
+
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("1"), dec("0.05"))).unwrap();
+    alice.order(placed.id).unwrap();
+
+    let registry = registry.lock().unwrap();
+    let tracked = registry.get("grid-1").unwrap();
+    assert_eq!(tracked.state, TrackedState::Done);
+    let states: Vec<TrackedState> = tracked.transitions.iter().map(|t| t.state).collect();
+    assert_eq!(states, [TrackedState::Pending, TrackedState::Wait, TrackedState::Done]);
+    assert_eq!(registry.open_orders().count(), 0);
+}
+
+#[test]
+fn tracks_cancellations() {
+    let server = server();
+    let registry = shared(OrderRegistry::new());
+    let alice = client(&server, "alice", &registry);
+
+    alice.orders_post_tracked("a", &sell("1")).unwrap();
+    alice.orders_post_tracked("b", &sell("1")).unwrap();
+    let a = registry.lock().unwrap().get("a").unwrap().exchange_id.unwrap();
+    alice.order_delete(a).unwrap();
+    alice.orders_clear(Side::Sell).unwrap();
+
+    let registry = registry.lock().unwrap();
+    assert_eq!(registry.get("a").unwrap().state, TrackedState::Cancel);
+    assert_eq!(registry.get("b").unwrap().state, TrackedState::Cancel);
+}
+
+#[test]
+fn maps_multi_orders_by_position() {
+    let server = server();
+    let registry = shared(OrderRegistry::new());
+    let alice = client(&server, "alice", &registry);
+    let orders = MultiOrder::new("ethbtc")
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.06")))
+        .push(OrderLeg::limit(Side::Buy, dec("1"), dec("0.04")));
+
+    let placed = alice.orders_multi_tracked(&["ask", "bid"], &orders).unwrap();
+
+    let registry = registry.lock().unwrap();
+    assert_eq!(registry.get("ask").unwrap().exchange_id, Some(placed[0].id));
+    assert_eq!(registry.get("bid").unwrap().exchange_id, Some(placed[1].id));
+    assert_eq!(registry.get("bid").unwrap().side, Side::Buy);
+    assert_eq!(registry.open_orders().count(), 2);
+}
+
+#[test]
+fn refuses_duplicate_client_ids() {
+    let server = server();
+    let registry = shared(OrderRegistry::new());
+    let alice = client(&server, "alice", &registry);
+
+    alice.orders_post_tracked("dup", &sell("1")).unwrap();
+    assert!(matches!(
+        alice.orders_post_tracked("dup", &sell("1")),
+        Err(GraviexError::Registry(RegistryError::DuplicateId(_)))
+    ));
+    let orders = MultiOrder::new("ethbtc")
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.06")))
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.07")));
+    assert!(alice.orders_multi_tracked(&["fresh", "dup"], &orders).is_err());
+    assert!(alice.orders_multi_tracked(&["fresh"], &orders).is_err());
+    assert!(matches!(
+        alice.orders_multi_tracked(&["twin", "twin"], &orders),
+        Err(GraviexError::Registry(RegistryError::DuplicateId(_)))
+    ));
+
+    // nothing was sent for the refused calls
+    assert!(registry.lock().unwrap().get("fresh").is_none());
+    assert!(registry.lock().unwrap().get("twin").is_none());
+    assert_eq!(alice.orders_get(&OrdersQuery::new()).unwrap().len(), 1);
+}
+
+#[test]
+fn rejections_and_unknown_outcomes() {
+    let server = server();
+    let registry = shared(OrderRegistry::new());
+    let alice = client(&server, "alice", &registry);
+
+    assert!(matches!(alice.orders_post_tracked("too-big", &sell("100")).unwrap(), Placement::NotPlaced(_)));
+    server.exchange().fail_next("POST", "/webapi/v3/orders.json", MockFailure::Unavailable);
+    assert!(matches!(alice.orders_post_tracked("lost", &sell("1")).unwrap(), Placement::Unknown(_)));
+
+    let registry = registry.lock().unwrap();
+    assert_eq!(registry.get("too-big").unwrap().state, TrackedState::Rejected);
+    // it may yet show up, so it isn't written off
+    assert_eq!(registry.get("lost").unwrap().state, TrackedState::Pending);
+}
+
+#[test]
+fn failed_lookups_leave_orders_pending() {
+    let server = server();
+    let registry = shared(OrderRegistry::new());
+    let alice = client(&server, "alice", &registry);
+
+    server.exchange().fail_next("POST", "/webapi/v3/orders.json", MockFailure::LostResponse);
+    server.exchange().fail_next("GET", "/webapi/v3/orders/history.json", MockFailure::Unavailable);
+    assert!(matches!(alice.orders_post_tracked("unseen", &sell("1")).unwrap(), Placement::Unknown(_)));
+
+    // the lookup failing says nothing about the order, which did land
+    assert_eq!(registry.lock().unwrap().get("unseen").unwrap().state, TrackedState::Pending);
+    assert_eq!(alice.orders_get(&OrdersQuery::new()).unwrap().len(), 1);
+}
+
+#[test]
+fn failed_saves_leave_the_id_free() {
+    let file = TempFile::new("no-such-dir");
+    let mut registry = OrderRegistry::open(file.0.join("orders.json")).unwrap();
+
+    for _ in 0..2 {
+        assert!(matches!(registry.register("retry", &sell("1")), Err(RegistryError::Io(_))));
+        assert!(registry.get("retry").is_none());
+    }
+}
+
+#[test]
+fn failed_multi_saves_send_nothing() {
+    let file = TempFile::new("no-such-dir");
+    let server = server();
+    let registry = shared(OrderRegistry::open(file.0.join("orders.json")).unwrap());
+    let alice = client(&server, "alice", &registry);
+    let orders = MultiOrder::new("ethbtc")
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.06")))
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.07")));
+
+    assert!(matches!(
+        alice.orders_multi_tracked(&["ask-1", "ask-2"], &orders),
+        Err(GraviexError::Registry(RegistryError::Io(_)))
+    ));
+    assert!(registry.lock().unwrap().is_empty());
+    assert!(alice.orders_get(&OrdersQuery::new()).unwrap().is_empty());
+}
+
+#[test]
+fn survives_restarts() {
+    let file = TempFile::new("survives-restarts");
+    let server = server();
+    let id = {
+        let registry = shared(OrderRegistry::open(&file.0).unwrap());
+        let alice = client(&server, "alice", &registry);
+        alice.orders_post_tracked("cancelled", &sell("1")).unwrap();
+        alice.orders_post_tracked("resting", &sell("1")).unwrap();
+        let id = registry.lock().unwrap().get("cancelled").unwrap().exchange_id;
+        id.unwrap()
+    };
+
+    let registry = shared(OrderRegistry::open(&file.0).unwrap());
+    assert_eq!(registry.lock().unwrap().len(), 2);
+    let alice = client(&server, "alice", &registry);
+    alice.order_delete(id).unwrap();
+    {
+        let mut registry = registry.lock().unwrap();
+        assert_eq!(registry.by_exchange_id(id).unwrap().state, TrackedState::Cancel);
+        registry.prune().unwrap();
+    }
+
+    let registry = OrderRegistry::open(&file.0).unwrap();
+    assert!(registry.get("cancelled").is_none());
+    assert_eq!(registry.get("resting").unwrap().state, TrackedState::Wait);
+}
+
+#[test]
+fn needs_a_registry() {
+    let server = server();
+    let alice = server.client("alice", "secret");
+    assert!(matches!(
+        alice.orders_post_tracked("x", &sell("1")),
+        Err(GraviexError::Registry(RegistryError::NoRegistry))
+    ));
+}
@@ -0,0 +1,112 @@
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+// Monday 2021-07-12 21:31:05 UTC
+const AT: usize = 1_626_125_465;
+
+fn trade(tid: usize, date: usize, price: &str, amount: &str) -> SimpleTrade {
+    SimpleTrade { tid, price: dec(price), amount: dec(amount), date, side: "buy".to_string() }
+}
+
+fn candle(timestamp: usize, prices: [&str; 4], volume: &str) -> Candle {
+    let [open, high, low, close] = prices.map(dec);
+    Candle { timestamp, open, high, low, close, volume: dec(volume) }
+}
+
+#[test]
+fn lines_bars_up_in_their_time_zone() {
+    assert_eq!(Timeframe::minutes(3).start_of(AT), 1_626_125_400);
+    assert_eq!(Timeframe::hours(2).start_of(AT), 1_626_120_000); // 20:00
+    assert_eq!(Timeframe::days(1).start_of(AT), 1_626_048_000);
+    assert_eq!(Timeframe::weeks(1).start_of(AT), 1_626_048_000); // that Monday
+
+    // already Tuesday in UTC+8, but the week still started on Monday there
+    let east = 8 * 3600;
+    assert_eq!(Timeframe::days(1).utc_offset(east).start_of(AT), 1_626_105_600);
+    assert_eq!(Timeframe::weeks(1).utc_offset(east).start_of(AT), 1_626_019_200);
+    // still Monday in UTC-5
+    assert_eq!(Timeframe::days(1).utc_offset(-5 * 3600).start_of(AT), 1_626_066_000);
+
+    assert_eq!(Timeframe::from(Period::Hour4).start_of(AT), Timeframe::hours(4).start_of(AT));
+}
+
+#[test]
+fn builds_bars_from_trades() {
+    let trades: Vec<Trade> = serde_json::from_str(include_str!("fixtures/trades.json")).unwrap();
+    let bars = bars_from_trades(&trades, Timeframe::minutes(3));
+    assert_eq!(bars, [Bar {
+        timestamp: 1_626_125_400,
+        open: dec("0.0614"),
+        high: dec("0.0614"),
+        low: dec("0.0612"),
+        close: dec("0.0612"),
+        volume: dec("0.35"),
+        quote_volume: dec("0.02147"),
+    }]);
+
+    // same second, told apart by id; quiet bars carry the close
+    let trades = [
+        trade(4, AT + 400, "0.07", "1"),
+        trade(2, AT, "0.05", "2"),
+        trade(3, AT, "0.055", "1"),
+        trade(1, AT - 10, "0.06", "1"),
+    ];
+    let bars = bars_from_trades(&trades, Timeframe::minutes(3));
+    let summary: Vec<(usize, Decimal, Decimal, Decimal, Decimal)> =
+        bars.iter().map(|bar| (bar.timestamp, bar.open, bar.close, bar.volume, bar.quote_volume)).collect();
+    assert_eq!(summary, [
+        (1_626_125_400, dec("0.06"), dec("0.055"), dec("4"), dec("0.215")),
+        (1_626_125_580, dec("0.055"), dec("0.055"), dec("0"), dec("0")),
+        (1_626_125_760, dec("0.07"), dec("0.07"), dec("1"), dec("0.07")),
+    ]);
+}
+
+#[test]
+fn resamples_kline_candles() {
+    let start = 1_626_048_000; // midnight
+    let candles = [
+        candle(start, ["1", "3", "1", "2"], "1"),
+        candle(start + 3600, ["2", "4", "2", "4"], "2"),
+        candle(start + 3 * 3600, ["5", "6", "3", "3"], "3"),
+    ];
+    let bars = resample(&candles, Period::Hour1, Timeframe::hours(2)).unwrap();
+    assert_eq!(bars.len(), 2);
+    assert_eq!(
+        (bars[0].open, bars[0].high, bars[0].low, bars[0].close, bars[0].volume),
+        (dec("1"), dec("4"), dec("1"), dec("4"), dec("3"))
+    );
+    assert_eq!(bars[0].quote_volume, dec("2") + dec("2") * dec("10") / dec("3"));
+    assert_eq!((bars[1].timestamp, bars[1].open, bars[1].close), (start + 7200, dec("5"), dec("3")));
+
+    // the whole day, and a gap filled in
+    let bars = resample(&candles, Period::Hour1, Timeframe::hours(1)).unwrap();
+    assert_eq!(bars.len(), 4);
+    assert_eq!((bars[2].open, bars[2].close, bars[2].volume), (dec("4"), dec("4"), dec("0")));
+    assert_eq!(resample(&candles, Period::Hour1, Timeframe::days(1)).unwrap()[0].volume, dec("6"));
+
+    let e = resample(&candles, Period::Hour1, Timeframe::minutes(90)).unwrap_err();
+    assert_eq!(e.timestamp, start + 3600);
+    assert_eq!(e.to_string(), "the 60 minute bar at 1626051600 crosses into the next 5400 second bar");
+    assert!(resample(&candles, Period::Hour1, Timeframe::minutes(30)).is_err());
+}
+
+#[test]
+fn resamples_as_data_comes_in() {
+    let mut resampler = Resampler::new(Timeframe::minutes(3));
+    assert!(resampler.push_trade(&trade(1, AT, "0.05", "1")).is_empty());
+    assert!(resampler.push_trade(&trade(2, AT + 60, "0.06", "1")).is_empty());
+    assert_eq!(resampler.current().unwrap().high, dec("0.06"));
+
+    let finished = resampler.push_trade(&trade(3, AT + 200, "0.04", "1"));
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].close, dec("0.06"));
+    // too late for a finished bar
+    assert!(resampler.push_trade(&trade(4, AT, "1", "1")).is_empty());
+    assert_eq!(resampler.current().unwrap().volume, dec("1"));
+
+    let last: Candle = resampler.finish().unwrap().into();
+    assert_eq!(last.close, dec("0.04"));
+    assert!(resampler.current().is_none());
+}
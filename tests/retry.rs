@@ -0,0 +1,124 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use graviex_api_client::*;
+
+// fails with each of `failures` in turn, then answers with `body`
+#[derive(Debug, Clone)]
+struct Flaky {
+    failures: Arc<Mutex<Vec<Option<u16>>>>, // None is a timeout, Some is a status
+    body: &'static str,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl Flaky {
+    fn new(failures: &[Option<u16>], body: &'static str) -> Flaky {
+        let mut failures = failures.to_vec();
+        failures.reverse();
+        Flaky { failures: Arc::new(Mutex::new(failures)), body, requests: Arc::default() }
+    }
+
+    fn attempts(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+impl Transport for Flaky {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, GraviexError> {
+        self.requests.lock().unwrap().push(request.clone());
+        match self.failures.lock().unwrap().pop() {
+            Some(None) => Err(minreq::Error::IoError(io::Error::new(io::ErrorKind::TimedOut, "timed out")).into()),
+            Some(Some(status)) => Ok(HttpResponse { status, body: "<html>oops</html>".to_string() }),
+            None => Ok(HttpResponse { status: 200, body: self.body.to_string() }),
+        }
+    }
+}
+
+fn client(transport: &Flaky, retries: u32) -> GraviexClient {
+    let policy = RetryPolicy::new(retries).base_delay(Duration::from_millis(1));
+    GraviexClient::new("retry-key", "secret")
+        .with_transport(transport.clone())
+        .with_retry(policy)
+}
+
+#[test]
+fn retries_public_gets() {
+    let flaky = Flaky::new(&[None, Some(502)], include_str!("fixtures/depth.json"));
+    let depth = client(&flaky, 3).depth(&DepthQuery::new("ethbtc")).unwrap();
+    assert_eq!(depth.bids.len(), 3);
+    assert_eq!(flaky.attempts(), 3);
+}
+
+#[test]
+fn gives_up_after_max_retries() {
+    let flaky = Flaky::new(&[Some(503), Some(503), Some(503)], include_str!("fixtures/depth.json"));
+    match client(&flaky, 2).depth(&DepthQuery::new("ethbtc")) {
+        Err(GraviexError::Status { status, .. }) => assert_eq!(status, 503),
+        other => panic!("expected the last 503, got {:?}", other),
+    }
+    assert_eq!(flaky.attempts(), 3);
+}
+
+#[test]
+fn resigns_signed_gets() {
+    let flaky = Flaky::new(&[None, None], include_str!("fixtures/orders.json"));
+    client(&flaky, 3).orders_get(&OrdersQuery::new()).unwrap();
+
+    let requests = flaky.requests.lock().unwrap();
+    let tonces: Vec<&str> = requests
+        .iter()
+        .map(|r| r.params.iter().find(|(k, _)| *k == "tonce").unwrap().1.as_str())
+        .collect();
+    assert_eq!(tonces.len(), 3);
+    assert!(tonces[0] < tonces[1] && tonces[1] < tonces[2]);
+}
+
+#[test]
+fn never_retries_posts() {
+    let flaky = Flaky::new(&[None], include_str!("fixtures/order.json"));
+    let order = NewOrder::limit("ethbtc", Side::Sell, "1".parse().unwrap(), "0.07".parse().unwrap());
+    assert!(matches!(client(&flaky, 3).orders_post(&order), Err(GraviexError::Transport(_))));
+    assert_eq!(flaky.attempts(), 1);
+
+    let flaky = Flaky::new(&[Some(500)], include_str!("fixtures/withdraws.json"));
+    let withdraw = NewWithdraw::new("btc", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT", "0.1".parse().unwrap());
+    assert!(client(&flaky, 3).create_withdraw(&withdraw).is_err());
+    assert_eq!(flaky.attempts(), 1);
+}
+
+#[test]
+fn never_retries_address_generation() {
+    let flaky = Flaky::new(&[Some(502)], include_str!("fixtures/deposit_address.json"));
+    match client(&flaky, 3).gen_deposit_address("btc") {
+        Err(GraviexError::Status { status, .. }) => assert_eq!(status, 502),
+        other => panic!("expected the 502, got {:?}", other),
+    }
+    assert_eq!(flaky.attempts(), 1);
+
+    // looking the address up is still retried
+    let flaky = Flaky::new(&[Some(502)], include_str!("fixtures/deposit_address.json"));
+    client(&flaky, 3).deposit_address("btc").unwrap();
+    assert_eq!(flaky.attempts(), 2);
+}
+
+#[test]
+fn api_errors_are_final() {
+    let flaky = Flaky::new(&[Some(404)], include_str!("fixtures/ticker.json"));
+    assert!(client(&flaky, 3).ticker("nope").is_err());
+    assert_eq!(flaky.attempts(), 1);
+}
+
+#[test]
+fn backoff_grows_and_caps() {
+    let policy = RetryPolicy::default()
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_millis(1000));
+    assert_eq!(policy.backoff(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(400));
+    assert_eq!(policy.backoff(10), Duration::from_millis(1000));
+    assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(1000));
+    for retry in 0..5 {
+        assert!(policy.delay(retry) <= policy.backoff(retry));
+    }
+}
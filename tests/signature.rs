@@ -0,0 +1,84 @@
+// golden values were computed independently with python's hmac module
+use graviex_api_client::signature::*;
+
+const TONCE: &str = "1626125887000";
+
+#[test]
+fn payload_format() {
+    assert_eq!(
+        payload("GET", "/webapi/v3/orders.json", "access_key=key&market=ethbtc"),
+        "GET|/webapi/v3/orders.json|access_key=key&market=ethbtc"
+    );
+    assert_eq!(payload("POST", "/webapi/v3/orders/clear.json", ""), "POST|/webapi/v3/orders/clear.json|");
+}
+
+#[test]
+fn hmac_golden() {
+    assert_eq!(
+        hmac_hex("yyyy", "GET|/api/v2/markets|access_key=xxx&foo=bar&tonce=123456789"),
+        "6e777f7ad305208be022d293d1993325c732ac2263ef5141c0cd1ebcfdcb39ef"
+    );
+}
+
+#[test]
+fn sorts_params_by_key() {
+    let mut params = vec![
+        ("tonce", TONCE.to_string()),
+        ("state", "wait".to_string()),
+        ("market", "ethbtc".to_string()),
+        ("access_key", "key".to_string()),
+    ];
+    sort_params(&mut params);
+    assert_eq!(query_string(&params), format!("access_key=key&market=ethbtc&state=wait&tonce={}", TONCE));
+}
+
+#[test]
+fn sign_golden() {
+    let mut params = vec![
+        ("market", "ethbtc".to_string()),
+        ("state", "wait".to_string()),
+        ("tonce", TONCE.to_string()),
+        ("access_key", "key".to_string()),
+    ];
+    assert_eq!(
+        sign("secret", "GET", "/webapi/v3/orders.json", &mut params),
+        "792b890a4653ba6c28592f4f1c27eb3958de50f45ca944cfd5d0fe185626f22d"
+    );
+    // sign leaves the params sorted, ready to send
+    assert_eq!(params[0].0, "access_key");
+}
+
+#[test]
+fn sign_keeps_repeated_keys_in_order() {
+    let mut params = vec![
+        ("market", "ethbtc".to_string()),
+        ("orders[][side]", "sell".to_string()),
+        ("orders[][volume]", "1".to_string()),
+        ("orders[][price]", "0.07".to_string()),
+        ("orders[][side]", "buy".to_string()),
+        ("orders[][volume]", "2".to_string()),
+        ("orders[][price]", "0.01".to_string()),
+        ("tonce", TONCE.to_string()),
+        ("access_key", "key".to_string()),
+    ];
+    let signature = sign("secret", "POST", "/webapi/v3/orders/multi.json", &mut params);
+    assert_eq!(
+        query_string(&params),
+        format!(
+            "access_key=key&market=ethbtc&orders[][side]=sell&orders[][volume]=1&orders[][price]=0.07\
+             &orders[][side]=buy&orders[][volume]=2&orders[][price]=0.01&tonce={}",
+            TONCE
+        )
+    );
+    assert_eq!(signature, "9a583ffe3f22395022228ac29b81d4d51fd5c154cb28ab1051f80e4bda3acfda");
+}
+
+#[test]
+fn owned_keys_sign_the_same() {
+    let mut borrowed = vec![("tonce", TONCE.to_string()), ("access_key", "key".to_string())];
+    let mut owned: Vec<(String, String)> = borrowed.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+    assert_eq!(
+        sign("secret", "GET", "/webapi/v3/members/me.json", &mut borrowed),
+        sign("secret", "GET", "/webapi/v3/members/me.json", &mut owned)
+    );
+}
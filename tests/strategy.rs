@@ -0,0 +1,218 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use graviex_api_client::mock::{MockFailure, MockServer};
+use graviex_api_client::strategy::Strategy;
+use graviex_api_client::*;
+
+mod common;
+use common::{dec, server, trade};
+
+// an engine for alice doing everything on every step, unless told otherwise
+fn engine<S: Strategy>(server: &MockServer, strategy: S) -> Engine<S> {
+    let client = server.client("alice", "secret").with_retry(RetryPolicy::none());
+    Engine::new(Arc::new(client), strategy)
+        .refresh_account(Some(Duration::ZERO))
+        .refresh_orders(Some(Duration::ZERO))
+}
+
+// writes down every hook called
+#[derive(Default)]
+struct Recorder {
+    calls: Vec<String>,
+    last: Option<Decimal>, // from the context, after each ticker
+}
+
+impl Strategy for Recorder {
+    fn on_start(&mut self, _ctx: &mut dyn Context) {
+        self.calls.push("start".to_string());
+    }
+
+    fn on_ticker(&mut self, ctx: &mut dyn Context, market: &str, ticker: &Ticker) {
+        self.calls.push(format!("ticker {} {}", market, ticker.last));
+        self.last = ctx.ticker(market).map(|ticker| ticker.last);
+    }
+
+    fn on_depth(&mut self, _ctx: &mut dyn Context, book: &OrderBookState, changes: &[LevelChange]) {
+        self.calls.push(format!("depth {} {}", book.market, changes.len()));
+    }
+
+    fn on_trade(&mut self, _ctx: &mut dyn Context, market: &str, trade: &Trade) {
+        self.calls.push(format!("trade {} {}", market, trade.price));
+    }
+
+    fn on_error(&mut self, _ctx: &mut dyn Context, error: &GraviexError) {
+        self.calls.push(format!("error {}", error));
+    }
+
+    fn on_stop(&mut self, _ctx: &mut dyn Context) {
+        self.calls.push("stop".to_string());
+    }
+}
+
+#[test]
+fn market_data_reaches_the_hooks() {
+    let server = server();
+    trade(&server, "0.03");
+    let mut engine = engine(&server, Recorder::default())
+        .refresh_account(None)
+        .refresh_orders(None)
+        .subscribe(Topic::Ticker("ethbtc".to_string()), Duration::ZERO)
+        .subscribe(Topic::Trades("ethbtc".to_string()), Duration::ZERO)
+        .subscribe(Topic::Depth("ethbtc".to_string()), Duration::ZERO);
+
+    server.client("bob", "secret").orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("2"), dec("0.029"))).unwrap();
+    engine.step();
+    let calls = &engine.strategy().calls;
+    assert_eq!(calls[0], "start");
+    assert!(calls.contains(&"ticker ethbtc 0.03".to_string()));
+    assert!(calls.contains(&"trade ethbtc 0.03".to_string()));
+    assert!(calls.contains(&"depth ethbtc 1".to_string()));
+    assert_eq!(engine.strategy().last, Some(dec("0.03")));
+
+    // only what changed comes through again
+    trade(&server, "0.031");
+    engine.step();
+    let calls = &engine.strategy().calls;
+    assert_eq!(calls.iter().filter(|call| call.starts_with("trade")).count(), 2);
+    assert_eq!(calls.last().unwrap(), "trade ethbtc 0.031");
+    assert_eq!(calls.iter().filter(|call| *call == "start").count(), 1);
+}
+
+// sells 1 eth once started, canceling whatever is left once some of it fills
+#[derive(Default)]
+struct Maker {
+    placed: Option<usize>,
+    updates: Vec<(String, Decimal)>,
+    open: usize, // open orders in the context after the last update
+}
+
+impl Strategy for Maker {
+    fn on_start(&mut self, ctx: &mut dyn Context) {
+        let order = ctx.place(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.03"))).unwrap();
+        self.placed = Some(order.id);
+    }
+
+    fn on_order_update(&mut self, ctx: &mut dyn Context, order: &Order) {
+        self.updates.push((order.state.clone(), order.executed_volume));
+        if order.state == "wait" && !order.executed_volume.is_zero() {
+            ctx.cancel(order.id).unwrap();
+        }
+        self.open = ctx.open_orders().len();
+    }
+}
+
+#[test]
+fn follows_orders_through_fills_and_cancels() {
+    let server = server();
+    let mut engine = engine(&server, Maker::default());
+
+    engine.step();
+    // placed and seen open, nothing new about it yet
+    assert!(engine.strategy().placed.is_some());
+    assert!(engine.strategy().updates.is_empty());
+
+    let bob = server.client("bob", "secret");
+    bob.orders_post(&NewOrder::limit("ethbtc", Side::Buy, dec("0.4"), dec("0.03"))).unwrap();
+    engine.step();
+    assert_eq!(engine.strategy().updates, vec![("wait".to_string(), dec("0.4"))]);
+    // out of the context as soon as the cancel goes in
+    assert_eq!(engine.strategy().open, 0);
+
+    engine.step();
+    assert_eq!(engine.strategy().updates[1], ("cancel".to_string(), dec("0.4")));
+    assert_eq!(engine.strategy().open, 0);
+
+    // quiet once it's done
+    engine.step();
+    assert_eq!(engine.strategy().updates.len(), 2);
+}
+
+#[test]
+fn orders_placed_elsewhere_show_up() {
+    let server = server();
+    let mut engine = engine(&server, Maker::default()).subscribe(Topic::Ticker("giobtc".to_string()), Duration::ZERO);
+    engine.step();
+
+    let alice = server.client("alice", "secret");
+    let order = alice.orders_post(&NewOrder::limit("giobtc", Side::Buy, dec("10"), dec("0.00001"))).unwrap();
+    engine.step();
+    assert_eq!(engine.strategy().updates, vec![("wait".to_string(), Decimal::ZERO)]);
+    assert_eq!(engine.strategy().open, 2);
+
+    alice.order_delete(order.id).unwrap();
+    engine.step();
+    assert_eq!(engine.strategy().updates[1].0, "cancel");
+    assert_eq!(engine.strategy().open, 1);
+}
+
+// what alice holds, read through the context
+#[derive(Default)]
+struct Holdings {
+    btc: Vec<Decimal>,
+    errors: usize,
+}
+
+impl Strategy for Holdings {
+    fn on_account(&mut self, ctx: &mut dyn Context, _member: &Member) {
+        self.btc.extend(ctx.account("btc").map(|account| account.balance));
+    }
+
+    fn on_error(&mut self, _ctx: &mut dyn Context, _error: &GraviexError) {
+        self.errors += 1;
+    }
+}
+
+#[test]
+fn refreshes_accounts_and_reports_failures() {
+    let server = server();
+    let mut engine = engine(&server, Holdings::default()).refresh_orders(None);
+    engine.step();
+    assert_eq!(engine.strategy().btc, vec![dec("1")]);
+
+    server.exchange().credit("alice", "btc", dec("0.5"));
+    server.exchange().fail_next("GET", "/webapi/v3/members/me.json", MockFailure::Unavailable);
+    engine.step();
+    assert_eq!(engine.strategy().errors, 1);
+    assert_eq!(engine.strategy().btc, vec![dec("1")]);
+
+    engine.step();
+    assert_eq!(engine.strategy().btc, vec![dec("1"), dec("1.5")]);
+}
+
+// stops itself on the third tick of its timer
+#[derive(Default)]
+struct Countdown {
+    ticks: usize,
+    stopped: bool,
+}
+
+impl Strategy for Countdown {
+    fn on_timer(&mut self, ctx: &mut dyn Context) {
+        self.ticks += 1;
+        if self.ticks == 3 {
+            ctx.stop();
+        }
+    }
+
+    fn on_stop(&mut self, _ctx: &mut dyn Context) {
+        self.stopped = true;
+    }
+}
+
+#[test]
+fn runs_until_the_strategy_stops() {
+    let server = server();
+    let strategy = engine(&server, Countdown::default())
+        .refresh_orders(None)
+        .timer(Duration::from_millis(10))
+        .run();
+    assert_eq!(strategy.ticks, 3);
+    assert!(strategy.stopped);
+
+    // or until stopped from outside
+    let handle = engine(&server, Recorder::default()).refresh_orders(None).start();
+    let strategy = handle.stop().unwrap();
+    assert_eq!(strategy.calls.first().unwrap(), "start");
+    assert_eq!(strategy.calls.last().unwrap(), "stop");
+}
@@ -0,0 +1,147 @@
+#![cfg(feature = "stream")]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use graviex_api_client::mock::MockStreamServer;
+use graviex_api_client::*;
+use serde_json::{json, Value};
+
+mod common;
+use common::dec;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+macro_rules! fixture {
+    ($name:expr) => {
+        serde_json::from_str::<Value>(include_str!(concat!("fixtures/", $name))).unwrap()
+    };
+}
+
+fn server() -> MockStreamServer {
+    let server = MockStreamServer::start().unwrap();
+    server.add_member("alice", "secret");
+    server
+}
+
+fn client(server: &MockStreamServer) -> StreamClient {
+    StreamClient::new(&server.url()).with_timeout(WAIT)
+}
+
+#[test]
+fn authenticates_with_the_api_credentials() {
+    let server = server();
+    let api = GraviexClient::new("alice", "secret");
+    let connection = api.stream(&server.url()).subscribe(Stream::Orders).connect().unwrap();
+    assert!(server.wait_for_subscribers("order", 1, WAIT));
+    connection.close();
+
+    match client(&server).with_credentials("alice", "wrong").connect().unwrap_err() {
+        StreamError::Rejected(e) => assert_eq!(e.code(), Some(2005)),
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+    // private streams need credentials
+    match client(&server).subscribe(Stream::MyTrades).connect().unwrap_err() {
+        StreamError::Rejected(e) => assert_eq!(e.code(), Some(2001)),
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+}
+
+#[test]
+fn decodes_public_streams() {
+    let server = server();
+    let mut connection = client(&server)
+        .subscribe(Stream::Tickers)
+        .subscribe(Stream::Trades("ethbtc".to_string()))
+        .subscribe(Stream::OrderBook("ethbtc".to_string()))
+        .connect()
+        .unwrap();
+
+    assert_eq!(server.publish("global.tickers", &fixture!("tickers.json")), 1);
+    match connection.next_event().unwrap() {
+        StreamEvent::Tickers(tickers) => assert_eq!(tickers["ethbtc"].last, dec("0.0612")),
+        other => panic!("expected tickers, got {:?}", other),
+    }
+
+    server.publish("ethbtc.trades", &json!({ "trades": fixture!("trades_simple.json") }));
+    match connection.next_event().unwrap() {
+        StreamEvent::Trades { market, trades } => {
+            assert_eq!(market, "ethbtc");
+            assert_eq!(trades[0].tid, 554013);
+        }
+        other => panic!("expected trades, got {:?}", other),
+    }
+
+    server.publish("ethbtc.update", &json!({ "asks": [["0.0614", "0.5"]], "bids": [["0.061", "2"], ["0.0605", "1"]] }));
+    match connection.next_event().unwrap() {
+        StreamEvent::OrderBook { market, asks, bids } => {
+            assert_eq!(market, "ethbtc");
+            assert_eq!((asks[0].price, asks[0].vol), (dec("0.0614"), dec("0.5")));
+            assert_eq!(bids.len(), 2);
+        }
+        other => panic!("expected the book, got {:?}", other),
+    }
+
+    // nobody follows giobtc
+    assert_eq!(server.publish("giobtc.trades", &json!({ "trades": [] })), 0);
+}
+
+#[test]
+fn delivers_private_streams_to_their_owner_only() {
+    let server = server();
+    server.add_member("bob", "secret");
+    let mut alice = client(&server)
+        .with_credentials("alice", "secret")
+        .subscribe(Stream::Orders)
+        .subscribe(Stream::MyTrades)
+        .connect()
+        .unwrap();
+    let _bob = client(&server).with_credentials("bob", "secret").subscribe(Stream::Orders).connect().unwrap();
+
+    assert_eq!(server.publish_to("alice", "order", &fixture!("order.json")), 1);
+    match alice.next_event().unwrap() {
+        StreamEvent::Order(order) => {
+            assert_eq!(order.id, 81723);
+            assert_eq!(order.remaining_volume, dec("0.5"));
+        }
+        other => panic!("expected an order, got {:?}", other),
+    }
+
+    server.publish_to("alice", "trade", &fixture!("trades_my.json")[0]);
+    match alice.next_event().unwrap() {
+        StreamEvent::Trade(trade) => assert_eq!(trade.id, 554012),
+        other => panic!("expected a trade, got {:?}", other),
+    }
+}
+
+#[test]
+fn reconnects_and_resubscribes() {
+    let server = server();
+    let errors = Arc::new(Mutex::new(0));
+    let errors_seen = Arc::clone(&errors);
+    let handle = client(&server)
+        .with_credentials("alice", "secret")
+        .subscribe(Stream::Trades("ethbtc".to_string()))
+        .subscribe(Stream::Orders)
+        .with_reconnect(RetryPolicy::default().base_delay(Duration::from_millis(10)))
+        .on_error(move |_| *errors_seen.lock().unwrap() += 1)
+        .start();
+    let events = handle.events();
+
+    assert!(matches!(events.recv_timeout(WAIT).unwrap(), StreamEvent::Connected));
+    assert!(server.wait_for_subscribers("ethbtc.trades", 1, WAIT));
+
+    server.drop_connections();
+    assert!(matches!(events.recv_timeout(WAIT).unwrap(), StreamEvent::Connected));
+    assert!(server.wait_for_subscribers("order", 1, WAIT));
+    assert_eq!(server.connections(), 2);
+    assert_eq!(*errors.lock().unwrap(), 1);
+
+    server.publish("ethbtc.trades", &json!({ "trades": fixture!("trades_simple.json") }));
+    assert!(matches!(events.recv_timeout(WAIT).unwrap(), StreamEvent::Trades { .. }));
+
+    handle.stop();
+    // stopped for good, no more reconnects
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(server.connections(), 2);
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use graviex_api_client::error::decode;
+use graviex_api_client::mock::{MockExchange, MockMarket, MockServer};
+use graviex_api_client::*;
+
+mod common;
+use common::dec;
+
+// ethbtc as captured from graviex: 8 decimals, no lots
+fn ethbtc() -> MarketRules {
+    let ticker: Ticker = decode(include_str!("fixtures/ticker.json")).unwrap();
+    let market: Market = decode(include_str!("fixtures/market.json")).unwrap();
+    MarketRules::new(&ticker, &market)
+}
+
+fn server() -> MockServer {
+    let mut exchange = MockExchange::new();
+    exchange.add_market(MockMarket { api: false, ..MockMarket::new("vrsc", "btc") });
+    exchange.add_member("alice", "secret");
+    exchange.credit("alice", "eth", dec("10"));
+    MockServer::start(exchange).unwrap()
+}
+
+fn refusal(result: Result<Order, GraviexError>) -> ValidationError {
+    match result {
+        Err(GraviexError::Validation(e)) => e,
+        other => panic!("expected the order to be refused, got {:?}", other),
+    }
+}
+
+#[test]
+fn rules_from_ticker_and_market() {
+    let rules = ethbtc();
+    assert_eq!(rules.market, "ethbtc");
+    assert_eq!(rules.volume_fixed, 8);
+    assert_eq!(rules.base_min, dec("0.001"));
+    assert_eq!(rules.volume_lot, None);
+    assert!(rules.check(&NewOrder::limit("ethbtc", Side::Buy, dec("0.5"), dec("0.0612"))).is_ok());
+}
+
+#[test]
+fn refuses_what_graviex_would() {
+    let rules = ethbtc();
+    let check = |volume: &str, price: &str| rules.check(&NewOrder::limit("ethbtc", Side::Sell, dec(volume), dec(price)));
+
+    assert!(matches!(check("0.0005", "0.06"), Err(ValidationError::TooSmall { field: "volume", .. })));
+    assert!(matches!(check("0.001", "0.06"), Err(ValidationError::TooSmall { field: "total", .. })));
+    assert!(matches!(check("0.123456789", "0.06"), Err(ValidationError::TooPrecise { decimals: 9, .. })));
+    assert!(matches!(check("1", "0"), Err(ValidationError::NotPositive { field: "price", .. })));
+    // trailing zeros don't count as decimals
+    assert!(check("1.0000000000", "0.06").is_ok());
+
+    // market orders have no price to check
+    assert!(rules.check(&NewOrder::market("ethbtc", Side::Sell, dec("1"))).is_ok());
+}
+
+#[test]
+fn lots_and_suspended_markets() {
+    let rules = MarketRules { volume_lot: Some(dec("1")), ..ethbtc() };
+    assert!(rules.check(&NewOrder::limit("ethbtc", Side::Sell, dec("3"), dec("0.06"))).is_ok());
+    let error = rules.check(&NewOrder::limit("ethbtc", Side::Sell, dec("2.5"), dec("0.06"))).unwrap_err();
+    assert_eq!(error.to_string(), "ethbtc: volume 2.5 is not a multiple of the lot size 1");
+
+    let rules = MarketRules { wstatus: "off".to_string(), ..ethbtc() };
+    assert!(matches!(
+        rules.check(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.06"))),
+        Err(ValidationError::Suspended { .. })
+    ));
+}
+
+#[test]
+fn refuses_before_sending() {
+    let server = server();
+    let alice = server.client("alice", "secret").with_validator(Arc::new(Validator::new()));
+
+    let error = refusal(alice.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("0.00001"), dec("0.05"))));
+    assert_eq!(error.to_string(), "ethbtc: volume 0.00001 is below the minimum of 0.0001 eth");
+    assert_eq!(
+        refusal(alice.orders_post(&NewOrder::limit("dogebtc", Side::Sell, dec("1"), dec("0.05")))),
+        ValidationError::UnknownMarket { market: "dogebtc".to_string() }
+    );
+    assert!(matches!(
+        refusal(alice.orders_post(&NewOrder::limit("vrscbtc", Side::Sell, dec("1"), dec("0.05")))),
+        ValidationError::ApiDisabled { .. }
+    ));
+
+    // one bad leg stops the whole batch
+    let orders = MultiOrder::new("ethbtc")
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.05")))
+        .push(OrderLeg::limit(Side::Sell, dec("1"), dec("0.050000001")));
+    assert!(matches!(alice.orders_multi(&orders), Err(GraviexError::Validation(_))));
+
+    assert!(alice.orders_history(&OrdersHistoryQuery::new()).unwrap().is_empty());
+    assert!(alice.orders_post(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"))).is_ok());
+}
+
+#[test]
+fn caches_rules_per_market() {
+    let server = server();
+    let validator = Arc::new(Validator::new());
+    let alice = server.client("alice", "secret").with_validator(Arc::clone(&validator));
+
+    assert!(validator.get("ethbtc").is_none());
+    alice.validate(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"))).unwrap();
+    assert_eq!(validator.get("ethbtc").unwrap().base_min, dec("0.0001"));
+    assert!(validator.get("giobtc").is_none());
+
+    // cached rules are used as they are, even when graviex has moved on
+    validator.insert(MarketRules { base_min: dec("5"), ..validator.get("ethbtc").unwrap() });
+    assert!(alice.validate(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"))).is_err());
+    validator.clear();
+    assert!(alice.validate(&NewOrder::limit("ethbtc", Side::Sell, dec("1"), dec("0.05"))).is_ok());
+}